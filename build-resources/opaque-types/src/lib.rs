@@ -2,11 +2,13 @@
 #![allow(dead_code)]
 #![allow(deprecated)]
 use core::ffi::c_void;
-#[cfg(all(feature = "shared-memory", feature = "unstable"))]
-use std::sync::Arc;
 use std::{
-    sync::{Condvar, Mutex, MutexGuard},
-    thread::JoinHandle,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize},
+        Arc, Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+    thread::{JoinHandle, ThreadId},
+    time::Instant,
 };
 
 use zenoh::{
@@ -25,7 +27,10 @@ use zenoh::{
 #[cfg(feature = "unstable")]
 use zenoh::{
     internal::builders::close::NolocalJoinHandle,
-    matching::MatchingListener, query::Querier, sample::SourceInfo, session::EntityGlobalId,
+    matching::MatchingListener,
+    query::{Querier, Selector},
+    sample::SourceInfo,
+    session::EntityGlobalId,
 };
 #[cfg(all(feature = "shared-memory", feature = "unstable"))]
 use zenoh::{
@@ -118,12 +123,36 @@ get_opaque_type_data!(ReplyError, z_owned_reply_err_t);
 /// A loaned Zenoh reply error.
 get_opaque_type_data!(ReplyError, z_loaned_reply_err_t);
 
+pub struct PendingQuery(Arc<AtomicUsize>);
+
+pub struct CQuery {
+    _query: Query,
+    _pending: Option<Arc<PendingQuery>>,
+}
+
 /// An owned Zenoh query received by a queryable.
 ///
 /// Queries are atomically reference-counted, letting you extract them from the callback that handed them to you by cloning.
-get_opaque_type_data!(Option<Query>, z_owned_query_t);
+get_opaque_type_data!(Option<CQuery>, z_owned_query_t);
 /// A loaned Zenoh query.
-get_opaque_type_data!(Query, z_loaned_query_t);
+get_opaque_type_data!(CQuery, z_loaned_query_t);
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An owned selector: a key expression together with query parameters.
+get_opaque_type_data!(Option<Selector<'static>>, z_owned_selector_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A loaned selector.
+get_opaque_type_data!(Selector<'static>, z_loaned_selector_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An owned builder of query parameters.
+get_opaque_type_data!(Option<String>, z_owned_query_parameters_builder_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A loaned builder of query parameters.
+get_opaque_type_data!(String, z_loaned_query_parameters_builder_t);
 
 /// An owned Zenoh <a href="https://zenoh.io/docs/manual/abstractions/#queryable"> queryable </a>.
 ///
@@ -132,14 +161,20 @@ get_opaque_type_data!(Option<Queryable<()>>, z_owned_queryable_t);
 /// A loaned Zenoh queryable.
 get_opaque_type_data!(Queryable<()>, z_loaned_queryable_t);
 
+#[cfg(feature = "unstable")]
+pub struct CQuerier {
+    _querier: Querier<'static>,
+    _pending_gets: Arc<Mutex<Vec<()>>>,
+}
+
 #[cfg(feature = "unstable")]
 /// An owned Zenoh querier.
 ///
 /// Sends queries to matching queryables.
-get_opaque_type_data!(Option<Querier>, z_owned_querier_t);
+get_opaque_type_data!(Option<CQuerier>, z_owned_querier_t);
 #[cfg(feature = "unstable")]
 /// A loaned Zenoh queryable.
-get_opaque_type_data!(Querier, z_loaned_querier_t);
+get_opaque_type_data!(CQuerier, z_loaned_querier_t);
 
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
@@ -235,10 +270,16 @@ get_opaque_type_data!(Option<KeyExpr<'static>>, z_view_keyexpr_t);
 /// both for local processing and network-wise.
 get_opaque_type_data!(KeyExpr<'static>, z_loaned_keyexpr_t);
 
+pub struct CSession {
+    _session: Session,
+    #[cfg(feature = "unstable")]
+    _pending_gets: Arc<Mutex<Vec<()>>>,
+}
+
 /// An owned Zenoh session.
-get_opaque_type_data!(Option<Session>, z_owned_session_t);
+get_opaque_type_data!(Option<CSession>, z_owned_session_t);
 /// A loaned Zenoh session.
-get_opaque_type_data!(Session, z_loaned_session_t);
+get_opaque_type_data!(CSession, z_loaned_session_t);
 
 #[cfg(feature = "unstable")]
 /// An owned Close handle
@@ -262,10 +303,16 @@ get_opaque_type_data!(ZenohId, z_id_t);
 /// It consists of a time generated by a Hybrid Logical Clock (HLC) in NPT64 format and a unique zenoh identifier.
 get_opaque_type_data!(Timestamp, z_timestamp_t);
 
+pub struct CPublisher {
+    _publisher: Publisher<'static>,
+    #[cfg(feature = "unstable")]
+    _transform: RwLock<Option<(extern "C" fn(), *mut c_void)>>,
+}
+
 /// An owned Zenoh <a href="https://zenoh.io/docs/manual/abstractions/#publisher"> publisher </a>.
-get_opaque_type_data!(Option<Publisher<'static>>, z_owned_publisher_t);
+get_opaque_type_data!(Option<CPublisher>, z_owned_publisher_t);
 /// A loaned Zenoh publisher.
-get_opaque_type_data!(Publisher<'static>, z_loaned_publisher_t);
+get_opaque_type_data!(CPublisher, z_loaned_publisher_t);
 
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
@@ -275,13 +322,29 @@ get_opaque_type_data!(Publisher<'static>, z_loaned_publisher_t);
 /// Dropping the corresponding publisher, also drops matching listener.
 get_opaque_type_data!(Option<MatchingListener<()>>, z_owned_matching_listener_t);
 
+pub struct CSubscriber {
+    _subscriber: Subscriber<()>,
+    #[cfg(feature = "unstable")]
+    _callback: Option<Arc<()>>,
+}
+
 /// An owned Zenoh <a href="https://zenoh.io/docs/manual/abstractions/#subscriber"> subscriber </a>.
 ///
 /// Receives data from publication on intersecting key expressions.
 /// Destroying the subscriber cancels the subscription.
-get_opaque_type_data!(Option<Subscriber<()>>, z_owned_subscriber_t);
+get_opaque_type_data!(Option<CSubscriber>, z_owned_subscriber_t);
 /// A loaned Zenoh subscriber.
-get_opaque_type_data!(Subscriber<()>, z_loaned_subscriber_t);
+get_opaque_type_data!(CSubscriber, z_loaned_subscriber_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An owned group of Zenoh subscribers sharing the same callback, declared with `z_declare_subscriber_multi()`.
+///
+/// Destroying the group cancels all of its subscriptions.
+get_opaque_type_data!(Option<Vec<Subscriber<()>>>, z_owned_subscriber_group_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A loaned group of Zenoh subscribers.
+get_opaque_type_data!(Vec<Subscriber<()>>, z_loaned_subscriber_group_t);
 
 /// @brief A liveliness token that can be used to provide the network with information about connectivity to its
 /// declarer: when constructed, a PUT sample will be received by liveliness subscribers on intersecting key
@@ -291,6 +354,25 @@ get_opaque_type_data!(Subscriber<()>, z_loaned_subscriber_t);
 get_opaque_type_data!(Option<LivelinessToken>, z_owned_liveliness_token_t);
 get_opaque_type_data!(LivelinessToken, z_loaned_liveliness_token_t);
 
+#[cfg(feature = "unstable")]
+pub struct LivelinessTokenBatch {
+    _tokens: Arc<(Mutex<(usize, usize, Vec<LivelinessToken>)>, Condvar)>,
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An owned batch of liveliness tokens declared with `zc_liveliness_declare_token_deferred()`.
+///
+/// Dropping the batch undeclares all of its tokens.
+get_opaque_type_data!(
+    Option<LivelinessTokenBatch>,
+    zc_owned_liveliness_token_batch_t
+);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A loaned batch of liveliness tokens.
+get_opaque_type_data!(LivelinessTokenBatch, zc_loaned_liveliness_token_batch_t);
+
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// @brief An owned Zenoh publication cache.
@@ -317,12 +399,51 @@ get_opaque_type_data!(
     z_loaned_mutex_t
 );
 
+/// An owned recursive mutex.
+///
+/// Unlike `z_owned_mutex_t`, can be locked several times by the thread holding it.
+get_opaque_type_data!(
+    Option<(Mutex<(Option<ThreadId>, usize)>, Condvar)>,
+    z_owned_rmutex_t
+);
+/// A loaned recursive mutex.
+get_opaque_type_data!(
+    (Mutex<(Option<ThreadId>, usize)>, Condvar),
+    z_loaned_rmutex_t
+);
+
+/// An owned read-write lock.
+///
+/// Allows several readers or a single writer to hold the lock at the same time.
+get_opaque_type_data!(
+    Option<(
+        RwLock<()>,
+        Mutex<Vec<RwLockReadGuard<'static, ()>>>,
+        Option<RwLockWriteGuard<'static, ()>>,
+    )>,
+    z_owned_rwlock_t
+);
+/// A loaned read-write lock.
+get_opaque_type_data!(
+    (
+        RwLock<()>,
+        Mutex<Vec<RwLockReadGuard<'static, ()>>>,
+        Option<RwLockWriteGuard<'static, ()>>,
+    ),
+    z_loaned_rwlock_t
+);
+
 /// An owned conditional variable.
 ///
 /// Used in combination with `z_owned_mutex_t` to wake up thread when certain conditions are met.
-get_opaque_type_data!(Option<Condvar>, z_owned_condvar_t);
+get_opaque_type_data!(Option<(Condvar, AtomicUsize)>, z_owned_condvar_t);
 /// A loaned conditional variable.
-get_opaque_type_data!(Condvar, z_loaned_condvar_t);
+get_opaque_type_data!((Condvar, AtomicUsize), z_loaned_condvar_t);
+
+/// An owned counting semaphore.
+get_opaque_type_data!(Option<(Mutex<usize>, Condvar)>, z_owned_semaphore_t);
+/// A loaned counting semaphore.
+get_opaque_type_data!((Mutex<usize>, Condvar), z_loaned_semaphore_t);
 
 /// An owned Zenoh task.
 get_opaque_type_data!(Option<JoinHandle<()>>, z_owned_task_t);
@@ -484,13 +605,24 @@ get_opaque_type_data!(Option<CSHMLayout>, z_owned_alloc_layout_t);
 /// @brief A loaned ShmProvider's AllocLayout.
 get_opaque_type_data!(CSHMLayout, z_loaned_alloc_layout_t);
 
+pub struct PendingSamples {
+    _count: AtomicUsize,
+    _lock: Mutex<()>,
+    _emptied: Condvar,
+    #[cfg(all(target_os = "linux", feature = "unstable"))]
+    _event_fd: Option<std::os::fd::OwnedFd>,
+}
+
+pub struct SampleFifoHandler {
+    _handler: FifoChannelHandler<(Option<Instant>, Sample)>,
+    _bytes: Arc<AtomicUsize>,
+    _pending: Arc<PendingSamples>,
+}
+
 /// An owned Zenoh fifo sample handler.
-get_opaque_type_data!(
-    Option<FifoChannelHandler<Sample>>,
-    z_owned_fifo_handler_sample_t
-);
+get_opaque_type_data!(Option<SampleFifoHandler>, z_owned_fifo_handler_sample_t);
 /// An loaned Zenoh fifo sample handler.
-get_opaque_type_data!(FifoChannelHandler<Sample>, z_loaned_fifo_handler_sample_t);
+get_opaque_type_data!(SampleFifoHandler, z_loaned_fifo_handler_sample_t);
 
 /// An owned Zenoh ring sample handler.
 get_opaque_type_data!(
@@ -500,29 +632,68 @@ get_opaque_type_data!(
 /// An loaned Zenoh ring sample handler.
 get_opaque_type_data!(RingChannelHandler<Sample>, z_loaned_ring_handler_sample_t);
 
+#[cfg(feature = "unstable")]
+pub struct SampleSpscRing {
+    _buffer: *mut Option<Sample>,
+    _sequences: Box<[AtomicUsize]>,
+    _capacity: usize,
+    _head: AtomicUsize,
+    _tail: AtomicUsize,
+    _dropped: AtomicUsize,
+    _closed: AtomicBool,
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An owned Zenoh single-producer single-consumer sample handler.
+get_opaque_type_data!(Option<Arc<SampleSpscRing>>, z_owned_spsc_handler_sample_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A loaned Zenoh single-producer single-consumer sample handler.
+get_opaque_type_data!(Arc<SampleSpscRing>, z_loaned_spsc_handler_sample_t);
+
+#[cfg(feature = "unstable")]
+#[derive(Clone)]
+pub struct SampleCounter {
+    _count: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An owned counter of the samples a closure combinator did not forward as-is.
+get_opaque_type_data!(Option<SampleCounter>, zc_owned_sample_counter_t);
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A loaned counter of the samples a closure combinator did not forward as-is.
+get_opaque_type_data!(SampleCounter, zc_loaned_sample_counter_t);
+
+pub struct QueryFifoHandler {
+    _handler: FifoChannelHandler<CQuery>,
+    _pending: Arc<AtomicUsize>,
+}
+
 /// An owned Zenoh fifo query handler.
-get_opaque_type_data!(
-    Option<FifoChannelHandler<Query>>,
-    z_owned_fifo_handler_query_t
-);
+get_opaque_type_data!(Option<QueryFifoHandler>, z_owned_fifo_handler_query_t);
 /// An loaned Zenoh fifo query handler.
-get_opaque_type_data!(FifoChannelHandler<Query>, z_loaned_fifo_handler_query_t);
+get_opaque_type_data!(QueryFifoHandler, z_loaned_fifo_handler_query_t);
 
 /// An owned Zenoh ring query handler.
 get_opaque_type_data!(
-    Option<RingChannelHandler<Query>>,
+    Option<RingChannelHandler<CQuery>>,
     z_owned_ring_handler_query_t
 );
 /// An loaned Zenoh ring query handler.
-get_opaque_type_data!(RingChannelHandler<Query>, z_loaned_ring_handler_query_t);
+get_opaque_type_data!(RingChannelHandler<CQuery>, z_loaned_ring_handler_query_t);
+
+pub struct ReplyFifoHandler {
+    _handler: FifoChannelHandler<(u64, Reply)>,
+    _pending: Arc<AtomicUsize>,
+}
 
 /// An owned Zenoh fifo reply handler.
-get_opaque_type_data!(
-    Option<FifoChannelHandler<Reply>>,
-    z_owned_fifo_handler_reply_t
-);
+get_opaque_type_data!(Option<ReplyFifoHandler>, z_owned_fifo_handler_reply_t);
 /// An loaned Zenoh fifo reply handler.
-get_opaque_type_data!(FifoChannelHandler<Reply>, z_loaned_fifo_handler_reply_t);
+get_opaque_type_data!(ReplyFifoHandler, z_loaned_fifo_handler_reply_t);
 
 /// An owned Zenoh ring reply handler.
 get_opaque_type_data!(
@@ -532,6 +703,22 @@ get_opaque_type_data!(
 /// An loaned Zenoh ring reply handler.
 get_opaque_type_data!(RingChannelHandler<Reply>, z_loaned_ring_handler_reply_t);
 
+/// An owned Zenoh fifo hello handler.
+get_opaque_type_data!(
+    Option<FifoChannelHandler<Hello>>,
+    z_owned_fifo_handler_hello_t
+);
+/// An loaned Zenoh fifo hello handler.
+get_opaque_type_data!(FifoChannelHandler<Hello>, z_loaned_fifo_handler_hello_t);
+
+/// An owned Zenoh ring hello handler.
+get_opaque_type_data!(
+    Option<RingChannelHandler<Hello>>,
+    z_owned_ring_handler_hello_t
+);
+/// An loaned Zenoh ring hello handler.
+get_opaque_type_data!(RingChannelHandler<Hello>, z_loaned_ring_handler_hello_t);
+
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// @brief An owned Zenoh-allocated source info`.
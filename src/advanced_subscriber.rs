@@ -521,7 +521,7 @@ pub extern "C" fn ze_advanced_subscriber_detect_publishers(
     let builder = _advanced_subscriber_detect_publishers_inner(subscriber, callback, options);
     match builder.wait() {
         Ok(s) => {
-            liveliness_subscriber.write(Some(s));
+            liveliness_subscriber.write(Some(s.into()));
             result::Z_OK
         }
         Err(e) => {
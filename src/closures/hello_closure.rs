@@ -25,9 +25,9 @@ use crate::{
 /// A closure is a structure that contains all the elements for stateful, memory-leak-free callbacks.
 #[repr(C)]
 pub struct z_owned_closure_hello_t {
-    _context: *mut c_void,
-    _call: Option<extern "C" fn(hello: &mut z_loaned_hello_t, context: *mut c_void)>,
-    _drop: Option<extern "C" fn(context: *mut c_void)>,
+    pub _context: *mut c_void,
+    pub(crate) _call: Option<extern "C" fn(hello: &mut z_loaned_hello_t, context: *mut c_void)>,
+    pub _drop: Option<extern "C" fn(context: *mut c_void)>,
 }
 
 /// Loaned closure.
@@ -22,7 +22,7 @@ use crate::{
 };
 
 #[repr(C)]
-#[derive(PartialOrd, PartialEq)]
+#[derive(Clone, Copy, PartialOrd, PartialEq)]
 /// Severity level of Zenoh log message.
 pub enum zc_log_severity_t {
     /// The `trace` level.
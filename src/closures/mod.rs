@@ -35,6 +35,9 @@ mod sample_channel;
 pub use hello_closure::*;
 mod hello_closure;
 
+pub use hello_channel::*;
+mod hello_channel;
+
 pub use log_closure::*;
 mod log_closure;
 
@@ -47,3 +50,8 @@ mod matching_status_closure;
 pub use miss_closure::*;
 #[cfg(feature = "unstable")]
 mod miss_closure;
+
+#[cfg(feature = "unstable")]
+pub use locators_closure::*;
+#[cfg(feature = "unstable")]
+mod locators_closure;
@@ -12,13 +12,17 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::{mem::MaybeUninit, sync::Arc};
+use std::{
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use libc::c_void;
-use zenoh::{
-    handlers::{self, FifoChannelHandler, IntoHandler, RingChannelHandler},
-    query::Query,
-};
+use zenoh::handlers::{self, FifoChannelHandler, IntoHandler, RingChannelHandler};
 
 pub use crate::opaque_types::{
     z_loaned_fifo_handler_query_t, z_moved_fifo_handler_query_t, z_owned_fifo_handler_query_t,
@@ -26,10 +30,51 @@ pub use crate::opaque_types::{
 use crate::{
     result::{self, z_result_t},
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
-    z_loaned_query_t, z_owned_closure_query_t, z_owned_query_t,
+    z_loaned_query_t, z_owned_closure_query_t, z_owned_query_t, CQuery,
 };
+/// Fifo query handler keeping track of the number of queries pending in its buffer.
+pub struct QueryFifoHandler {
+    handler: FifoChannelHandler<CQuery>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl QueryFifoHandler {
+    pub(crate) fn new(capacity: usize) -> (Arc<dyn Fn(CQuery) + Send + Sync>, Self) {
+        let (cb, handler) = handlers::FifoChannel::new(capacity).into_handler();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let counter = pending.clone();
+        let cb = Arc::new(move |query: CQuery| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            cb(query)
+        });
+        (cb, QueryFifoHandler { handler, pending })
+    }
+
+    pub(crate) fn recv(&self) -> zenoh::Result<CQuery> {
+        let query = self.handler.recv()?;
+        self.pending.fetch_sub(1, Ordering::Relaxed);
+        Ok(query)
+    }
+
+    pub(crate) fn recv_timeout(&self, timeout: Duration) -> zenoh::Result<Option<CQuery>> {
+        let query = self.handler.recv_timeout(timeout)?;
+        if query.is_some() {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        }
+        Ok(query)
+    }
+
+    pub(crate) fn try_recv(&self) -> zenoh::Result<Option<CQuery>> {
+        let query = self.handler.try_recv()?;
+        if query.is_some() {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        }
+        Ok(query)
+    }
+}
+
 decl_c_type!(
-    owned(z_owned_fifo_handler_query_t, option FifoChannelHandler<Query> ),
+    owned(z_owned_fifo_handler_query_t, option QueryFifoHandler),
     loaned(z_loaned_fifo_handler_query_t),
 );
 
@@ -57,17 +102,17 @@ pub extern "C" fn z_internal_fifo_handler_query_check(
 
 extern "C" fn __z_handler_query_send(query: &mut z_loaned_query_t, context: *mut c_void) {
     unsafe {
-        let f = (context as *mut std::sync::Arc<dyn Fn(Query) + Send + Sync>)
+        let f = (context as *mut std::sync::Arc<dyn Fn(CQuery) + Send + Sync>)
             .as_mut()
             .unwrap_unchecked();
-        let owned_ref: &mut Option<Query> = std::mem::transmute(query);
+        let owned_ref: &mut Option<CQuery> = std::mem::transmute(query);
         (f)(std::mem::take(owned_ref).unwrap_unchecked());
     }
 }
 
 extern "C" fn __z_handler_query_drop(context: *mut c_void) {
     unsafe {
-        let f = Box::from_raw(context as *mut Arc<dyn Fn(Query) + Send + Sync>);
+        let f = Box::from_raw(context as *mut Arc<dyn Fn(CQuery) + Send + Sync>);
         std::mem::drop(f);
     }
 }
@@ -80,8 +125,7 @@ pub unsafe extern "C" fn z_fifo_channel_query_new(
     handler: &mut MaybeUninit<z_owned_fifo_handler_query_t>,
     capacity: usize,
 ) {
-    let fifo = handlers::FifoChannel::new(capacity);
-    let (cb, h) = fifo.into_handler();
+    let (cb, h) = QueryFifoHandler::new(capacity);
     let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
     handler.as_rust_type_mut_uninit().write(Some(h));
     callback.write(z_owned_closure_query_t {
@@ -148,13 +192,60 @@ pub extern "C" fn z_fifo_handler_query_try_recv(
     }
 }
 
+/// Returns query from the fifo buffer. If there are no more pending queries will block until next query is received,
+/// until the channel is dropped (normally when Queryable is dropped), or until the timeout expires.
+/// @param this_: The fifo handler.
+/// @param query: An uninitialized memory location where the query will be constructed.
+/// @param timeout_ms: The maximum time to wait in milliseconds.
+/// @return 0 in case of success, `Z_CHANNEL_DISCONNECTED` if channel was dropped (the query will be in the gravestone state),
+/// `Z_ETIMEOUT` if no query was received before the timeout (the query will be in the gravestone state).
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_query_recv_timeout(
+    this: &z_loaned_fifo_handler_query_t,
+    query: &mut MaybeUninit<z_owned_query_t>,
+    timeout_ms: u64,
+) -> z_result_t {
+    match this
+        .as_rust_type_ref()
+        .recv_timeout(Duration::from_millis(timeout_ms))
+    {
+        Ok(Some(q)) => {
+            query.as_rust_type_mut_uninit().write(Some(q));
+            result::Z_OK
+        }
+        Ok(None) => {
+            query.as_rust_type_mut_uninit().write(None);
+            result::Z_ETIMEOUT
+        }
+        Err(_) => {
+            query.as_rust_type_mut_uninit().write(None);
+            result::Z_CHANNEL_DISCONNECTED
+        }
+    }
+}
+
+/// Returns the number of queries pending in the fifo buffer.
+///
+/// The value is approximate when queries are concurrently received: in particular a query is accounted
+/// as soon as it is passed to the channel, even if the channel is full and the sender is still waiting for room in the buffer.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_query_len(this: &z_loaned_fifo_handler_query_t) -> usize {
+    this.as_rust_type_ref().pending.load(Ordering::Relaxed)
+}
+
+/// Returns ``true`` if there are no queries pending in the fifo buffer, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_query_is_empty(this: &z_loaned_fifo_handler_query_t) -> bool {
+    z_fifo_handler_query_len(this) == 0
+}
+
 pub use crate::opaque_types::{
     z_loaned_ring_handler_query_t, z_moved_ring_handler_query_t, z_owned_ring_handler_query_t,
 };
 decl_c_type!(
     owned(
         z_owned_ring_handler_query_t,
-        option RingChannelHandler<Query>,
+        option RingChannelHandler<CQuery>,
     ),
     loaned(z_loaned_ring_handler_query_t),
 );
@@ -13,9 +13,23 @@
 //
 
 use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
 
 use libc::c_void;
+#[cfg(feature = "unstable")]
+use zenoh::{
+    bytes::{Encoding, ZBytes},
+    key_expr::KeyExpr,
+    query::{Reply, ReplyError},
+};
 
+#[cfg(feature = "unstable")]
+use crate::{result, transmute::RustTypeRef};
 use crate::{
     transmute::{LoanedCTypeRef, OwnedCTypeRef, TakeRustType},
     z_loaned_reply_t,
@@ -172,3 +186,164 @@ pub extern "C" fn z_closure_reply(
         _drop: drop,
     });
 }
+
+#[cfg(feature = "unstable")]
+struct ReplyLatest {
+    inner: z_owned_closure_reply_t,
+    latest: Mutex<HashMap<KeyExpr<'static>, Reply>>,
+}
+
+#[cfg(feature = "unstable")]
+impl ReplyLatest {
+    fn call(&self, reply: &mut z_loaned_reply_t) {
+        let sample = match reply.as_rust_type_ref().result() {
+            Ok(sample) => sample,
+            Err(_) => {
+                z_closure_reply_call(z_closure_reply_loan(&self.inner), reply);
+                return;
+            }
+        };
+        let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+        let is_newer = match latest.get(sample.key_expr()) {
+            Some(current) => match current.result() {
+                Ok(current) => sample.timestamp() > current.timestamp(),
+                Err(_) => true,
+            },
+            None => true,
+        };
+        if is_newer {
+            latest.insert(sample.key_expr().clone(), reply.as_rust_type_ref().clone());
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for ReplyLatest {
+    fn drop(&mut self) {
+        let latest = std::mem::take(self.latest.get_mut().unwrap_or_else(|e| e.into_inner()));
+        for (_, mut reply) in latest {
+            z_closure_reply_call(
+                z_closure_reply_loan(&self.inner),
+                reply.as_loaned_c_type_mut(),
+            );
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure performing client-side latest consolidation of the replies before forwarding them to `inner`.
+///
+/// Successful replies are kept per key expression, only the one with the newest timestamp being retained (replies without
+/// timestamp are considered older than any timestamped one, and duplicates are suppressed). The retained replies are
+/// forwarded to `inner` once the closure is dropped, i.e. once all the replies of the query were received.
+/// Error replies are forwarded immediately.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param inner: the closure to forward consolidated replies to. Will be consumed.
+#[no_mangle]
+pub extern "C" fn z_closure_reply_latest(
+    this: &mut MaybeUninit<z_owned_closure_reply_t>,
+    inner: &mut z_moved_closure_reply_t,
+) {
+    let latest = ReplyLatest {
+        inner: inner.take_rust_type(),
+        latest: Mutex::new(HashMap::new()),
+    };
+    this.write((move |reply: &mut z_loaned_reply_t| latest.call(reply)).into());
+}
+
+#[cfg(feature = "unstable")]
+struct ReplyDeadlineState {
+    inner: Mutex<Option<z_owned_closure_reply_t>>,
+    dropped: Condvar,
+}
+
+#[cfg(feature = "unstable")]
+struct ReplyDeadline(Arc<ReplyDeadlineState>);
+
+#[cfg(feature = "unstable")]
+impl ReplyDeadline {
+    fn call(&self, reply: &mut z_loaned_reply_t) {
+        let inner = self.0.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(inner) = inner.as_ref() {
+            z_closure_reply_call(z_closure_reply_loan(inner), reply);
+        }
+    }
+
+    fn expire(state: Arc<ReplyDeadlineState>, deadline: Instant) {
+        let mut inner = state.inner.lock().unwrap_or_else(|e| e.into_inner());
+        while inner.is_some() {
+            let now = Instant::now();
+            if now >= deadline {
+                let Some(expired) = inner.take() else {
+                    return;
+                };
+                std::mem::drop(inner);
+                let err = ReplyError::new(ZBytes::from("timed out"), Encoding::ZENOH_STRING);
+                let mut reply = Reply::new(Err(err), None);
+                z_closure_reply_call(z_closure_reply_loan(&expired), reply.as_loaned_c_type_mut());
+                return;
+            }
+            inner = state
+                .dropped
+                .wait_timeout(inner, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for ReplyDeadline {
+    fn drop(&mut self) {
+        let inner = self
+            .0
+            .inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        self.0.dropped.notify_all();
+        std::mem::drop(inner);
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure forwarding replies to `inner` until a deadline, independently of the query timeout.
+///
+/// If the closure is still alive `deadline_ms` milliseconds after its construction, an error reply with "timed out" payload
+/// (and `zenoh/string` encoding) is delivered to `inner`, which is then dropped immediately. Replies received afterwards are discarded.
+/// If the closure is dropped before the deadline (i.e. the query completed, possibly because of its own timeout), `inner`
+/// is dropped as well and no error reply is delivered. Hence the effective timeout is the shortest of the query timeout and `deadline_ms`.
+///
+/// The deadline is tracked by a separate thread, from which the error reply is delivered.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param inner: the closure to forward replies to. Will be consumed.
+/// @param deadline_ms: the deadline in milliseconds, counted from the construction of the closure.
+/// @return 0 in case of success, negative error code otherwise (in this case `this_` is set to its gravestone state and `inner` is dropped).
+#[no_mangle]
+pub extern "C" fn z_closure_reply_deadline(
+    this: &mut MaybeUninit<z_owned_closure_reply_t>,
+    inner: &mut z_moved_closure_reply_t,
+    deadline_ms: u64,
+) -> result::z_result_t {
+    let deadline = Instant::now() + Duration::from_millis(deadline_ms);
+    let state = Arc::new(ReplyDeadlineState {
+        inner: Mutex::new(Some(inner.take_rust_type())),
+        dropped: Condvar::new(),
+    });
+    let timer_state = state.clone();
+    if let Err(e) =
+        std::thread::Builder::new().spawn(move || ReplyDeadline::expire(timer_state, deadline))
+    {
+        tracing::error!("Failed to spawn reply deadline timer: {}", e);
+        std::mem::drop(ReplyDeadline(state));
+        this.write(z_owned_closure_reply_t::default());
+        return result::Z_EGENERIC;
+    }
+    let deadline = ReplyDeadline(state);
+    this.write((move |reply: &mut z_loaned_reply_t| deadline.call(reply)).into());
+    result::Z_OK
+}
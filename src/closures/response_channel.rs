@@ -12,7 +12,13 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::{mem::MaybeUninit, sync::Arc};
+use std::{
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use libc::c_void;
 use zenoh::{
@@ -28,8 +34,56 @@ use crate::{
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_loaned_reply_t, z_owned_closure_reply_t, z_owned_reply_t,
 };
+#[cfg(feature = "unstable")]
+use crate::{z_owned_reply_err_t, z_owned_sample_t};
+/// Fifo reply handler keeping track of the number of replies pending in its buffer.
+///
+/// Each reply is buffered together with its arrival index, assigned when it is pushed into the channel.
+pub struct ReplyFifoHandler {
+    handler: FifoChannelHandler<(u64, Reply)>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl ReplyFifoHandler {
+    fn new(
+        capacity: usize,
+        on_push: Option<Box<dyn Fn(usize) + Send + Sync>>,
+    ) -> (Arc<dyn Fn(Reply) + Send + Sync>, Self) {
+        let (cb, handler) = handlers::FifoChannel::new(capacity).into_handler();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let counter = pending.clone();
+        let next_index = AtomicU64::new(0);
+        let cb = Arc::new(move |reply: Reply| {
+            let pending = counter.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(on_push) = &on_push {
+                on_push(pending);
+            }
+            cb((next_index.fetch_add(1, Ordering::Relaxed), reply))
+        });
+        (cb, ReplyFifoHandler { handler, pending })
+    }
+
+    pub(crate) fn recv(&self) -> zenoh::Result<Reply> {
+        self.recv_indexed().map(|(_, reply)| reply)
+    }
+
+    pub(crate) fn recv_indexed(&self) -> zenoh::Result<(u64, Reply)> {
+        let reply = self.handler.recv()?;
+        self.pending.fetch_sub(1, Ordering::Relaxed);
+        Ok(reply)
+    }
+
+    pub(crate) fn try_recv(&self) -> zenoh::Result<Option<Reply>> {
+        let reply = self.handler.try_recv()?;
+        if reply.is_some() {
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+        }
+        Ok(reply.map(|(_, reply)| reply))
+    }
+}
+
 decl_c_type!(
-    owned(z_owned_fifo_handler_reply_t, option FifoChannelHandler<Reply>),
+    owned(z_owned_fifo_handler_reply_t, option ReplyFifoHandler),
     loaned(z_loaned_fifo_handler_reply_t),
 );
 
@@ -73,6 +127,9 @@ extern "C" fn __z_handler_reply_drop(context: *mut c_void) {
 }
 
 /// Constructs send and recieve ends of the fifo channel
+///
+/// The channel is always bounded by `capacity`, so it never grows without limit: once it is full, the delivery of further
+/// replies blocks until the handler consumes some of them. To drop the oldest replies instead of blocking, use `z_ring_channel_reply_new()`.
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn z_fifo_channel_reply_new(
@@ -80,8 +137,85 @@ pub unsafe extern "C" fn z_fifo_channel_reply_new(
     handler: &mut MaybeUninit<z_owned_fifo_handler_reply_t>,
     capacity: usize,
 ) {
-    let fifo = handlers::FifoChannel::new(capacity);
-    let (cb, h) = fifo.into_handler();
+    let (cb, h) = ReplyFifoHandler::new(capacity, None);
+    let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
+    handler.as_rust_type_mut_uninit().write(Some(h));
+    callback.write(z_owned_closure_reply_t {
+        _call: Some(__z_handler_reply_send),
+        _context: cb_ptr,
+        _drop: Some(__z_handler_reply_drop),
+    });
+}
+
+#[cfg(feature = "unstable")]
+struct HighWaterNotifier {
+    threshold: usize,
+    call: extern "C" fn(pending: usize, context: *mut c_void),
+    drop: Option<extern "C" fn(context: *mut c_void)>,
+    context: *mut c_void,
+}
+
+#[cfg(feature = "unstable")]
+unsafe impl Send for HighWaterNotifier {}
+#[cfg(feature = "unstable")]
+unsafe impl Sync for HighWaterNotifier {}
+
+#[cfg(feature = "unstable")]
+impl HighWaterNotifier {
+    fn on_push(&self, pending: usize) {
+        // Pending count changes one at a time, so it is equal to the threshold exactly once per upward crossing.
+        if pending == self.threshold {
+            (self.call)(pending, self.context);
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for HighWaterNotifier {
+    fn drop(&mut self) {
+        if let Some(drop) = self.drop {
+            drop(self.context)
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs send and recieve ends of the fifo channel, notifying when the number of pending replies reaches a high-water mark.
+///
+/// `on_high_water` is called by the sending side each time the number of replies pending in the channel reaches `threshold`
+/// from below, i.e. before delivering the reply that makes it reach `threshold`. This allows to detect that the consumer is falling
+/// behind before the delivery of replies starts blocking (which happens when `capacity` replies are pending).
+/// It is called from zenoh threads, so it should not block.
+///
+/// @param callback: An uninitialized memory location where the send end of the channel will be constructed.
+/// @param handler: An uninitialized memory location where the receive end of the channel will be constructed.
+/// @param capacity: The capacity of the channel.
+/// @param threshold: The number of pending replies triggering the notification, normally lower than `capacity`.
+/// @param on_high_water: The function called with the number of pending replies when `threshold` is reached.
+/// @param drop: An optional function to be called once when the send end of the channel is dropped, to release `context`.
+/// @param context: The notification context.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_fifo_channel_reply_new_with_high_water(
+    callback: &mut MaybeUninit<z_owned_closure_reply_t>,
+    handler: &mut MaybeUninit<z_owned_fifo_handler_reply_t>,
+    capacity: usize,
+    threshold: usize,
+    on_high_water: extern "C" fn(pending: usize, context: *mut c_void),
+    drop: Option<extern "C" fn(context: *mut c_void)>,
+    context: *mut c_void,
+) {
+    let notifier = HighWaterNotifier {
+        threshold,
+        call: on_high_water,
+        drop,
+        context,
+    };
+    let (cb, h) = ReplyFifoHandler::new(
+        capacity,
+        Some(Box::new(move |pending| notifier.on_push(pending))),
+    );
     let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
     handler.as_rust_type_mut_uninit().write(Some(h));
     callback.write(z_owned_closure_reply_t {
@@ -147,6 +281,101 @@ pub extern "C" fn z_fifo_handler_reply_try_recv(
     }
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns reply from the fifo buffer together with its arrival index. If there are no more pending replies will block
+/// until next reply is received, or until the channel is dropped (normally when all replies are received).
+///
+/// The arrival index is assigned when the reply is pushed into the channel: it starts at 0 and is incremented by one for each
+/// reply, so it reflects the order in which replies were received by the channel, regardless of the order they are read in.
+///
+/// @param this_: The fifo handler.
+/// @param reply: An uninitialized memory location where the reply will be constructed.
+/// @param index: Will contain the arrival index of the reply in case of success.
+/// @return 0 in case of success, `Z_CHANNEL_DISCONNECTED` if channel was dropped (the reply will be in the gravestone state).
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_reply_recv_indexed(
+    this: &z_loaned_fifo_handler_reply_t,
+    reply: &mut MaybeUninit<z_owned_reply_t>,
+    index: &mut u64,
+) -> z_result_t {
+    match this.as_rust_type_ref().recv_indexed() {
+        Ok((i, q)) => {
+            reply.as_rust_type_mut_uninit().write(Some(q));
+            *index = i;
+            result::Z_OK
+        }
+        Err(_) => {
+            reply.as_rust_type_mut_uninit().write(None);
+            result::Z_CHANNEL_DISCONNECTED
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Pops up to `max` pending replies from the fifo buffer without blocking, separating successful replies from errors.
+///
+/// The samples of the successful replies are written in order into `out_samples` and the errors into `out_errs`, so that
+/// both kinds can be processed separately. Both arrays must be able to hold `max` elements, since all the popped replies may
+/// be of the same kind.
+///
+/// @param this_: The fifo handler.
+/// @param out_samples: Pointer to an array of `max` uninitialized samples, the first `*out_ok_count` ones of which will be constructed.
+/// @param out_errs: Pointer to an array of `max` uninitialized reply errors, the first `*out_err_count` ones of which will be constructed.
+/// @param max: Capacity of `out_samples` and `out_errs` arrays.
+/// @param out_ok_count: Will contain the number of samples written into `out_samples`.
+/// @param out_err_count: Will contain the number of errors written into `out_errs`.
+/// @return 0 if at least one reply was popped, `Z_CHANNEL_NODATA` if the buffer is empty but the channel is still alive,
+/// `Z_CHANNEL_DISCONNECTED` if the buffer is empty and the channel was dropped, `Z_EINVAL` if an output array is NULL.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_fifo_handler_reply_drain_split(
+    this: &z_loaned_fifo_handler_reply_t,
+    out_samples: *mut MaybeUninit<z_owned_sample_t>,
+    out_errs: *mut MaybeUninit<z_owned_reply_err_t>,
+    max: usize,
+    out_ok_count: &mut usize,
+    out_err_count: &mut usize,
+) -> z_result_t {
+    *out_ok_count = 0;
+    *out_err_count = 0;
+    if out_samples.is_null() || out_errs.is_null() {
+        return result::Z_EINVAL;
+    }
+    let this = this.as_rust_type_ref();
+    let mut res = result::Z_CHANNEL_NODATA;
+    while *out_ok_count + *out_err_count < max {
+        match this.try_recv() {
+            Ok(Some(reply)) => {
+                match reply.into_result() {
+                    Ok(sample) => {
+                        (*out_samples.add(*out_ok_count))
+                            .as_rust_type_mut_uninit()
+                            .write(Some(sample));
+                        *out_ok_count += 1;
+                    }
+                    Err(err) => {
+                        (*out_errs.add(*out_err_count))
+                            .as_rust_type_mut_uninit()
+                            .write(err);
+                        *out_err_count += 1;
+                    }
+                }
+                res = result::Z_OK;
+            }
+            Ok(None) => break,
+            Err(_) => {
+                if res != result::Z_OK {
+                    res = result::Z_CHANNEL_DISCONNECTED;
+                }
+                break;
+            }
+        }
+    }
+    res
+}
+
 pub use crate::opaque_types::{
     z_loaned_ring_handler_reply_t, z_moved_ring_handler_reply_t, z_owned_ring_handler_reply_t,
 };
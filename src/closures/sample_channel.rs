@@ -12,9 +12,26 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::{mem::MaybeUninit, sync::Arc};
+#[cfg(all(target_os = "linux", feature = "unstable"))]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+#[cfg(feature = "unstable")]
+use std::{collections::HashSet, sync::atomic::AtomicBool};
+use std::{
+    mem::MaybeUninit,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use libc::c_void;
+#[cfg(feature = "unstable")]
+use zenoh::{
+    bytes::ZBytes,
+    sample::{Locality, SampleBuilder, SampleKind},
+    Wait,
+};
 use zenoh::{
     handlers::{self, FifoChannelHandler, IntoHandler, RingChannelHandler},
     sample::Sample,
@@ -28,8 +45,180 @@ use crate::{
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_loaned_sample_t, z_owned_closure_sample_t, z_owned_sample_t,
 };
+#[cfg(feature = "unstable")]
+use crate::{
+    z_closure_sample_call, z_closure_sample_loan, z_loaned_keyexpr_t, z_loaned_session_t,
+    z_moved_closure_sample_t, z_owned_subscriber_t,
+};
+/// Number of samples pending in a fifo buffer, with a condition variable notified when it drops to zero.
+///
+/// On Linux, an eventfd in semaphore mode is additionally written once per enqueued sample and read once per received sample,
+/// so that it is readable as long as samples are pending.
+struct PendingSamples {
+    count: AtomicUsize,
+    lock: Mutex<()>,
+    emptied: Condvar,
+    #[cfg(all(target_os = "linux", feature = "unstable"))]
+    event_fd: Option<OwnedFd>,
+}
+
+#[cfg(all(target_os = "linux", feature = "unstable"))]
+impl PendingSamples {
+    fn new_event_fd() -> Option<OwnedFd> {
+        let fd = unsafe {
+            libc::eventfd(
+                0,
+                libc::EFD_CLOEXEC | libc::EFD_NONBLOCK | libc::EFD_SEMAPHORE,
+            )
+        };
+        if fd < 0 {
+            tracing::warn!(
+                "Failed to create the eventfd of the fifo handler: {}",
+                std::io::Error::last_os_error()
+            );
+            return None;
+        }
+        Some(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    fn signal(&self) {
+        if let Some(fd) = &self.event_fd {
+            let one: u64 = 1;
+            unsafe {
+                libc::write(
+                    fd.as_raw_fd(),
+                    &one as *const u64 as *const c_void,
+                    std::mem::size_of::<u64>(),
+                )
+            };
+        }
+    }
+
+    fn unsignal(&self) {
+        if let Some(fd) = &self.event_fd {
+            let mut value: u64 = 0;
+            unsafe {
+                libc::read(
+                    fd.as_raw_fd(),
+                    &mut value as *mut u64 as *mut c_void,
+                    std::mem::size_of::<u64>(),
+                )
+            };
+        }
+    }
+}
+
+/// Signals the eventfd of the fifo handler once the send end of the channel is dropped, so that disconnection can be polled too.
+#[cfg(all(target_os = "linux", feature = "unstable"))]
+struct DisconnectSignal(Arc<PendingSamples>);
+
+#[cfg(all(target_os = "linux", feature = "unstable"))]
+impl Drop for DisconnectSignal {
+    fn drop(&mut self) {
+        self.0.signal();
+    }
+}
+
+/// Fifo sample handler keeping track of the samples and payload bytes pending in its buffer.
+///
+/// Each buffered sample is paired with the instant it was enqueued at, if the handler records queue latency.
+pub struct SampleFifoHandler {
+    handler: FifoChannelHandler<(Option<Instant>, Sample)>,
+    bytes: Arc<AtomicUsize>,
+    pending: Arc<PendingSamples>,
+}
+
+impl SampleFifoHandler {
+    fn new(capacity: usize, record_latency: bool) -> (Arc<dyn Fn(Sample) + Send + Sync>, Self) {
+        let (cb, handler) = handlers::FifoChannel::new(capacity).into_handler();
+        let bytes = Arc::new(AtomicUsize::new(0));
+        let pending = Arc::new(PendingSamples {
+            count: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            emptied: Condvar::new(),
+            #[cfg(all(target_os = "linux", feature = "unstable"))]
+            event_fd: PendingSamples::new_event_fd(),
+        });
+        let counter = bytes.clone();
+        let pending_counter = pending.clone();
+        #[cfg(all(target_os = "linux", feature = "unstable"))]
+        let disconnect = DisconnectSignal(pending.clone());
+        let cb = Arc::new(move |sample: Sample| {
+            counter.fetch_add(sample.payload().len(), Ordering::Relaxed);
+            pending_counter.count.fetch_add(1, Ordering::AcqRel);
+            // Signal before pushing, so that the eventfd can not be read for this sample before it is written.
+            #[cfg(all(target_os = "linux", feature = "unstable"))]
+            disconnect.0.signal();
+            cb((record_latency.then(Instant::now), sample))
+        });
+        (
+            cb,
+            SampleFifoHandler {
+                handler,
+                bytes,
+                pending,
+            },
+        )
+    }
+
+    fn on_received(&self, sample: &Sample) {
+        self.bytes
+            .fetch_sub(sample.payload().len(), Ordering::Relaxed);
+        #[cfg(all(target_os = "linux", feature = "unstable"))]
+        self.pending.unsignal();
+        if self.pending.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Take the lock so that the notification can not be missed by a waiter between its check and its wait.
+            let _guard = self.pending.lock.lock().unwrap_or_else(|e| e.into_inner());
+            self.pending.emptied.notify_all();
+        }
+    }
+
+    #[cfg(feature = "unstable")]
+    fn wait_empty(&self, timeout: Duration) -> bool {
+        let guard = self.pending.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let (_guard, res) = self
+            .pending
+            .emptied
+            .wait_timeout_while(guard, timeout, |_| {
+                self.pending.count.load(Ordering::Acquire) != 0
+            })
+            .unwrap_or_else(|e| e.into_inner());
+        !res.timed_out()
+    }
+
+    /// Receives a sample along with the instant it was enqueued at, if recorded.
+    fn recv_stamped(&self) -> zenoh::Result<(Option<Instant>, Sample)> {
+        let (enqueued, sample) = self.handler.recv()?;
+        self.on_received(&sample);
+        Ok((enqueued, sample))
+    }
+
+    pub(crate) fn recv(&self) -> zenoh::Result<Sample> {
+        self.recv_stamped().map(|(_, sample)| sample)
+    }
+
+    pub(crate) fn recv_timeout(&self, timeout: Duration) -> zenoh::Result<Option<Sample>> {
+        let sample = self
+            .handler
+            .recv_timeout(timeout)?
+            .map(|(_, sample)| sample);
+        if let Some(sample) = &sample {
+            self.on_received(sample);
+        }
+        Ok(sample)
+    }
+
+    pub(crate) fn try_recv(&self) -> zenoh::Result<Option<Sample>> {
+        let sample = self.handler.try_recv()?.map(|(_, sample)| sample);
+        if let Some(sample) = &sample {
+            self.on_received(sample);
+        }
+        Ok(sample)
+    }
+}
+
 decl_c_type!(
-    owned(z_owned_fifo_handler_sample_t, option FifoChannelHandler<Sample>),
+    owned(z_owned_fifo_handler_sample_t, option SampleFifoHandler),
     loaned(z_loaned_fifo_handler_sample_t),
 );
 
@@ -80,8 +269,7 @@ pub unsafe extern "C" fn z_fifo_channel_sample_new(
     handler: &mut MaybeUninit<z_owned_fifo_handler_sample_t>,
     capacity: usize,
 ) {
-    let fifo = handlers::FifoChannel::new(capacity);
-    let (cb, h) = fifo.into_handler();
+    let (cb, h) = SampleFifoHandler::new(capacity, false);
     let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
     handler.as_rust_type_mut_uninit().write(Some(h));
     callback.write(z_owned_closure_sample_t {
@@ -91,6 +279,132 @@ pub unsafe extern "C" fn z_fifo_channel_sample_new(
     });
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs send and recieve ends of the fifo channel, which additionally records the instant each sample is enqueued at.
+///
+/// The time each sample spent in the buffer can then be obtained with `z_fifo_handler_sample_recv_with_latency()`.
+///
+/// @param callback: An uninitialized memory location where the send end of the channel will be constructed.
+/// @param handler: An uninitialized memory location where the receive end of the channel will be constructed.
+/// @param capacity: The capacity of the channel.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_fifo_channel_sample_new_with_latency(
+    callback: &mut MaybeUninit<z_owned_closure_sample_t>,
+    handler: &mut MaybeUninit<z_owned_fifo_handler_sample_t>,
+    capacity: usize,
+) {
+    let (cb, h) = SampleFifoHandler::new(capacity, true);
+    let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
+    handler.as_rust_type_mut_uninit().write(Some(h));
+    callback.write(z_owned_closure_sample_t {
+        _call: Some(__z_handler_sample_send),
+        _context: cb_ptr,
+        _drop: Some(__z_handler_sample_drop),
+    });
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs send and recieve ends of the fifo channel, which additionally receives a marker sample
+/// each time the session reconnects after having lost all of its connections.
+///
+/// The marker is a PUT sample with an empty payload on `marker_key_expr`. It is inserted into the same channel as the regular samples,
+/// so the consumer knows at which point of the stream a discontinuity may have occurred. No marker is inserted for the first
+/// connection of the session, nor when a new connection is established while another one is still up (e.g. a new peer joining).
+/// Connectivity changes are observed through the session admin space, so it should be enabled in the session configuration.
+///
+/// @param callback: An uninitialized memory location where the send end of the channel will be constructed.
+/// @param handler: An uninitialized memory location where the receive end of the channel will be constructed.
+/// @param capacity: The capacity of the channel.
+/// @param session: The session whose connectivity should be monitored.
+/// @param marker_key_expr: The key expression of the marker samples.
+/// @param listener: An uninitialized memory location where the connectivity listener will be constructed.
+/// Markers are no longer inserted once it is dropped.
+/// @return 0 in case of success, negative error code otherwise (in this case all output arguments will be in their gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_fifo_channel_sample_new_with_reconnect_marker(
+    callback: &mut MaybeUninit<z_owned_closure_sample_t>,
+    handler: &mut MaybeUninit<z_owned_fifo_handler_sample_t>,
+    capacity: usize,
+    session: &z_loaned_session_t,
+    marker_key_expr: &z_loaned_keyexpr_t,
+    listener: &mut MaybeUninit<z_owned_subscriber_t>,
+) -> z_result_t {
+    let (cb, h) = SampleFifoHandler::new(capacity, false);
+    let sender = Arc::downgrade(&cb);
+    let marker = marker_key_expr.as_rust_type_ref().clone();
+    let session = session.as_rust_type_ref();
+    // The ids of the peers and routers the session has a transport with, and whether it ever had one.
+    let transports = Arc::new(Mutex::new((HashSet::<String>::new(), false)));
+    let listener_transports = transports.clone();
+    let res = session
+        .declare_subscriber(format!("@/{}/session/transport/unicast/*", session.zid()))
+        .allowed_origin(Locality::SessionLocal)
+        .callback(move |s| {
+            let Some(peer) = s.key_expr().as_str().rsplit('/').next() else {
+                return;
+            };
+            let mut transports = listener_transports
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let (open, was_connected) = &mut *transports;
+            match s.kind() {
+                SampleKind::Put => {
+                    let reconnected = open.is_empty() && *was_connected;
+                    open.insert(peer.to_string());
+                    *was_connected = true;
+                    if !reconnected {
+                        return;
+                    }
+                }
+                SampleKind::Delete => {
+                    open.remove(peer);
+                    return;
+                }
+            }
+            drop(transports);
+            if let Some(send) = sender.upgrade() {
+                send(SampleBuilder::put(marker.clone(), ZBytes::new()).into());
+            }
+        })
+        .wait();
+    if res.is_ok() {
+        // Transports established before the listener was declared are not reported by it.
+        let info = session.info();
+        let established = info
+            .routers_zid()
+            .wait()
+            .chain(info.peers_zid().wait())
+            .map(|zid| zid.to_string());
+        let mut transports = transports.lock().unwrap_or_else(|e| e.into_inner());
+        transports.0.extend(established);
+        transports.1 |= !transports.0.is_empty();
+    }
+    match res {
+        Ok(sub) => {
+            listener.as_rust_type_mut_uninit().write(Some(sub.into()));
+            let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
+            handler.as_rust_type_mut_uninit().write(Some(h));
+            callback.write(z_owned_closure_sample_t {
+                _call: Some(__z_handler_sample_send),
+                _context: cb_ptr,
+                _drop: Some(__z_handler_sample_drop),
+            });
+            result::Z_OK
+        }
+        Err(e) => {
+            tracing::error!("Failed to declare connectivity listener: {}", e);
+            listener.as_rust_type_mut_uninit().write(None);
+            handler.as_rust_type_mut_uninit().write(None);
+            callback.write(z_owned_closure_sample_t::default());
+            result::Z_EGENERIC
+        }
+    }
+}
+
 /// Borrows handler.
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
@@ -123,6 +437,38 @@ pub extern "C" fn z_fifo_handler_sample_recv(
     }
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns sample from the fifo buffer along with the time it waited in the buffer. Blocks like `z_fifo_handler_sample_recv()`.
+///
+/// The waiting time is measured from the moment the sample was passed to the send end of the channel, so it includes the time
+/// the sender waited for room in a full buffer. It is only recorded by channels constructed with `zc_fifo_channel_sample_new_with_latency()`,
+/// for other channels it is reported as 0.
+///
+/// @param this_: The fifo handler.
+/// @param sample: An uninitialized memory location where the sample will be constructed.
+/// @param out_queue_latency_us: Set to the time the sample waited in the buffer, in microseconds.
+/// @return 0 in case of success, `Z_CHANNEL_DISCONNECTED` if channel was dropped (the sample will be in the gravestone state).
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_recv_with_latency(
+    this: &z_loaned_fifo_handler_sample_t,
+    sample: &mut MaybeUninit<z_owned_sample_t>,
+    out_queue_latency_us: &mut u64,
+) -> z_result_t {
+    match this.as_rust_type_ref().recv_stamped() {
+        Ok((enqueued, q)) => {
+            *out_queue_latency_us = enqueued.map_or(0, |t| t.elapsed().as_micros() as u64);
+            sample.as_rust_type_mut_uninit().write(Some(q));
+            result::Z_OK
+        }
+        Err(_) => {
+            *out_queue_latency_us = 0;
+            sample.as_rust_type_mut_uninit().write(None);
+            result::Z_CHANNEL_DISCONNECTED
+        }
+    }
+}
+
 /// Returns sample from the fifo buffer.
 /// If there are no more pending replies will return immediately (with sample set to its gravestone state).
 /// @return 0 in case of success, `Z_CHANNEL_DISCONNECTED` if channel was dropped (the sample will be in the gravestone state),
@@ -148,6 +494,172 @@ pub extern "C" fn z_fifo_handler_sample_try_recv(
     }
 }
 
+/// Returns sample from the fifo buffer. If there are no more pending samples will block until next sample is received,
+/// until the channel is dropped, or until the timeout expires.
+/// @param this_: The fifo handler.
+/// @param sample: An uninitialized memory location where the sample will be constructed.
+/// @param timeout_ms: The maximum time to wait in milliseconds.
+/// @return 0 in case of success, `Z_CHANNEL_DISCONNECTED` if channel was dropped (the sample will be in the gravestone state),
+/// `Z_ETIMEOUT` if no sample was received before the timeout (the sample will be in the gravestone state).
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_recv_timeout(
+    this: &z_loaned_fifo_handler_sample_t,
+    sample: &mut MaybeUninit<z_owned_sample_t>,
+    timeout_ms: u64,
+) -> z_result_t {
+    match this
+        .as_rust_type_ref()
+        .recv_timeout(Duration::from_millis(timeout_ms))
+    {
+        Ok(Some(q)) => {
+            sample.as_rust_type_mut_uninit().write(Some(q));
+            result::Z_OK
+        }
+        Ok(None) => {
+            sample.as_rust_type_mut_uninit().write(None);
+            result::Z_ETIMEOUT
+        }
+        Err(_) => {
+            sample.as_rust_type_mut_uninit().write(None);
+            result::Z_CHANNEL_DISCONNECTED
+        }
+    }
+}
+
+/// Returns the total size in bytes of the payloads of the samples pending in the fifo buffer.
+///
+/// The value is approximate when samples are concurrently received or popped: in particular a sample is accounted
+/// as soon as it is passed to the channel, even if the channel is full and the sender is still waiting for room in the buffer.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_bytes(this: &z_loaned_fifo_handler_sample_t) -> usize {
+    this.as_rust_type_ref().bytes.load(Ordering::Relaxed)
+}
+
+#[cfg(all(target_os = "linux", feature = "unstable"))]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns a file descriptor which is readable as long as samples are pending in the fifo buffer,
+/// and once the send end of the channel is dropped.
+///
+/// This allows to wait for samples with `poll()`, `select()` or `epoll()` along with other file descriptors,
+/// and to then receive them with `z_fifo_handler_sample_try_recv()`. The readiness may be spurious, in which case
+/// `z_fifo_handler_sample_try_recv()` returns `Z_CHANNEL_NODATA`.
+/// The file descriptor is owned by the handler: it should not be read from or closed, and is only valid until the handler is dropped.
+///
+/// @param this_: The fifo handler.
+/// @return The file descriptor, or -1 if it could not be created.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_fd(this: &z_loaned_fifo_handler_sample_t) -> libc::c_int {
+    this.as_rust_type_ref()
+        .pending
+        .event_fd
+        .as_ref()
+        .map_or(-1, |fd| fd.as_raw_fd())
+}
+
+/// Returns the number of samples pending in the fifo buffer.
+///
+/// The value is approximate when samples are concurrently received or popped, in the same way as for `z_fifo_handler_sample_bytes()`.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_len(this: &z_loaned_fifo_handler_sample_t) -> usize {
+    this.as_rust_type_ref()
+        .pending
+        .count
+        .load(Ordering::Acquire)
+}
+
+/// Returns ``true`` if there are no samples pending in the fifo buffer, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_is_empty(this: &z_loaned_fifo_handler_sample_t) -> bool {
+    z_fifo_handler_sample_len(this) == 0
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Blocks until all the samples pending in the fifo buffer have been received, or until the timeout expires.
+///
+/// This is intended to synchronize with the consumer of the handler, e.g. in tests, rather than sleeping for an arbitrary duration.
+/// Samples pushed while waiting have to be received as well for this function to return successfully.
+///
+/// @param this_: The fifo handler.
+/// @param timeout_ms: The maximum time to wait in milliseconds.
+/// @return 0 if the buffer is empty, `Z_ETIMEOUT` if it is still not empty after the timeout.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_wait_empty(
+    this: &z_loaned_fifo_handler_sample_t,
+    timeout_ms: u64,
+) -> z_result_t {
+    if this
+        .as_rust_type_ref()
+        .wait_empty(Duration::from_millis(timeout_ms))
+    {
+        result::Z_OK
+    } else {
+        result::Z_ETIMEOUT
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Drops the handler, passing each sample still pending in its buffer to `drain` beforehand.
+///
+/// Only the samples already buffered at the time of the call are drained, the ones received afterwards are discarded.
+/// Both the handler and the `drain` closure are consumed.
+///
+/// @param this_: The handler to drop.
+/// @param drain: The closure to call on each pending sample.
+#[no_mangle]
+pub extern "C" fn z_fifo_handler_sample_drain_drop(
+    this: &mut z_moved_fifo_handler_sample_t,
+    drain: &mut z_moved_closure_sample_t,
+) {
+    let drain = drain.take_rust_type();
+    let Some(handler) = this.take_rust_type() else {
+        return;
+    };
+    while let Ok(Some(mut sample)) = handler.try_recv() {
+        z_closure_sample_call(z_closure_sample_loan(&drain), sample.as_loaned_c_type_mut());
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Pops pending samples from the fifo buffer without blocking, within a time budget.
+///
+/// Samples are popped until either `max` samples were received, the buffer is empty (or the channel was dropped),
+/// or `budget_ms` milliseconds have elapsed since the call. At least one sample is popped if available, even with a
+/// zero budget.
+///
+/// @param this_: The fifo handler.
+/// @param out: Pointer to an array of `max` uninitialized samples, the first ones of which will be constructed.
+/// @param max: Capacity of `out` array.
+/// @param budget_ms: Time budget in milliseconds.
+/// @return The number of samples written into `out`.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_fifo_handler_sample_drain_until(
+    this: &z_loaned_fifo_handler_sample_t,
+    out: *mut MaybeUninit<z_owned_sample_t>,
+    max: usize,
+    budget_ms: u64,
+) -> usize {
+    if out.is_null() {
+        return 0;
+    }
+    let this = this.as_rust_type_ref();
+    let deadline = Instant::now() + Duration::from_millis(budget_ms);
+    let mut n = 0;
+    while n < max && (n == 0 || Instant::now() < deadline) {
+        match this.try_recv() {
+            Ok(Some(s)) => {
+                (*out.add(n)).as_rust_type_mut_uninit().write(Some(s));
+                n += 1;
+            }
+            _ => break,
+        }
+    }
+    n
+}
+
 pub use crate::opaque_types::{
     z_loaned_ring_handler_sample_t, z_moved_ring_handler_sample_t, z_owned_ring_handler_sample_t,
 };
@@ -256,3 +768,231 @@ pub extern "C" fn z_ring_handler_sample_try_recv(
         }
     }
 }
+
+/// Bounded ring of samples over a caller-provided buffer, with a single consumer and possibly concurrent producers
+/// (a closure may be called concurrently by several threads).
+///
+/// Each slot has a sequence number, as in Vyukov's bounded queue: producers claim a slot with a CAS on `tail`
+/// and publish it by bumping its sequence number, so the consumer never observes a slot being written.
+#[cfg(feature = "unstable")]
+pub struct SampleSpscRing {
+    buffer: *mut Option<Sample>,
+    sequences: Box<[AtomicUsize]>,
+    capacity: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+    closed: AtomicBool,
+}
+
+#[cfg(feature = "unstable")]
+unsafe impl Send for SampleSpscRing {}
+#[cfg(feature = "unstable")]
+unsafe impl Sync for SampleSpscRing {}
+
+#[cfg(feature = "unstable")]
+impl SampleSpscRing {
+    /// May be called concurrently by several producers.
+    fn push(&self, sample: Sample) {
+        let mut tail = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = tail % self.capacity;
+            let sequence = self.sequences[slot].load(Ordering::Acquire);
+            if sequence == tail {
+                match self.tail.compare_exchange_weak(
+                    tail,
+                    tail + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { *self.buffer.add(slot) = Some(sample) };
+                        self.sequences[slot].store(tail + 1, Ordering::Release);
+                        return;
+                    }
+                    Err(current) => tail = current,
+                }
+            } else if sequence < tail {
+                // The slot still holds the sample pushed one lap before: the buffer is full.
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            } else {
+                tail = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Must only be called by the single consumer.
+    fn pop(&self) -> Option<Sample> {
+        let head = self.head.load(Ordering::Relaxed);
+        let slot = head % self.capacity;
+        if self.sequences[slot].load(Ordering::Acquire) != head + 1 {
+            return None;
+        }
+        let sample = unsafe { (*self.buffer.add(slot)).take() };
+        self.sequences[slot].store(head + self.capacity, Ordering::Release);
+        self.head.store(head + 1, Ordering::Relaxed);
+        sample
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for SampleSpscRing {
+    fn drop(&mut self) {
+        for i in 0..self.capacity {
+            unsafe { std::ptr::drop_in_place(self.buffer.add(i)) };
+        }
+    }
+}
+
+/// Marks the ring as closed once the send end of the channel is dropped.
+#[cfg(feature = "unstable")]
+struct SampleSpscSender(Arc<SampleSpscRing>);
+
+#[cfg(feature = "unstable")]
+impl Drop for SampleSpscSender {
+    fn drop(&mut self) {
+        self.0.closed.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(feature = "unstable")]
+pub use crate::opaque_types::{
+    z_loaned_spsc_handler_sample_t, z_moved_spsc_handler_sample_t, z_owned_spsc_handler_sample_t,
+};
+#[cfg(feature = "unstable")]
+decl_c_type!(
+    owned(z_owned_spsc_handler_sample_t, option Arc<SampleSpscRing>),
+    loaned(z_loaned_spsc_handler_sample_t),
+);
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs send and receive ends of a wait-free single-producer single-consumer sample channel over a caller-provided buffer.
+///
+/// The send end never blocks nor allocates: when the buffer is full, incoming samples are dropped and counted
+/// (see `z_spsc_handler_sample_dropped()`). It is safe to call concurrently, as a subscriber callback may be.
+/// The receive end is wait-free, it is meant to be polled with `z_spsc_handler_sample_try_recv()` by a single consumer thread,
+/// e.g. a rendering loop which can not wait on a mutex.
+///
+/// @param callback: An uninitialized memory location where the send end of the channel will be constructed.
+/// @param handler: An uninitialized memory location where the receive end of the channel will be constructed.
+/// @param buffer: Pointer to an array of `capacity` samples, used as storage for the channel. Its content is initialized by this function.
+/// It must stay valid until both ends of the channel are dropped.
+/// @param capacity: The capacity of the channel.
+/// @return 0 in case of success, `Z_EINVAL` if `buffer` is null or `capacity` is 0 (in this case both ends are in their gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_spsc_channel_sample_new(
+    callback: &mut MaybeUninit<z_owned_closure_sample_t>,
+    handler: &mut MaybeUninit<z_owned_spsc_handler_sample_t>,
+    buffer: *mut MaybeUninit<z_owned_sample_t>,
+    capacity: usize,
+) -> z_result_t {
+    if buffer.is_null() || capacity == 0 {
+        callback.write(z_owned_closure_sample_t::default());
+        handler.as_rust_type_mut_uninit().write(None);
+        return result::Z_EINVAL;
+    }
+    for i in 0..capacity {
+        (*buffer.add(i)).as_rust_type_mut_uninit().write(None);
+    }
+    let ring = Arc::new(SampleSpscRing {
+        buffer: buffer as *mut Option<Sample>,
+        sequences: (0..capacity).map(AtomicUsize::new).collect(),
+        capacity,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        dropped: AtomicUsize::new(0),
+        closed: AtomicBool::new(false),
+    });
+    let sender = SampleSpscSender(ring.clone());
+    let cb: Arc<dyn Fn(Sample) + Send + Sync> = Arc::new(move |sample| sender.0.push(sample));
+    let cb_ptr = Box::into_raw(Box::new(cb)) as *mut libc::c_void;
+    handler.as_rust_type_mut_uninit().write(Some(ring));
+    callback.write(z_owned_closure_sample_t {
+        _call: Some(__z_handler_sample_send),
+        _context: cb_ptr,
+        _drop: Some(__z_handler_sample_drop),
+    });
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns sample from the single-producer single-consumer channel, without blocking. Must only be called by a single consumer thread.
+/// @return 0 in case of success, `Z_CHANNEL_DISCONNECTED` if the send end was dropped and the buffer is empty (the sample will be in the gravestone state),
+/// `Z_CHANNEL_NODATA` if the send end is still alive, but the buffer is empty (the sample will be in the gravestone state).
+#[no_mangle]
+pub extern "C" fn z_spsc_handler_sample_try_recv(
+    this: &z_loaned_spsc_handler_sample_t,
+    sample: &mut MaybeUninit<z_owned_sample_t>,
+) -> z_result_t {
+    let ring = this.as_rust_type_ref();
+    // Check for closure before popping, so that samples pushed right before the send end is dropped are not missed.
+    let closed = ring.closed.load(Ordering::Acquire);
+    match ring.pop() {
+        Some(s) => {
+            sample.as_rust_type_mut_uninit().write(Some(s));
+            result::Z_OK
+        }
+        None => {
+            sample.as_rust_type_mut_uninit().write(None);
+            if closed {
+                result::Z_CHANNEL_DISCONNECTED
+            } else {
+                result::Z_CHANNEL_NODATA
+            }
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns the number of samples dropped by the single-producer single-consumer channel because its buffer was full.
+#[no_mangle]
+pub extern "C" fn z_spsc_handler_sample_dropped(this: &z_loaned_spsc_handler_sample_t) -> usize {
+    this.as_rust_type_ref().dropped.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Borrows handler.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_spsc_handler_sample_loan(
+    this: &z_owned_spsc_handler_sample_t,
+) -> &z_loaned_spsc_handler_sample_t {
+    this.as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Drops the handler and resets it to a gravestone state.
+#[no_mangle]
+pub extern "C" fn z_spsc_handler_sample_drop(this_: &mut z_moved_spsc_handler_sample_t) {
+    let _ = this_.take_rust_type();
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a handler in gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_spsc_handler_sample_null(
+    this_: &mut MaybeUninit<z_owned_spsc_handler_sample_t>,
+) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if handler is valid, ``false`` if it is in gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_spsc_handler_sample_check(
+    this_: &z_owned_spsc_handler_sample_t,
+) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
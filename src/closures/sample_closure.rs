@@ -13,9 +13,54 @@
 //
 
 use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    ffi::CStr,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
 
 use libc::c_void;
 
+#[cfg(feature = "unstable")]
+use zenoh::bytes::ZBytes;
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+use zenoh::{bytes::ZBytesWriter, shm::GarbageCollect};
+#[cfg(feature = "unstable")]
+use zenoh::{
+    internal::traits::{EncodingBuilderTrait, TimestampBuilderTrait},
+    key_expr::KeyExpr,
+    sample::{Sample, SampleBuilder, SampleKind},
+    session::Session,
+    Wait,
+};
+#[cfg(feature = "unstable")]
+use zenoh_ext::{z_deserialize, z_serialize};
+
+#[cfg(feature = "unstable")]
+pub use crate::opaque_types::{
+    zc_loaned_sample_counter_t, zc_moved_sample_counter_t, zc_owned_sample_counter_t,
+};
+#[cfg(feature = "unstable")]
+use crate::{
+    result::{self, z_result_t},
+    transmute::{Gravestone, RustTypeRef, RustTypeRefUninit},
+    z_loaned_keyexpr_t, z_loaned_session_t, z_moved_publisher_t, z_owned_keyexpr_t, CPublisher,
+};
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+use crate::{
+    shm::provider::{shm_provider::CSHMProvider, shm_provider_impl::alloc_buf},
+    z_moved_shm_provider_t,
+};
 use crate::{
     transmute::{LoanedCTypeRef, OwnedCTypeRef, TakeRustType},
     z_loaned_sample_t,
@@ -170,3 +215,1191 @@ pub extern "C" fn z_closure_sample(
         _drop: drop,
     });
 }
+
+/// A sample closure combinator, turned into a closure by `write_sample_combinator()`.
+#[cfg(feature = "unstable")]
+trait SampleCombinator: Send + Sync + 'static {
+    fn call(&self, sample: &mut z_loaned_sample_t);
+}
+
+#[cfg(feature = "unstable")]
+fn write_sample_combinator(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    combinator: impl SampleCombinator,
+) {
+    this.write((move |sample: &mut z_loaned_sample_t| combinator.call(sample)).into());
+}
+
+/// Number of samples a closure combinator did not forward as-is, shared with the `zc_owned_sample_counter_t` exported to the caller.
+#[cfg(feature = "unstable")]
+#[derive(Clone)]
+pub struct SampleCounter {
+    count: Arc<AtomicUsize>,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCounter {
+    /// Constructs a counter, exporting it to `out` if provided.
+    fn new(out: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>) -> Self {
+        let counter = SampleCounter {
+            count: Arc::new(AtomicUsize::new(0)),
+        };
+        if let Some(out) = out {
+            out.as_rust_type_mut_uninit().write(Some(counter.clone()));
+        }
+        counter
+    }
+
+    fn increment(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "unstable")]
+decl_c_type!(
+    owned(zc_owned_sample_counter_t, option SampleCounter),
+    loaned(zc_loaned_sample_counter_t),
+);
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns the number of samples counted so far by the closure the counter was constructed with.
+///
+/// The counter remains valid after the closure is dropped, and then holds its final count.
+#[no_mangle]
+pub extern "C" fn zc_sample_counter_get(this_: &zc_loaned_sample_counter_t) -> usize {
+    this_.as_rust_type_ref().count.load(Ordering::Relaxed)
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Borrows sample counter.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_sample_counter_loan(
+    this_: &zc_owned_sample_counter_t,
+) -> &zc_loaned_sample_counter_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Drops sample counter and resets it to its gravestone state.
+#[no_mangle]
+pub extern "C" fn zc_sample_counter_drop(this_: &mut zc_moved_sample_counter_t) {
+    let _ = this_.take_rust_type();
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs sample counter in its gravestone state.
+#[no_mangle]
+pub extern "C" fn zc_internal_sample_counter_null(
+    this_: &mut MaybeUninit<zc_owned_sample_counter_t>,
+) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if sample counter is valid, ``false`` if it is in gravestone state.
+#[no_mangle]
+pub extern "C" fn zc_internal_sample_counter_check(this_: &zc_owned_sample_counter_t) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+#[cfg(feature = "unstable")]
+struct SampleSizeFilter {
+    inner: z_owned_closure_sample_t,
+    min_bytes: usize,
+    max_bytes: usize,
+    filtered: SampleCounter,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleSizeFilter {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let len = sample.as_rust_type_ref().payload().len();
+        if (self.min_bytes..=self.max_bytes).contains(&len) {
+            z_closure_sample_call(z_closure_sample_loan(&self.inner), sample);
+        } else {
+            self.filtered.increment();
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure forwarding to `inner` only the samples whose payload length is within [`min_bytes`, `max_bytes`].
+///
+/// The payload length is the total length of the sample payload, regardless of how many slices it is made of.
+/// Other samples are dropped.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param inner: the closure to forward accepted samples to. Will be consumed.
+/// @param min_bytes: minimal accepted payload length, inclusive.
+/// @param max_bytes: maximal accepted payload length, inclusive.
+/// @param filtered: an optional uninitialized memory location where a counter of the dropped samples will be constructed.
+#[no_mangle]
+pub extern "C" fn z_closure_sample_size_filter(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    inner: &mut z_moved_closure_sample_t,
+    min_bytes: usize,
+    max_bytes: usize,
+    filtered: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>,
+) {
+    let filter = SampleSizeFilter {
+        inner: inner.take_rust_type(),
+        min_bytes,
+        max_bytes,
+        filtered: SampleCounter::new(filtered),
+    };
+    write_sample_combinator(this, filter);
+}
+
+#[cfg(feature = "unstable")]
+struct SampleRepublisher {
+    session: Session,
+    key_map: extern "C" fn(
+        key_expr: &z_loaned_keyexpr_t,
+        target: &mut z_owned_keyexpr_t,
+        context: *mut c_void,
+    ),
+    drop: Option<extern "C" fn(context: *mut c_void)>,
+    context: *mut c_void,
+}
+
+#[cfg(feature = "unstable")]
+unsafe impl Send for SampleRepublisher {}
+#[cfg(feature = "unstable")]
+unsafe impl Sync for SampleRepublisher {}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleRepublisher {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let sample = sample.as_rust_type_ref();
+        let mut target = KeyExpr::gravestone();
+        (self.key_map)(
+            sample.key_expr().as_loaned_c_type_ref(),
+            target.as_owned_c_type_mut(),
+            self.context,
+        );
+        if target.is_gravestone() {
+            return;
+        }
+        let res = match sample.kind() {
+            SampleKind::Put => {
+                let mut put = self
+                    .session
+                    .put(target, sample.payload().clone())
+                    .encoding(sample.encoding().clone())
+                    .timestamp(sample.timestamp().cloned());
+                if let Some(attachment) = sample.attachment() {
+                    put = put.attachment(attachment.clone());
+                }
+                put.wait()
+            }
+            SampleKind::Delete => {
+                let mut del = self
+                    .session
+                    .delete(target)
+                    .timestamp(sample.timestamp().cloned());
+                if let Some(attachment) = sample.attachment() {
+                    del = del.attachment(attachment.clone());
+                }
+                del.wait()
+            }
+        };
+        if let Err(e) = res {
+            tracing::error!("Failed to republish sample: {}", e);
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for SampleRepublisher {
+    fn drop(&mut self) {
+        if let Some(drop) = self.drop {
+            drop(self.context)
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure republishing each received sample on another key expression.
+///
+/// For each sample, `key_map` is called with the sample key expression and a key expression in its gravestone state,
+/// that it should construct (e.g. with `z_keyexpr_from_str()`) with the target key expression. If it is left in its
+/// gravestone state, the sample is not republished. The sample payload, encoding, timestamp and attachment are then published
+/// on the target key expression through `session`, a `DELETE` being issued for delete samples.
+///
+/// A session is used rather than a publisher, since a publisher can only publish on the key expression it was declared on.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param session: the session to publish on. The closure holds a reference to it, so dropping the session will only close it
+/// once the closure is dropped as well, unless it is explicitly closed with `z_close()`.
+/// @param key_map: function computing the target key expression.
+/// @param drop: an optional function to be called once on closure drop, to release `context`.
+/// @param context: key mapping context.
+#[no_mangle]
+pub extern "C" fn z_closure_sample_republish(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    session: &z_loaned_session_t,
+    key_map: extern "C" fn(
+        key_expr: &z_loaned_keyexpr_t,
+        target: &mut z_owned_keyexpr_t,
+        context: *mut c_void,
+    ),
+    drop: Option<extern "C" fn(context: *mut c_void)>,
+    context: *mut c_void,
+) {
+    let republisher = SampleRepublisher {
+        session: (**session.as_rust_type_ref()).clone(),
+        key_map,
+        drop,
+        context,
+    };
+    write_sample_combinator(this, republisher);
+}
+
+/// Writes the varint length prefix of a payload frame to `prefix`, returning the number of written bytes.
+#[cfg(feature = "unstable")]
+fn payload_frame_prefix(mut len: usize, prefix: &mut [u8; 10]) -> usize {
+    let mut n = 0;
+    while len >= 0x80 {
+        prefix[n] = (len as u8) | 0x80;
+        len >>= 7;
+        n += 1;
+    }
+    prefix[n] = len as u8;
+    n + 1
+}
+
+/// Appends `payload` to `buf` with the same framing as a serialized slice: varint length followed by the payload bytes.
+#[cfg(feature = "unstable")]
+fn push_payload_frame(buf: &mut Vec<u8>, payload: &ZBytes) {
+    let mut prefix = [0; 10];
+    let n = payload_frame_prefix(payload.len(), &mut prefix);
+    buf.extend_from_slice(&prefix[..n]);
+    for slice in payload.slices() {
+        buf.extend_from_slice(slice);
+    }
+}
+
+/// The batch being accumulated by a `SampleShmBatcher`.
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+struct ShmBatchState {
+    batch: ZBytesWriter,
+    len: usize,
+    stopped: bool,
+}
+
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+struct ShmBatchShared {
+    provider: CSHMProvider,
+    publisher: CPublisher,
+    batch_size: usize,
+    state: Mutex<ShmBatchState>,
+    cv: Condvar,
+}
+
+// Providers with a non-threadsafe context are rejected by `z_closure_sample_shm_batch()`.
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+unsafe impl Send for ShmBatchShared {}
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+unsafe impl Sync for ShmBatchShared {}
+
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+impl ShmBatchShared {
+    /// Writes the frame of `payload` directly into a buffer allocated from the provider.
+    fn frame(&self, payload: &ZBytes) -> ZBytes {
+        let mut prefix = [0; 10];
+        let n = payload_frame_prefix(payload.len(), &mut prefix);
+        let len = n + payload.len();
+        let provider = self.provider.as_loaned_c_type_ref();
+        match alloc_buf::<GarbageCollect>(provider, len) {
+            Ok(mut buf) => {
+                let data = buf.as_mut();
+                data[..n].copy_from_slice(&prefix[..n]);
+                let mut offset = n;
+                for slice in payload.slices() {
+                    data[offset..offset + slice.len()].copy_from_slice(slice);
+                    offset += slice.len();
+                }
+                buf.into()
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to allocate SHM buffer of {} bytes: {:?}, batching sample from heap",
+                    len,
+                    e
+                );
+                let mut frame = Vec::with_capacity(len);
+                push_payload_frame(&mut frame, payload);
+                frame.into()
+            }
+        }
+    }
+
+    fn publish(&self, state: &mut ShmBatchState) {
+        if state.len == 0 {
+            return;
+        }
+        let batch = std::mem::replace(&mut state.batch, ZBytes::writer()).finish();
+        state.len = 0;
+        if let Err(e) = self.publisher.put(batch).wait() {
+            tracing::error!("Failed to publish batch: {}", e);
+        }
+    }
+
+    /// Publishes the pending batch every `interval`, until stopped.
+    fn run(&self, interval: Duration) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            state = self
+                .cv
+                .wait_timeout_while(state, interval, |s| !s.stopped)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+            if state.stopped {
+                return;
+            }
+            self.publish(&mut state);
+        }
+    }
+}
+
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+struct SampleShmBatcher {
+    shared: Arc<ShmBatchShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+impl SampleCombinator for SampleShmBatcher {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let frame = self.shared.frame(sample.as_rust_type_ref().payload());
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.len += frame.len();
+        state.batch.append(frame);
+        if state.len >= self.shared.batch_size {
+            // Keep the lock while publishing, so that batches are sent in order.
+            self.shared.publish(&mut state);
+        }
+    }
+}
+
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+impl Drop for SampleShmBatcher {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.stopped = true;
+        self.shared.cv.notify_all();
+        drop(state);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        let mut state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.shared.publish(&mut state);
+    }
+}
+
+#[cfg(all(feature = "shared-memory", feature = "unstable"))]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure accumulating received sample payloads and publishing them in batches backed by SHM buffers.
+///
+/// Each payload is written as a serialized slice into a buffer allocated from `provider` (with garbage collection if needed),
+/// and appended without copy to the pending batch, so that the batch can be split back on the receiving side by calling
+/// `ze_deserializer_deserialize_slice()` until `ze_deserializer_is_done()` returns ``true``. If the allocation fails, the
+/// payload is appended from a regular heap buffer instead. The pending batch is published with `publisher` once it reaches
+/// `batch_size` bytes, and every `flush_interval_ms` milliseconds by an internal thread, so that data is not kept pending
+/// indefinitely when samples are scarce.
+/// Remaining pending data is published when the closure is dropped, and the provider and the publisher are dropped with it.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param provider: the SHM provider to allocate batch buffers from. Will be consumed. Since the closure can be called
+/// concurrently from zenoh threads, providers created with a non-threadsafe context are rejected.
+/// @param publisher: the publisher to publish batches with. Will be consumed.
+/// @param batch_size: the number of pending bytes that triggers the publication of a batch.
+/// @param flush_interval_ms: the interval at which the pending batch is published regardless of its size, in milliseconds.
+/// If 0, the pending batch is only published once it reaches `batch_size` bytes, or when the closure is dropped.
+/// @return 0 in case of success, `Z_EINVAL` if the provider or the publisher is in its gravestone state or if the provider
+/// is not threadsafe, negative error code if the flush thread can not be started (in these cases the closure is set to its
+/// gravestone state).
+#[no_mangle]
+pub extern "C" fn z_closure_sample_shm_batch(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    provider: &mut z_moved_shm_provider_t,
+    publisher: &mut z_moved_publisher_t,
+    batch_size: usize,
+    flush_interval_ms: u64,
+) -> z_result_t {
+    let (provider, publisher) = match (provider.take_rust_type(), publisher.take_rust_type()) {
+        (Some(CSHMProvider::Dynamic(_)), _) => {
+            tracing::error!("SHM batching requires a threadsafe provider");
+            this.write(z_owned_closure_sample_t::default());
+            return result::Z_EINVAL;
+        }
+        (Some(provider), Some(publisher)) => (provider, publisher),
+        _ => {
+            this.write(z_owned_closure_sample_t::default());
+            return result::Z_EINVAL;
+        }
+    };
+    let shared = Arc::new(ShmBatchShared {
+        provider,
+        publisher,
+        batch_size,
+        state: Mutex::new(ShmBatchState {
+            batch: ZBytes::writer(),
+            len: 0,
+            stopped: false,
+        }),
+        cv: Condvar::new(),
+    });
+    let worker = if flush_interval_ms > 0 {
+        let interval = Duration::from_millis(flush_interval_ms);
+        let worker_shared = shared.clone();
+        match std::thread::Builder::new().spawn(move || worker_shared.run(interval)) {
+            Ok(worker) => Some(worker),
+            Err(e) => {
+                tracing::error!("Failed to start batch flushing thread: {}", e);
+                this.write(z_owned_closure_sample_t::default());
+                return result::Z_EGENERIC;
+            }
+        }
+    } else {
+        None
+    };
+    let batcher = SampleShmBatcher { shared, worker };
+    write_sample_combinator(this, batcher);
+    result::Z_OK
+}
+
+#[cfg(all(unix, feature = "unstable"))]
+#[derive(Default)]
+struct SampleSocketSinkState {
+    frame: Vec<u8>,
+    // The unwritten end of a frame partially written to a stream socket, written before any other frame.
+    tail: Vec<u8>,
+}
+
+#[cfg(all(unix, feature = "unstable"))]
+struct SampleSocketSink {
+    fd: libc::c_int,
+    state: Mutex<SampleSocketSinkState>,
+    dropped: SampleCounter,
+}
+
+#[cfg(all(unix, feature = "unstable"))]
+impl SampleCombinator for SampleSocketSink {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        // Keep the lock while writing, so that frames from concurrent calls are not interleaved.
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let SampleSocketSinkState { frame, tail } = &mut *state;
+        if !tail.is_empty() {
+            match self.write(tail) {
+                Ok(n) => {
+                    tail.drain(..n);
+                }
+                Err(e) => tracing::error!("Failed to write sample to socket {}: {}", self.fd, e),
+            }
+            if !tail.is_empty() {
+                self.dropped.increment();
+                return;
+            }
+        }
+        frame.clear();
+        push_payload_frame(frame, sample.as_rust_type_ref().payload());
+        match self.write(frame) {
+            Ok(0) => self.dropped.increment(),
+            Ok(n) => tail.extend_from_slice(&frame[n..]),
+            Err(e) => {
+                tracing::error!("Failed to write sample to socket {}: {}", self.fd, e);
+                self.dropped.increment();
+            }
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "unstable"))]
+impl SampleSocketSink {
+    /// Writes as much of `buf` as the socket accepts without blocking, returning the number of written bytes.
+    fn write(&self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            let n = unsafe {
+                libc::write(
+                    self.fd,
+                    buf[written..].as_ptr() as *const c_void,
+                    buf.len() - written,
+                )
+            };
+            if n >= 0 {
+                written += n as usize;
+                continue;
+            }
+            let e = std::io::Error::last_os_error();
+            match e.kind() {
+                std::io::ErrorKind::Interrupted => {}
+                std::io::ErrorKind::WouldBlock => break,
+                _ => return Err(e),
+            }
+        }
+        Ok(written)
+    }
+}
+
+#[cfg(all(unix, feature = "unstable"))]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure writing the payload of each received sample to a socket.
+///
+/// Each payload is written as a serialized slice (varint length followed by the payload bytes), so that it can be read
+/// back by any process without linking zenoh. With a datagram socket, each payload is written as a single datagram.
+/// If the socket is non-blocking and can not accept a new payload, the sample is dropped. If a payload is partially written
+/// to a stream socket, the rest of it is kept and written first when the next sample is received: until it is fully
+/// written, the received samples are dropped, so that the payloads are never broken.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param fd: the socket file descriptor to write to. It is not closed when the closure is dropped, and should outlive it.
+/// @param dropped: an optional uninitialized memory location where a counter of the dropped samples, including the ones
+/// that failed to be written, will be constructed.
+#[no_mangle]
+pub extern "C" fn z_closure_sample_to_socket(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    fd: libc::c_int,
+    dropped: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>,
+) {
+    let sink = SampleSocketSink {
+        fd,
+        state: Mutex::new(SampleSocketSinkState::default()),
+        dropped: SampleCounter::new(dropped),
+    };
+    write_sample_combinator(this, sink);
+}
+
+#[cfg(feature = "unstable")]
+struct SampleDownsampleAvg {
+    inner: z_owned_closure_sample_t,
+    window: usize,
+    windows: Mutex<HashMap<KeyExpr<'static>, (f64, usize)>>,
+    non_numeric: SampleCounter,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleDownsampleAvg {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let s = sample.as_rust_type_ref();
+        let value = match s.kind() {
+            SampleKind::Put => z_deserialize::<f64>(s.payload()).ok(),
+            SampleKind::Delete => None,
+        };
+        let Some(value) = value else {
+            self.non_numeric.increment();
+            z_closure_sample_call(z_closure_sample_loan(&self.inner), sample);
+            return;
+        };
+        let avg = {
+            let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+            let (sum, count) = windows.entry(s.key_expr().clone()).or_insert((0.0, 0));
+            *sum += value;
+            *count += 1;
+            if *count < self.window {
+                return;
+            }
+            let avg = *sum / *count as f64;
+            (*sum, *count) = (0.0, 0);
+            avg
+        };
+        let mut averaged: Sample = SampleBuilder::put(s.key_expr().clone(), z_serialize(&avg))
+            .encoding(s.encoding().clone())
+            .timestamp(s.timestamp().cloned())
+            .into();
+        z_closure_sample_call(
+            z_closure_sample_loan(&self.inner),
+            averaged.as_loaned_c_type_mut(),
+        );
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure downsampling numeric samples by averaging them over windows of `window` samples.
+///
+/// The payload of each sample is deserialized as a `double` (as serialized by `ze_serialize_double()`). Values are accumulated
+/// per key expression, and each time `window` values were received on a key expression, a single sample with their average
+/// is forwarded to `inner`. It has the key expression, encoding and timestamp of the last sample of the window.
+/// Values accumulated on a key expression since the last forwarded sample are discarded when the closure is dropped.
+///
+/// Samples whose payload is not a `double`, as well as delete samples, are forwarded unchanged.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param inner: the closure to forward averaged samples to. Will be consumed.
+/// @param window: the number of samples to average, 0 being treated as 1.
+/// @param non_numeric: an optional uninitialized memory location where a counter of the samples forwarded unchanged will be constructed.
+#[no_mangle]
+pub extern "C" fn z_closure_sample_downsample_avg(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    inner: &mut z_moved_closure_sample_t,
+    window: usize,
+    non_numeric: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>,
+) {
+    let downsampler = SampleDownsampleAvg {
+        inner: inner.take_rust_type(),
+        window: window.max(1),
+        windows: Mutex::new(HashMap::new()),
+        non_numeric: SampleCounter::new(non_numeric),
+    };
+    write_sample_combinator(this, downsampler);
+}
+
+#[cfg(feature = "unstable")]
+struct SampleSharder {
+    inners: Vec<z_owned_closure_sample_t>,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleSharder {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let mut hasher = DefaultHasher::new();
+        sample
+            .as_rust_type_ref()
+            .key_expr()
+            .as_str()
+            .hash(&mut hasher);
+        let shard = (hasher.finish() % self.inners.len() as u64) as usize;
+        z_closure_sample_call(z_closure_sample_loan(&self.inners[shard]), sample);
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure routing each sample to one of `inners` closures, chosen by a hash of the sample key expression.
+///
+/// All the samples with a given key expression are forwarded to the same closure, so that they can be processed
+/// in order, while samples with different key expressions are spread over all closures. In particular, when `inners` are
+/// the send ends of fifo channels (see `z_fifo_channel_sample_new()`), each of their handlers can be processed by a separate worker.
+/// The mapping of key expressions to closures only depends on the key expression and on `n`.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param inners: pointer to an array of `n` closures to route samples to. All of them will be consumed.
+/// @param n: the number of closures.
+/// @return 0 in case of success, `Z_EINVAL` if `inners` is null or `n` is 0 (in this case `this_` is set to its gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_closure_sample_shard(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    inners: *mut z_moved_closure_sample_t,
+    n: usize,
+) -> z_result_t {
+    if inners.is_null() || n == 0 {
+        this.write(z_owned_closure_sample_t::default());
+        return result::Z_EINVAL;
+    }
+    let sharder = SampleSharder {
+        inners: std::slice::from_raw_parts_mut(inners, n)
+            .iter_mut()
+            .map(|inner| inner.take_rust_type())
+            .collect(),
+    };
+    write_sample_combinator(this, sharder);
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+struct SampleOnChange {
+    inner: z_owned_closure_sample_t,
+    last_hashes: Mutex<HashMap<KeyExpr<'static>, u64>>,
+    suppressed: SampleCounter,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleOnChange {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let s = sample.as_rust_type_ref();
+        {
+            let mut last_hashes = self.last_hashes.lock().unwrap_or_else(|e| e.into_inner());
+            match s.kind() {
+                SampleKind::Put => {
+                    let mut hasher = DefaultHasher::new();
+                    for slice in s.payload().slices() {
+                        hasher.write(slice);
+                    }
+                    let hash = hasher.finish();
+                    if last_hashes.insert(s.key_expr().clone(), hash) == Some(hash) {
+                        self.suppressed.increment();
+                        return;
+                    }
+                }
+                SampleKind::Delete => {
+                    last_hashes.remove(s.key_expr());
+                }
+            }
+        }
+        z_closure_sample_call(z_closure_sample_loan(&self.inner), sample);
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure forwarding to `inner` only the samples whose payload differs from the previous one on the same key expression.
+///
+/// A hash of the whole payload, regardless of how many slices it is made of, is remembered for each key expression, and
+/// put samples with the same payload hash as the previous sample on their key expression are suppressed. Delete samples are
+/// always forwarded, and reset the remembered payload of their key expression.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param inner: the closure to forward changed samples to. Will be consumed.
+/// @param suppressed: an optional uninitialized memory location where a counter of the suppressed samples will be constructed.
+#[no_mangle]
+pub extern "C" fn z_closure_sample_on_change(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    inner: &mut z_moved_closure_sample_t,
+    suppressed: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>,
+) {
+    let filter = SampleOnChange {
+        inner: inner.take_rust_type(),
+        last_hashes: Mutex::new(HashMap::new()),
+        suppressed: SampleCounter::new(suppressed),
+    };
+    write_sample_combinator(this, filter);
+}
+
+#[cfg(feature = "unstable")]
+struct SampleSplitPutDelete {
+    on_put: z_owned_closure_sample_t,
+    on_delete: z_owned_closure_sample_t,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleSplitPutDelete {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let target = match sample.as_rust_type_ref().kind() {
+            SampleKind::Put => &self.on_put,
+            SampleKind::Delete => &self.on_delete,
+        };
+        z_closure_sample_call(z_closure_sample_loan(target), sample);
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure forwarding put samples to `on_put` and delete samples to `on_delete`.
+///
+/// Both closures are dropped when the constructed closure is dropped.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param on_put: the closure to forward put samples to. Will be consumed.
+/// @param on_delete: the closure to forward delete samples to. Will be consumed.
+#[no_mangle]
+pub extern "C" fn z_closure_sample_split_put_delete(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    on_put: &mut z_moved_closure_sample_t,
+    on_delete: &mut z_moved_closure_sample_t,
+) {
+    let split = SampleSplitPutDelete {
+        on_put: on_put.take_rust_type(),
+        on_delete: on_delete.take_rust_type(),
+    };
+    write_sample_combinator(this, split);
+}
+
+#[cfg(feature = "unstable")]
+struct RotatingFileState {
+    file: Option<BufWriter<File>>,
+    size: usize,
+    files: VecDeque<u64>,
+    frame: Vec<u8>,
+}
+
+#[cfg(feature = "unstable")]
+struct SampleRotatingFile {
+    dir: PathBuf,
+    prefix: String,
+    max_bytes: usize,
+    max_files: usize,
+    state: Mutex<RotatingFileState>,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleRotatingFile {
+    fn path(&self, index: u64) -> PathBuf {
+        self.dir.join(format!("{}.{}", self.prefix, index))
+    }
+
+    /// Returns the indices of the files already recorded in the directory with the same prefix, in increasing order.
+    fn existing_files(&self) -> std::io::Result<VecDeque<u64>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let index = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(self.prefix.as_str()))
+                .and_then(|suffix| suffix.strip_prefix('.'))
+                .and_then(|index| index.parse::<u64>().ok());
+            files.extend(index);
+        }
+        files.sort_unstable();
+        Ok(files.into())
+    }
+
+    fn rotate(&self, state: &mut RotatingFileState) -> std::io::Result<()> {
+        if let Some(mut file) = state.file.take() {
+            file.flush()?;
+        }
+        let index = state.files.back().map_or(0, |last| last + 1);
+        state.file = Some(BufWriter::new(File::create(self.path(index))?));
+        state.size = 0;
+        state.files.push_back(index);
+        while self.max_files > 0 && state.files.len() > self.max_files {
+            let oldest = state.files.pop_front().unwrap_or_default();
+            if let Err(e) = std::fs::remove_file(self.path(oldest)) {
+                tracing::warn!("Failed to remove {}: {}", self.path(oldest).display(), e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleRotatingFile {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let state = &mut *state;
+        state.frame.clear();
+        push_payload_frame(&mut state.frame, sample.as_rust_type_ref().payload());
+        if state.file.is_none()
+            || (state.size > 0 && state.size + state.frame.len() > self.max_bytes)
+        {
+            if let Err(e) = self.rotate(state) {
+                tracing::error!("Failed to rotate recording file: {}", e);
+                return;
+            }
+        }
+        let Some(file) = state.file.as_mut() else {
+            return;
+        };
+        match file.write_all(&state.frame) {
+            Ok(()) => state.size += state.frame.len(),
+            Err(e) => tracing::error!("Failed to record sample: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for SampleRotatingFile {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(mut file) = state.file.take() {
+            if let Err(e) = file.flush() {
+                tracing::error!("Failed to flush recording file: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure recording the payload of each received sample to files, rotated by size.
+///
+/// Each payload is written as a serialized slice (varint length followed by the payload bytes) to the file `<dir>/<prefix>.<index>`,
+/// so that a file can be read back by calling `ze_deserializer_deserialize_slice()` until `ze_deserializer_is_done()` returns ``true``.
+/// A new file with the next index is started when writing a payload would make the current file exceed `max_bytes` (a single
+/// payload larger than `max_bytes` is written to its own file). Indices continue after the ones of the files already present in
+/// `dir` with the same prefix, and the oldest files are removed so that at most `max_files` files are kept.
+/// The current file is flushed and closed when the closure is dropped.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param dir: null-terminated path of an existing directory to record to.
+/// @param prefix: null-terminated prefix of the recording file names.
+/// @param max_bytes: the maximum size of a recording file in bytes.
+/// @param max_files: the maximum number of recording files to keep, or 0 to keep all of them.
+/// @return 0 in case of success, `Z_EINVAL` if an argument is invalid, negative error code if `dir` can not be read (in these
+/// cases the closure is set to its gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_closure_sample_to_rotating_file(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    dir: *const libc::c_char,
+    prefix: *const libc::c_char,
+    max_bytes: usize,
+    max_files: usize,
+) -> z_result_t {
+    let to_str = |s: *const libc::c_char| {
+        (!s.is_null())
+            .then(|| CStr::from_ptr(s).to_str().ok())
+            .flatten()
+    };
+    let (Some(dir), Some(prefix)) = (to_str(dir), to_str(prefix)) else {
+        this.write(z_owned_closure_sample_t::default());
+        return result::Z_EINVAL;
+    };
+    if max_bytes == 0 || prefix.is_empty() {
+        this.write(z_owned_closure_sample_t::default());
+        return result::Z_EINVAL;
+    }
+    let mut recorder = SampleRotatingFile {
+        dir: PathBuf::from(dir),
+        prefix: prefix.to_string(),
+        max_bytes,
+        max_files,
+        state: Mutex::new(RotatingFileState {
+            file: None,
+            size: 0,
+            files: VecDeque::new(),
+            frame: Vec::new(),
+        }),
+    };
+    match recorder.existing_files() {
+        Ok(files) => recorder.state.get_mut().unwrap().files = files,
+        Err(e) => {
+            tracing::error!("Failed to read recording directory {}: {}", dir, e);
+            this.write(z_owned_closure_sample_t::default());
+            return result::Z_EGENERIC;
+        }
+    }
+    write_sample_combinator(this, recorder);
+    result::Z_OK
+}
+
+/// The samples waiting to be forwarded by a `SampleForwardRetry` worker.
+#[cfg(feature = "unstable")]
+struct ForwardRetryState {
+    queue: VecDeque<Sample>,
+    /// Whether a sample older than the queued ones is being forwarded, either directly or by the worker.
+    in_flight: bool,
+    stopped: bool,
+}
+
+#[cfg(feature = "unstable")]
+struct ForwardRetryShared {
+    publisher: CPublisher,
+    max_retries: usize,
+    backoff: Duration,
+    capacity: usize,
+    state: Mutex<ForwardRetryState>,
+    cv: Condvar,
+    overflowed: SampleCounter,
+    failed: SampleCounter,
+}
+
+#[cfg(feature = "unstable")]
+impl ForwardRetryShared {
+    fn forward(&self, sample: &Sample) -> zenoh::Result<()> {
+        match sample.kind() {
+            SampleKind::Put => {
+                let mut put = self
+                    .publisher
+                    .put(sample.payload().clone())
+                    .encoding(sample.encoding().clone());
+                if let Some(attachment) = sample.attachment() {
+                    put = put.attachment(attachment.clone());
+                }
+                put.wait()
+            }
+            SampleKind::Delete => {
+                let mut del = self.publisher.delete();
+                if let Some(attachment) = sample.attachment() {
+                    del = del.attachment(attachment.clone());
+                }
+                del.wait()
+            }
+        }
+    }
+
+    /// Forwards the buffered samples in order, retrying each of them with exponential backoff.
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            state = self
+                .cv
+                .wait_while(state, |s| (s.queue.is_empty() || s.in_flight) && !s.stopped)
+                .unwrap_or_else(|e| e.into_inner());
+            let Some(sample) = state.queue.pop_front() else {
+                return;
+            };
+            if state.stopped {
+                // Flush the remaining samples best-effort, without retrying.
+                drop(state);
+                if self.forward(&sample).is_err() {
+                    self.failed.increment();
+                }
+                state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                continue;
+            }
+            state.in_flight = true;
+            let mut retries = 0;
+            loop {
+                drop(state);
+                let res = self.forward(&sample);
+                state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                let Err(e) = res else {
+                    break;
+                };
+                if retries == self.max_retries || state.stopped {
+                    tracing::error!("Failed to forward sample: {}", e);
+                    self.failed.increment();
+                    break;
+                }
+                let backoff = self.backoff.saturating_mul(1u32 << retries.min(16));
+                retries += 1;
+                state = self
+                    .cv
+                    .wait_timeout_while(state, backoff, |s| !s.stopped)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            }
+            state.in_flight = false;
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+struct SampleForwardRetry {
+    shared: Arc<ForwardRetryShared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "unstable")]
+impl SampleCombinator for SampleForwardRetry {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let sample = sample.as_rust_type_ref();
+        let shared = &self.shared;
+        let mut state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        // Samples are only sent directly when none is buffered or being forwarded, so that they are forwarded in order.
+        if state.queue.is_empty() && !state.in_flight {
+            state.in_flight = true;
+            drop(state);
+            let res = shared.forward(sample);
+            state = shared.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.in_flight = false;
+            if res.is_err() {
+                // The sample is older than the ones buffered meanwhile, it is the one to drop on overflow.
+                if state.queue.len() == shared.capacity {
+                    shared.overflowed.increment();
+                } else {
+                    state.queue.push_front(sample.clone());
+                }
+            }
+            shared.cv.notify_all();
+            return;
+        }
+        if state.queue.len() == shared.capacity {
+            state.queue.pop_front();
+            shared.overflowed.increment();
+        }
+        state.queue.push_back(sample.clone());
+        shared.cv.notify_all();
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for SampleForwardRetry {
+    fn drop(&mut self) {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .stopped = true;
+        self.shared.cv.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a closure forwarding each received sample with `publisher`, retrying failed publications in the background.
+///
+/// The sample payload, encoding and attachment are published with `publisher`, a `DELETE` being issued for delete samples.
+/// If the publication fails, the sample is buffered and an internal thread retries it up to `max_retries` times, waiting
+/// `backoff_ms` milliseconds before the first retry and doubling the delay before each of the next ones. While a sample is
+/// being published or retried, the following ones are buffered, so that samples are forwarded in order. When the buffer is full
+/// the oldest buffered sample is dropped.
+/// Dropping the closure stops the retries and makes a single last attempt to publish each sample still buffered, then drops
+/// the publisher.
+///
+/// @param this_: uninitialized memory location where new closure will be constructed.
+/// @param publisher: the publisher to forward samples with. Will be consumed.
+/// @param max_retries: the maximum number of retries of a failed publication.
+/// @param backoff_ms: the delay before the first retry, in milliseconds.
+/// @param buffer_capacity: the maximum number of buffered samples.
+/// @param overflowed: an optional uninitialized memory location where a counter of the samples dropped on buffer overflow
+/// will be constructed.
+/// @param failed: an optional uninitialized memory location where a counter of the samples dropped after exhausting their
+/// retries will be constructed.
+/// @return 0 in case of success, `Z_EINVAL` if `buffer_capacity` is 0 or if the publisher is in its gravestone state, negative
+/// error code if the retry thread can not be started (in these cases the closure and the counters are set to their gravestone state).
+#[no_mangle]
+pub extern "C" fn z_closure_sample_forward_retry(
+    this: &mut MaybeUninit<z_owned_closure_sample_t>,
+    publisher: &mut z_moved_publisher_t,
+    max_retries: usize,
+    backoff_ms: u64,
+    buffer_capacity: usize,
+    mut overflowed: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>,
+    mut failed: Option<&mut MaybeUninit<zc_owned_sample_counter_t>>,
+) -> z_result_t {
+    let mut fail = |this: &mut MaybeUninit<z_owned_closure_sample_t>| {
+        this.write(z_owned_closure_sample_t::default());
+        for counter in [overflowed.as_deref_mut(), failed.as_deref_mut()]
+            .into_iter()
+            .flatten()
+        {
+            counter.as_rust_type_mut_uninit().write(None);
+        }
+    };
+    let Some(publisher) = publisher.take_rust_type() else {
+        fail(this);
+        return result::Z_EINVAL;
+    };
+    if buffer_capacity == 0 {
+        fail(this);
+        return result::Z_EINVAL;
+    }
+    let shared = Arc::new(ForwardRetryShared {
+        publisher,
+        max_retries,
+        backoff: Duration::from_millis(backoff_ms),
+        capacity: buffer_capacity,
+        state: Mutex::new(ForwardRetryState {
+            queue: VecDeque::new(),
+            in_flight: false,
+            stopped: false,
+        }),
+        cv: Condvar::new(),
+        overflowed: SampleCounter::new(None),
+        failed: SampleCounter::new(None),
+    });
+    let worker_shared = shared.clone();
+    let worker = match std::thread::Builder::new().spawn(move || worker_shared.run()) {
+        Ok(worker) => worker,
+        Err(e) => {
+            tracing::error!("Failed to start forwarding thread: {}", e);
+            fail(this);
+            return result::Z_EGENERIC;
+        }
+    };
+    if let Some(overflowed) = overflowed {
+        overflowed
+            .as_rust_type_mut_uninit()
+            .write(Some(shared.overflowed.clone()));
+    }
+    if let Some(failed) = failed {
+        failed
+            .as_rust_type_mut_uninit()
+            .write(Some(shared.failed.clone()));
+    }
+    let forwarder = SampleForwardRetry {
+        shared,
+        worker: Some(worker),
+    };
+    write_sample_combinator(this, forwarder);
+    result::Z_OK
+}
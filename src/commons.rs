@@ -17,29 +17,30 @@ use std::{mem::MaybeUninit, ptr::null};
 use libc::c_ulong;
 #[cfg(feature = "unstable")]
 use zenoh::{
+    internal::traits::EncodingBuilderTrait,
     qos::Reliability,
     query::ReplyKeyExpr,
-    sample::{Locality, SourceInfo},
+    sample::{Locality, SampleBuilder, SourceInfo},
     session::EntityGlobalId,
 };
 use zenoh::{
     qos::{CongestionControl, Priority},
     query::{ConsolidationMode, QueryTarget},
     sample::{Sample, SampleKind},
-    time::Timestamp,
+    time::{Timestamp, NTP64},
 };
 
 #[cfg(feature = "unstable")]
 use crate::transmute::Gravestone;
 #[cfg(feature = "unstable")]
 use crate::transmute::IntoCType;
-#[cfg(feature = "unstable")]
-use crate::z_moved_source_info_t;
 use crate::{
     result,
     transmute::{CTypeRef, LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_id_t, z_loaned_bytes_t, z_loaned_encoding_t, z_loaned_keyexpr_t, z_loaned_session_t,
 };
+#[cfg(feature = "unstable")]
+use crate::{z_moved_bytes_t, z_moved_encoding_t, z_moved_source_info_t};
 
 /// A zenoh unsigned integer
 #[allow(non_camel_case_types)]
@@ -98,6 +99,14 @@ pub extern "C" fn z_timestamp_id(this_: &z_timestamp_t) -> z_id_t {
     this_.as_rust_type_ref().get_id().to_le_bytes().into()
 }
 
+/// @brief Converts a NTP64 time (as returned by `z_timestamp_ntp64_time()`) into nanoseconds since UNIX epoch.
+///
+/// The conversion is lossy, since the sub-nanosecond part of NTP64 time (which includes the HLC logical counter) is truncated.
+#[no_mangle]
+pub extern "C" fn z_timestamp_ntp64_to_unix_ns(ntp64: u64) -> u64 {
+    NTP64(ntp64).to_duration().as_nanos() as u64
+}
+
 use crate::opaque_types::z_loaned_sample_t;
 pub use crate::opaque_types::{z_moved_sample_t, z_owned_sample_t};
 decl_c_type!(
@@ -167,6 +176,25 @@ pub extern "C" fn z_sample_source_info(this_: &z_loaned_sample_t) -> &z_loaned_s
         .as_loaned_c_type_ref()
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if the sample was published by an entity of the given session, ``false`` otherwise.
+///
+/// Zenoh does not record the origin of a sample by itself: the check relies on the sample source info, so it only
+/// returns ``true`` if the publisher attached it (see `z_publisher_put_options_t::source_info`).
+/// To simply avoid receiving samples published by the same session, prefer declaring the subscriber with
+/// `z_subscriber_options_t::allowed_origin` set to `ZC_LOCALITY_REMOTE`.
+#[no_mangle]
+pub extern "C" fn z_sample_is_local(
+    this_: &z_loaned_sample_t,
+    session: &z_loaned_session_t,
+) -> bool {
+    match this_.as_rust_type_ref().source_info().source_id() {
+        Some(source_id) => source_id.zid() == session.as_rust_type_ref().zid(),
+        None => false,
+    }
+}
+
 /// Constructs an owned shallow copy of the sample (i.e. all modficiations applied to the copy, might be visible in the original) in provided uninitilized memory location.
 #[no_mangle]
 pub extern "C" fn z_sample_clone(
@@ -177,6 +205,39 @@ pub extern "C" fn z_sample_clone(
         .write(Some(this.as_rust_type_ref().clone()));
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a standalone sample, not associated with any session.
+///
+/// This is intended primarily for tests, to feed sample closures and handlers deterministically without a live session,
+/// and for bridges re-injecting data received by other means. The sample has no timestamp, source info or attachment,
+/// and default QoS.
+///
+/// @param this_: An uninitialized memory location where the sample will be constructed.
+/// @param key_expr: The key expression of the sample.
+/// @param payload: The payload of the sample. It is consumed and ignored for `Z_SAMPLE_KIND_DELETE` samples.
+/// @param encoding: An optional encoding of the sample payload. It is consumed and ignored for `Z_SAMPLE_KIND_DELETE` samples.
+/// @param kind: The kind of the sample.
+#[no_mangle]
+pub extern "C" fn z_sample_new_for_test(
+    this_: &mut MaybeUninit<z_owned_sample_t>,
+    key_expr: &z_loaned_keyexpr_t,
+    payload: &mut z_moved_bytes_t,
+    encoding: Option<&mut z_moved_encoding_t>,
+    kind: z_sample_kind_t,
+) {
+    let key_expr = key_expr.as_rust_type_ref().clone();
+    let payload = payload.take_rust_type();
+    let encoding = encoding.map(|e| e.take_rust_type()).unwrap_or_default();
+    let sample = match kind {
+        z_sample_kind_t::PUT => SampleBuilder::put(key_expr, payload)
+            .encoding(encoding)
+            .into(),
+        z_sample_kind_t::DELETE => SampleBuilder::delete(key_expr).into(),
+    };
+    this_.as_rust_type_mut_uninit().write(Some(sample));
+}
+
 /// Returns sample qos priority value.
 #[no_mangle]
 pub extern "C" fn z_sample_priority(this_: &z_loaned_sample_t) -> z_priority_t {
@@ -300,3 +300,170 @@ pub unsafe extern "C" fn zc_config_from_env(
         }
     }
 }
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Link-level timing options, applied to a configuration with `zc_config_set_link_options()`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct zc_link_options_t {
+    /// Lease of the links in milliseconds: a link that does not receive anything during this period is closed.
+    pub lease_ms: u64,
+    /// Interval in milliseconds between keep-alive messages. Zenoh expresses keep-alive as a number of messages
+    /// per lease, so the interval is rounded to a divisor of `lease_ms`; it must be non-zero and not exceed `lease_ms`.
+    pub keep_alive_interval_ms: u64,
+    /// Timeout in milliseconds for establishing a transport over an opened link.
+    pub open_timeout_ms: u64,
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs link options with zenoh default values.
+#[no_mangle]
+pub extern "C" fn zc_link_options_default(this: &mut MaybeUninit<zc_link_options_t>) {
+    this.write(zc_link_options_t {
+        lease_ms: 10000,
+        keep_alive_interval_ms: 2500,
+        open_timeout_ms: 10000,
+    });
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Writes link options into the configuration (`transport/link/tx/lease`, `transport/link/tx/keep_alive`
+/// and `transport/unicast/open_timeout` keys). Should be called before the configuration is used to open a session.
+///
+/// @return 0 in case of success, `Z_EINVAL` if options are inconsistent, negative error code otherwise.
+/// The configuration is left unchanged in case of failure.
+#[no_mangle]
+pub extern "C" fn zc_config_set_link_options(
+    this: &mut z_loaned_config_t,
+    options: &zc_link_options_t,
+) -> result::z_result_t {
+    if options.keep_alive_interval_ms == 0
+        || options.keep_alive_interval_ms > options.lease_ms
+        || options.open_timeout_ms == 0
+    {
+        tracing::error!("Invalid link options: {:?}", options);
+        return result::Z_EINVAL;
+    }
+    let keep_alive = options.lease_ms / options.keep_alive_interval_ms;
+    let mut config = this.as_rust_type_ref().clone();
+    for (key, value) in [
+        ("transport/link/tx/lease", options.lease_ms),
+        ("transport/link/tx/keep_alive", keep_alive),
+        ("transport/unicast/open_timeout", options.open_timeout_ms),
+    ] {
+        if let Err(e) = config.insert_json5(key, &value.to_string()) {
+            tracing::error!(
+                "Failed to insert value '{}' for key '{}' into config: {}",
+                value,
+                key,
+                e
+            );
+            return result::Z_EGENERIC;
+        }
+    }
+    *this.as_rust_type_mut() = config;
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Enables or disables the session admin space (`adminspace/enabled` key of the configuration).
+/// Should be called before the configuration is used to open a session.
+///
+/// Disabling the admin space reduces the memory footprint of the session, but the functionalities relying on it
+/// become unavailable: the session can no longer be introspected by remote admin queries on `@/<zid>/**`, and the following
+/// functions will fail or never observe any event: `zc_info_routers_locators()`, `zc_info_peers_locators()`,
+/// `zc_fifo_channel_sample_new_with_reconnect_marker()`, `z_session_wait_connected()` and `zc_info_metadata()`.
+///
+/// @return 0 in case of success, negative error code otherwise.
+#[no_mangle]
+pub extern "C" fn zc_config_set_adminspace_enabled(
+    this: &mut z_loaned_config_t,
+    enabled: bool,
+) -> result::z_result_t {
+    match this
+        .as_rust_type_mut()
+        .insert_json5("adminspace/enabled", &enabled.to_string())
+    {
+        Ok(_) => result::Z_OK,
+        Err(e) => {
+            tracing::error!("Failed to set adminspace/enabled: {}", e);
+            result::Z_EGENERIC
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Enables or disables the timestamping of the publications of the session with its HLC (`timestamping/enabled` key
+/// of the configuration), whatever the session mode. Should be called before the configuration is used to open a session.
+///
+/// When enabled, each sample published without an explicit timestamp is stamped by the session, which allows to order them.
+/// By default only routers timestamp their publications. Use `z_session_timestamping_enabled()` to check the setting of an open session.
+///
+/// @return 0 in case of success, negative error code otherwise.
+#[no_mangle]
+pub extern "C" fn zc_config_set_timestamping_enabled(
+    this: &mut z_loaned_config_t,
+    enabled: bool,
+) -> result::z_result_t {
+    match this
+        .as_rust_type_mut()
+        .insert_json5("timestamping/enabled", &enabled.to_string())
+    {
+        Ok(_) => result::Z_OK,
+        Err(e) => {
+            tracing::error!("Failed to set timestamping/enabled: {}", e);
+            result::Z_EGENERIC
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Sets a string entry of the user-defined metadata of the configuration (`metadata` key).
+///
+/// The metadata is a JSON object published by the session in its admin space, so that other nodes can retrieve it
+/// with `zc_info_metadata()` (e.g. to get a human-readable role or label of the node). An existing entry with the same key is replaced.
+/// Should be called before the configuration is used to open a session.
+///
+/// @param this_: The configuration.
+/// @param key: A null-terminated string with the metadata entry key.
+/// @param value: A null-terminated string with the metadata entry value.
+/// @return 0 in case of success, negative error code otherwise.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_config_set_metadata(
+    this: &mut z_loaned_config_t,
+    key: *const c_char,
+    value: *const c_char,
+) -> result::z_result_t {
+    if key.is_null() || value.is_null() {
+        return result::Z_EINVAL;
+    }
+    let (key, value) = match (CStr::from_ptr(key).to_str(), CStr::from_ptr(value).to_str()) {
+        (Ok(k), Ok(v)) => (k, v),
+        _ => {
+            tracing::error!("Metadata key and value should be valid utf-8 strings");
+            return result::Z_EINVAL;
+        }
+    };
+    let config = this.as_rust_type_mut();
+    let mut metadata = config
+        .get_json("metadata")
+        .ok()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+        .filter(|m| m.is_object())
+        .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+    metadata[key] = serde_json::Value::String(value.to_owned());
+    match config.insert_json5("metadata", &metadata.to_string()) {
+        Ok(_) => result::Z_OK,
+        Err(e) => {
+            tracing::error!("Failed to set metadata: {}", e);
+            result::Z_EGENERIC
+        }
+    }
+}
@@ -19,7 +19,11 @@ use std::{
     slice::from_raw_parts,
     str::{from_utf8, FromStr},
 };
+#[cfg(feature = "unstable")]
+use std::{collections::HashMap, ffi::CStr, sync::RwLock};
 
+#[cfg(feature = "unstable")]
+use lazy_static::lazy_static;
 use libc::{c_char, strlen};
 use unwrap_infallible::UnwrapInfallible;
 use zenoh::bytes::Encoding;
@@ -195,6 +199,103 @@ pub extern "C" fn z_encoding_equals(
     this_.as_rust_type_ref() == other.as_rust_type_ref()
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief A process-local identifier of an encoding registered with `z_encoding_register()`.
+///
+/// Value `0` is never assigned to a registered encoding and denotes an unknown encoding.
+pub type z_encoding_id_t = u32;
+
+#[cfg(feature = "unstable")]
+#[derive(Default)]
+struct EncodingRegistry {
+    ids: HashMap<String, z_encoding_id_t>,
+    names: Vec<String>,
+}
+
+#[cfg(feature = "unstable")]
+lazy_static! {
+    static ref ENCODING_REGISTRY: RwLock<EncodingRegistry> =
+        RwLock::new(EncodingRegistry::default());
+}
+
+#[cfg(feature = "unstable")]
+fn encoding_name(encoding: &Encoding) -> String {
+    let s: Cow<'static, str> = encoding.into();
+    s.into_owned()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Registers an encoding and returns its identifier.
+///
+/// Registering the same encoding several times returns the same identifier, so it can be used to compare and dispatch
+/// on encodings without string comparisons. The identifiers are only stable within the current process:
+/// they are not transmitted over the network and may differ between processes or runs.
+///
+/// @param name: A null-terminated string representation of the encoding, as accepted by `z_encoding_from_str()`.
+/// @return The identifier of the encoding, or `0` if `name` is `NULL` or is not a valid UTF-8 string.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_encoding_register(name: *const c_char) -> z_encoding_id_t {
+    if name.is_null() {
+        return 0;
+    }
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(name) => encoding_name(&Encoding::from_str(name).unwrap_infallible()),
+        Err(e) => {
+            tracing::error!("Can not register encoding from non UTF-8 string: {}", e);
+            return 0;
+        }
+    };
+    let mut registry = ENCODING_REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = registry.ids.get(&name) {
+        return *id;
+    }
+    registry.names.push(name.clone());
+    let id = registry.names.len() as z_encoding_id_t;
+    registry.ids.insert(name, id);
+    id
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns the identifier of a registered encoding, or `0` if the encoding was not registered with `z_encoding_register()`.
+#[no_mangle]
+pub extern "C" fn z_encoding_id_of(this_: &z_loaned_encoding_t) -> z_encoding_id_t {
+    let name = encoding_name(this_.as_rust_type_ref());
+    let registry = ENCODING_REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.ids.get(&name).copied().unwrap_or(0)
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs an encoding from the identifier returned by `z_encoding_register()`.
+///
+/// @return 0 in case of success, `Z_EINVAL` if no encoding was registered with this identifier
+/// (in this case the encoding will be in its default state).
+#[no_mangle]
+pub extern "C" fn z_encoding_from_id(
+    this: &mut MaybeUninit<z_owned_encoding_t>,
+    id: z_encoding_id_t,
+) -> z_result_t {
+    let encoding = this.as_rust_type_mut_uninit();
+    let registry = ENCODING_REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    match (id as usize)
+        .checked_sub(1)
+        .and_then(|i| registry.names.get(i))
+    {
+        Some(name) => {
+            encoding.write(Encoding::from_str(name).unwrap_infallible());
+            result::Z_OK
+        }
+        None => {
+            encoding.write(Encoding::default());
+            result::Z_EINVAL
+        }
+    }
+}
+
 /// Just some bytes.
 ///
 /// Constant alias for string: `"zenoh/bytes"`.
@@ -12,6 +12,8 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
+#[cfg(feature = "unstable")]
+use std::sync::{Arc, Mutex, Weak};
 use std::{
     ffi::CStr,
     mem::MaybeUninit,
@@ -19,7 +21,10 @@ use std::{
 };
 
 use libc::c_char;
+#[cfg(feature = "unstable")]
+use zenoh::sample::Sample;
 use zenoh::{
+    bytes::Encoding,
     qos::{CongestionControl, Priority},
     query::{ConsolidationMode, QueryConsolidation, QueryTarget, Reply, ReplyError, Selector},
     session::SessionClosedError,
@@ -32,13 +37,13 @@ use crate::{
     transmute::{Gravestone, LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_closure_reply_call, z_closure_reply_loan, z_congestion_control_t, z_consolidation_mode_t,
     z_loaned_bytes_t, z_loaned_encoding_t, z_loaned_keyexpr_t, z_loaned_sample_t,
-    z_loaned_session_t, z_moved_bytes_t, z_moved_closure_reply_t, z_moved_encoding_t, z_priority_t,
-    z_query_target_t,
+    z_loaned_session_t, z_moved_bytes_t, z_moved_closure_reply_t, z_moved_encoding_t,
+    z_owned_sample_t, z_owned_string_t, z_priority_t, z_query_target_t, CStringOwned,
 };
 #[cfg(feature = "unstable")]
 use crate::{
-    transmute::IntoCType, z_id_t, z_moved_source_info_t, zc_locality_default, zc_locality_t,
-    zc_reply_keyexpr_default, zc_reply_keyexpr_t,
+    transmute::IntoCType, z_id_t, z_moved_source_info_t, z_owned_closure_reply_t,
+    zc_locality_default, zc_locality_t, zc_reply_keyexpr_default, zc_reply_keyexpr_t,
 };
 decl_c_type!(
     owned(z_owned_reply_err_t, ReplyError),
@@ -92,6 +97,49 @@ pub extern "C" fn z_reply_err_encoding(this_: &z_loaned_reply_err_t) -> &z_loane
     this_.as_rust_type_ref().encoding().as_loaned_c_type_ref()
 }
 
+/// Converts reply error payload into a string.
+///
+/// The payload encoding should be a textual one (`zenoh/string`, `text/*`, `application/json`, ...) or the default one,
+/// in which case the payload is assumed to be text.
+///
+/// @param this_: Reply error to convert.
+/// @param dst: An uninitialized memory location where to construct a string.
+/// @return 0 in case of success, `Z_EINVAL` if the encoding is not textual, `Z_EUTF8` if the payload is not a valid UTF-8 string.
+/// In case of failure `dst` is set to its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_reply_err_as_string(
+    this_: &z_loaned_reply_err_t,
+    dst: &mut MaybeUninit<z_owned_string_t>,
+) -> result::z_result_t {
+    let err = this_.as_rust_type_ref();
+    let dst = dst.as_rust_type_mut_uninit();
+    let encoding = err.encoding();
+    let encoding_str = encoding.to_string();
+    let mime = encoding_str.split(';').next().unwrap_or_default();
+    let is_text = *encoding == Encoding::default()
+        || *encoding == Encoding::ZENOH_STRING
+        || mime.starts_with("text/")
+        || mime.starts_with("application/json")
+        || mime.starts_with("application/yaml")
+        || mime.starts_with("application/xml");
+    if !is_text {
+        tracing::error!("Reply error encoding is not textual: {}", encoding_str);
+        dst.write(CStringOwned::gravestone());
+        return result::Z_EINVAL;
+    }
+    match err.payload().try_to_string() {
+        Ok(s) => {
+            dst.write(s.into_owned().into());
+            result::Z_OK
+        }
+        Err(e) => {
+            tracing::error!("Reply error payload is not a valid utf-8 string: {}", e);
+            dst.write(CStringOwned::gravestone());
+            result::Z_EUTF8
+        }
+    }
+}
+
 /// Borrows reply error.
 #[no_mangle]
 pub extern "C" fn z_reply_err_loan(this_: &z_owned_reply_err_t) -> &z_loaned_reply_err_t {
@@ -175,6 +223,81 @@ pub unsafe extern "C" fn z_reply_err_mut(
     }
 }
 
+/// Converts the reply into its content, consuming it.
+///
+/// If the reply contains a sample, it is moved into `out_sample` and `out_err` is set to its gravestone state,
+/// otherwise the reply error is moved into `out_err` and `out_sample` is set to its gravestone state.
+///
+/// @return ``true`` if reply contained a sample, ``false`` otherwise (including if `reply` was in its gravestone state).
+#[no_mangle]
+pub extern "C" fn z_reply_into_result(
+    reply: &mut z_moved_reply_t,
+    out_sample: &mut MaybeUninit<z_owned_sample_t>,
+    out_err: &mut MaybeUninit<z_owned_reply_err_t>,
+) -> bool {
+    let out_sample = out_sample.as_rust_type_mut_uninit();
+    let out_err = out_err.as_rust_type_mut_uninit();
+    match reply.take_rust_type().map(Reply::into_result) {
+        Some(Ok(sample)) => {
+            out_sample.write(Some(sample));
+            out_err.write(ReplyError::gravestone());
+            true
+        }
+        Some(Err(err)) => {
+            out_sample.write(None);
+            out_err.write(err);
+            false
+        }
+        None => {
+            out_sample.write(None);
+            out_err.write(ReplyError::gravestone());
+            false
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Moves the sample with the newest timestamp among the successful replies of an array into `out_sample`, dropping all the replies.
+///
+/// Replies without timestamp are considered older than any timestamped one. If several replies have the newest timestamp,
+/// the first of them in the array is selected. All the replies of the array are consumed (i.e. set to their gravestone state),
+/// including the error ones.
+///
+/// @param replies: pointer to an array of `n` replies.
+/// @param n: the number of replies.
+/// @param out_sample: an uninitialized memory location where the selected sample will be constructed.
+/// @return ``true`` if a sample was selected, ``false`` if there is no successful reply (in this case `out_sample` is set to its gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_replies_reduce_latest(
+    replies: *mut z_owned_reply_t,
+    n: usize,
+    out_sample: &mut MaybeUninit<z_owned_sample_t>,
+) -> bool {
+    let mut latest: Option<Sample> = None;
+    if !replies.is_null() {
+        for i in 0..n {
+            let Some(Ok(sample)) = (*replies.add(i))
+                .as_rust_type_mut()
+                .take()
+                .map(Reply::into_result)
+            else {
+                continue;
+            };
+            if latest
+                .as_ref()
+                .is_none_or(|l| sample.timestamp() > l.timestamp())
+            {
+                latest = Some(sample);
+            }
+        }
+    }
+    let found = latest.is_some();
+    out_sample.as_rust_type_mut_uninit().write(latest);
+    found
+}
+
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// @brief Gets the id of the zenoh instance that answered this Reply.
@@ -209,7 +332,8 @@ pub extern "C" fn z_reply_clone(dst: &mut MaybeUninit<z_owned_reply_t>, this_: &
 /// Options passed to the `z_get()` function.
 #[repr(C)]
 pub struct z_get_options_t {
-    /// The Queryables that should be target of the query.
+    /// The Queryables that should be target of the query (`Z_QUERY_TARGET_BEST_MATCHING` by default).
+    /// Use `Z_QUERY_TARGET_ALL` to query every matching queryable, e.g. all the replicas of a storage.
     pub target: z_query_target_t,
     /// The replies consolidation strategy to apply on replies to the query.
     pub consolidation: z_query_consolidation_t,
@@ -266,9 +390,72 @@ pub extern "C" fn z_get_options_default(this_: &mut MaybeUninit<z_get_options_t>
     });
 }
 
+/// The reply callback of a get operation, which can be aborted by `z_session_abort_pending()`.
+///
+/// Replies are delivered without holding any lock: aborting only releases the reference held by the get operation,
+/// so the callback is dropped as soon as the replies being delivered concurrently are processed.
+#[cfg(feature = "unstable")]
+pub(crate) struct PendingReplyCallback {
+    callback: Mutex<Option<Arc<z_owned_closure_reply_t>>>,
+}
+
+#[cfg(feature = "unstable")]
+impl PendingReplyCallback {
+    pub(crate) fn call(&self, reply: &mut z_loaned_reply_t) {
+        let callback = self
+            .callback
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(callback) = callback {
+            z_closure_reply_call(z_closure_reply_loan(&callback), reply);
+        }
+    }
+
+    fn abort(&self) {
+        let callback = self
+            .callback
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        std::mem::drop(callback);
+    }
+}
+
+/// The reply callbacks of the get operations issued on a session and its queriers, which can be aborted by
+/// `z_session_abort_pending()`.
+#[cfg(feature = "unstable")]
+#[derive(Default)]
+pub(crate) struct PendingGets(Mutex<Vec<Weak<PendingReplyCallback>>>);
+
+#[cfg(feature = "unstable")]
+impl PendingGets {
+    /// Registers the reply callback of a get operation, so that it can be dropped by `abort()`.
+    pub(crate) fn track(&self, callback: z_owned_closure_reply_t) -> Arc<PendingReplyCallback> {
+        let callback = Arc::new(PendingReplyCallback {
+            callback: Mutex::new(Some(Arc::new(callback))),
+        });
+        let mut pending = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|c| c.strong_count() > 0);
+        pending.push(Arc::downgrade(&callback));
+        callback
+    }
+
+    /// Drops the reply callbacks of all get operations still in progress.
+    pub(crate) fn abort(&self) {
+        let pending = std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()));
+        for callback in pending.iter().filter_map(Weak::upgrade) {
+            callback.abort();
+        }
+    }
+}
+
 /// Query data from the matching queryables in the system.
 /// Replies are provided through a callback function.
 ///
+/// Replies do not carry the selector of the query that produced them: to demultiplex replies of several queries,
+/// use a distinct callback (or closure context) per query rather than sharing a single handler.
+///
 /// @param session: The zenoh session.
 /// @param key_expr: The key expression matching resources to query.
 /// @param parameters: The query's parameters, similar to a url's query segment.
@@ -292,6 +479,8 @@ pub unsafe extern "C" fn z_get(
         CStr::from_ptr(parameters).to_str().unwrap()
     };
     let session = session.as_rust_type_ref();
+    #[cfg(feature = "unstable")]
+    let callback = session.pending_gets().track(callback);
     let key_expr = key_expr.as_rust_type_ref();
     let mut get = session.get(Selector::from((key_expr, p)));
     if let Some(options) = options {
@@ -329,13 +518,14 @@ pub unsafe extern "C" fn z_get(
     match get
         .callback(move |response| {
             let mut owned_response = Some(response);
-            z_closure_reply_call(
-                z_closure_reply_loan(&callback),
-                owned_response
-                    .as_mut()
-                    .unwrap_unchecked()
-                    .as_loaned_c_type_mut(),
-            )
+            let reply = owned_response
+                .as_mut()
+                .unwrap_unchecked()
+                .as_loaned_c_type_mut();
+            #[cfg(feature = "unstable")]
+            callback.call(reply);
+            #[cfg(not(feature = "unstable"))]
+            z_closure_reply_call(z_closure_reply_loan(&callback), reply);
         })
         .wait()
     {
@@ -11,8 +11,13 @@
 // Contributors:
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
-use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::collections::HashSet;
+use std::{ffi::CStr, mem::MaybeUninit, str::FromStr};
 
+use libc::c_char;
+#[cfg(feature = "unstable")]
+use libc::c_void;
 use zenoh::{session::ZenohId, Wait};
 
 pub use crate::opaque_types::z_id_t;
@@ -22,6 +27,11 @@ use crate::{
     z_closure_zid_call, z_closure_zid_loan, z_loaned_session_t, z_moved_closure_zid_t,
     z_owned_string_t,
 };
+#[cfg(feature = "unstable")]
+use crate::{
+    transmute::Gravestone, zc_closure_locators_call, zc_closure_locators_loan,
+    zc_moved_closure_locators_t, CStringOwned,
+};
 decl_c_type!(copy(z_id_t, ZenohId));
 
 impl From<[u8; 16]> for z_id_t {
@@ -37,6 +47,36 @@ pub extern "C" fn z_id_to_string(zid: &z_id_t, dst: &mut MaybeUninit<z_owned_str
     dst.as_rust_type_mut_uninit().write(zid.to_string().into());
 }
 
+/// @brief Parses a `z_id_t` from its hex string representation, as produced by `z_id_to_string()`.
+///
+/// @param this_: An uninitialized memory location where the id will be constructed.
+/// @param s: A null-terminated hex string.
+/// @return 0 in case of success, `Z_EINVAL` if `s` is not a valid id string (in this case `this_` is left uninitialized).
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn z_id_from_str(
+    this: &mut MaybeUninit<z_id_t>,
+    s: *const c_char,
+) -> result::z_result_t {
+    if s.is_null() {
+        return result::Z_EINVAL;
+    }
+    let zid = CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(|s| ZenohId::from_str(s).map_err(|e| e.to_string()));
+    match zid {
+        Ok(zid) => {
+            this.as_rust_type_mut_uninit().write(zid);
+            result::Z_OK
+        }
+        Err(e) => {
+            tracing::error!("Invalid zenoh id: {}", e);
+            result::Z_EINVAL
+        }
+    }
+}
+
 /// @brief Returns the session's Zenoh ID.
 ///
 /// Unless the `session` is invalid, that ID is guaranteed to be non-zero.
@@ -88,3 +128,266 @@ pub unsafe extern "C" fn z_info_routers_zid(
     }
     result::Z_OK
 }
+
+#[cfg(feature = "unstable")]
+fn _info_locators_inner(
+    session: &z_loaned_session_t,
+    zids: HashSet<ZenohId>,
+    callback: &mut zc_moved_closure_locators_t,
+) -> result::z_result_t {
+    let callback = callback.take_rust_type();
+    let session = session.as_rust_type_ref();
+    let key_expr = format!("@/{}/session/transport/unicast/*", session.zid());
+    let replies = match session.get(key_expr).wait() {
+        Ok(replies) => replies,
+        Err(e) => {
+            tracing::error!("Failed to query admin space: {}", e);
+            return result::Z_EGENERIC;
+        }
+    };
+    while let Ok(reply) = replies.recv() {
+        let Ok(sample) = reply.result() else {
+            continue;
+        };
+        let peer: serde_json::Value = match serde_json::from_slice(&sample.payload().to_bytes()) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Failed to parse transport info: {}", e);
+                continue;
+            }
+        };
+        let Some(zid) = peer["zid"].as_str().and_then(|s| ZenohId::from_str(s).ok()) else {
+            continue;
+        };
+        if !zids.contains(&zid) {
+            continue;
+        }
+        let locators = peer["links"]
+            .as_array()
+            .map(|links| {
+                links
+                    .iter()
+                    .filter_map(|l| l["dst"].as_str())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or_default();
+        let locators = std::ffi::CString::new(locators).unwrap_or_default();
+        zc_closure_locators_call(
+            zc_closure_locators_loan(&callback),
+            zid.as_ctype_ref(),
+            locators.as_ptr(),
+        );
+    }
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Fetches the Zenoh IDs of all connected routers together with the locators of their links.
+///
+/// The information is retrieved from the session admin space, so it should be enabled in the session configuration.
+/// `callback` will be called once for each router with its ID and a null-terminated comma-separated list of its locators.
+/// The locators string is only valid for the duration of the `callback` call.
+/// `callback` is guaranteed to never be called concurrently, and is guaranteed to be dropped before this function exits.
+///
+/// @return 0 on success, negative values on failure.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn zc_info_routers_locators(
+    session: &z_loaned_session_t,
+    callback: &mut zc_moved_closure_locators_t,
+) -> result::z_result_t {
+    let zids = session
+        .as_rust_type_ref()
+        .info()
+        .routers_zid()
+        .wait()
+        .collect();
+    _info_locators_inner(session, zids, callback)
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Fetches the Zenoh IDs of all connected peers together with the locators of their links.
+///
+/// The information is retrieved from the session admin space, so it should be enabled in the session configuration.
+/// `callback` will be called once for each peer with its ID and a null-terminated comma-separated list of its locators.
+/// The locators string is only valid for the duration of the `callback` call.
+/// `callback` is guaranteed to never be called concurrently, and is guaranteed to be dropped before this function exits.
+///
+/// @return 0 on success, negative values on failure.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn zc_info_peers_locators(
+    session: &z_loaned_session_t,
+    callback: &mut zc_moved_closure_locators_t,
+) -> result::z_result_t {
+    let zids = session
+        .as_rust_type_ref()
+        .info()
+        .peers_zid()
+        .wait()
+        .collect();
+    _info_locators_inner(session, zids, callback)
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Fetches the user-defined metadata of a node, set in its configuration with `zc_config_set_metadata()`.
+///
+/// The metadata is retrieved from the admin space of the node with the given id, so it should be enabled in its configuration.
+/// The local session id can be used to get the metadata of the session itself.
+///
+/// @param session: The session to query the admin space with.
+/// @param zid: The id of the node.
+/// @param dst: An uninitialized memory location where a string with the JSON-serialized metadata object will be constructed.
+/// @return 0 in case of success, `Z_EUNAVAILABLE` if the node could not be reached or has no metadata (in this case `dst`
+/// is set to its gravestone state), negative error code otherwise.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn zc_info_metadata(
+    session: &z_loaned_session_t,
+    zid: &z_id_t,
+    dst: &mut MaybeUninit<z_owned_string_t>,
+) -> result::z_result_t {
+    let dst = dst.as_rust_type_mut_uninit();
+    let session = session.as_rust_type_ref();
+    let key_expr = format!("@/{}/*", zid.as_rust_type_ref());
+    let replies = match session.get(key_expr).wait() {
+        Ok(replies) => replies,
+        Err(e) => {
+            tracing::error!("Failed to query admin space: {}", e);
+            dst.write(CStringOwned::gravestone());
+            return result::Z_EGENERIC;
+        }
+    };
+    while let Ok(reply) = replies.recv() {
+        let Ok(sample) = reply.result() else {
+            continue;
+        };
+        let node: serde_json::Value = match serde_json::from_slice(&sample.payload().to_bytes()) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Failed to parse node info: {}", e);
+                continue;
+            }
+        };
+        if let Some(metadata) = node.get("metadata").filter(|m| !m.is_null()) {
+            dst.write(metadata.to_string().into());
+            return result::Z_OK;
+        }
+    }
+    dst.write(CStringOwned::gravestone());
+    result::Z_EUNAVAILABLE
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief The state of a session link, as reported by `z_session_links()`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum z_link_state_t {
+    /// The link is established.
+    UP,
+    /// The link is not established and no connection is attempted, e.g. because the session is closed.
+    DOWN,
+    /// The link is not established yet, but zenoh is trying to connect it.
+    CONNECTING,
+}
+
+#[cfg(feature = "unstable")]
+/// Returns the connect endpoints of the session configuration for its mode, without their metadata and configuration.
+fn _session_connect_locators(session: &zenoh::Session) -> Vec<String> {
+    let config = match serde_json::to_value(&*session.config().lock()) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!("Failed to serialize session config: {}", e);
+            return Vec::new();
+        }
+    };
+    let mode = config["mode"].as_str().unwrap_or("peer");
+    let endpoints = &config["connect"]["endpoints"];
+    let endpoints = if endpoints.is_object() {
+        &endpoints[mode]
+    } else {
+        endpoints
+    };
+    endpoints
+        .as_array()
+        .map(|endpoints| {
+            endpoints
+                .iter()
+                .filter_map(|e| e.as_str())
+                .map(|e| e.split(['?', '#']).next().unwrap_or(e).to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Enumerates the transport links of the session together with their state.
+///
+/// The established links are retrieved from the session admin space, so it should be enabled in the session configuration.
+/// `callback` is called once for each established link with its remote locator and `UP` state, then once for each connect
+/// endpoint of the session configuration that has no established link, with `CONNECTING` state. Once the session is closed,
+/// all the connect endpoints are reported with `DOWN` state.
+/// Endpoints are matched with links by their locators, so an endpoint using a hostname is reported as `CONNECTING` even if
+/// a link to the resolved address is established.
+/// The locator string is only valid for the duration of the `callback` call.
+/// `callback` is guaranteed to never be called concurrently and to not be called after this function exits.
+///
+/// @return 0 on success, negative values on failure.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn z_session_links(
+    session: &z_loaned_session_t,
+    callback: extern "C" fn(locator: *const c_char, state: z_link_state_t, context: *mut c_void),
+    context: *mut c_void,
+) -> result::z_result_t {
+    let session = session.as_rust_type_ref();
+    let call = |locator: &str, state: z_link_state_t| {
+        let locator = std::ffi::CString::new(locator).unwrap_or_default();
+        callback(locator.as_ptr(), state, context);
+    };
+    let endpoints = _session_connect_locators(session);
+    if session.is_closed() {
+        for endpoint in &endpoints {
+            call(endpoint, z_link_state_t::DOWN);
+        }
+        return result::Z_OK;
+    }
+    let key_expr = format!("@/{}/session/transport/unicast/*", session.zid());
+    let replies = match session.get(key_expr).wait() {
+        Ok(replies) => replies,
+        Err(e) => {
+            tracing::error!("Failed to query admin space: {}", e);
+            return result::Z_EGENERIC;
+        }
+    };
+    let mut established = HashSet::new();
+    while let Ok(reply) = replies.recv() {
+        let Ok(sample) = reply.result() else {
+            continue;
+        };
+        let peer: serde_json::Value = match serde_json::from_slice(&sample.payload().to_bytes()) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Failed to parse transport info: {}", e);
+                continue;
+            }
+        };
+        for link in peer["links"].as_array().into_iter().flatten() {
+            if let Some(dst) = link["dst"].as_str() {
+                call(dst, z_link_state_t::UP);
+                established.insert(dst.to_string());
+            }
+        }
+    }
+    for endpoint in endpoints.iter().filter(|e| !established.contains(*e)) {
+        call(endpoint, z_link_state_t::CONNECTING);
+    }
+    result::Z_OK
+}
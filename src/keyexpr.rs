@@ -165,14 +165,25 @@ pub extern "C" fn z_view_keyexpr_is_empty(this_: &z_view_keyexpr_t) -> bool {
 }
 
 /// Returns 0 if the passed string is a valid (and canon) key expression.
-/// Otherwise returns negative error value.
+/// Otherwise returns negative error value: `Z_EPARSE` if the string is not valid UTF-8, `Z_EINVAL` if it is not a canon key expression.
+///
+/// The passed string is neither modified nor copied, which makes this function suitable for validating input on hot paths.
 #[allow(clippy::missing_safety_doc)]
 #[no_mangle]
 pub unsafe extern "C" fn z_keyexpr_is_canon(start: *const c_char, len: usize) -> z_result_t {
-    let name = std::slice::from_raw_parts_mut(start as _, len);
-    match keyexpr_create(name, false, false) {
-        Ok(_) => result::Z_OK,
-        Err(e) => e,
+    let name = std::slice::from_raw_parts(start as *const u8, len);
+    match std::str::from_utf8(name) {
+        Ok(name) => match keyexpr::new(name) {
+            Ok(_) => result::Z_OK,
+            Err(e) => {
+                tracing::debug!("Not a canon key expression: {}", e);
+                result::Z_EINVAL
+            }
+        },
+        Err(e) => {
+            tracing::debug!("{}", e);
+            result::Z_EPARSE
+        }
     }
 }
 
@@ -455,6 +466,11 @@ pub unsafe extern "C" fn z_keyexpr_as_view_string(
 /// Constructs and declares a key expression on the network. This reduces key key expression to a numerical id,
 /// which allows to save the bandwitdth, when passing key expression between Zenoh entities.
 ///
+/// Declared key expressions are best suited for hot paths, such as publishers or repeated `z_put()` calls on the same key:
+/// the numerical id is sent on the wire instead of the full key expression, and its resolution is cached by the session.
+/// The key expression stays declared until it is passed to `z_undeclare_keyexpr()` (or the session is closed),
+/// so it should be undeclared on teardown to release the associated resources on the network.
+///
 /// @param session: Session on which to declare key expression.
 /// @param declared_key_expr: An uninitialized location in memory where key expression will be constructed.
 /// @param key_expr: Key expression to declare on network.
@@ -536,6 +552,17 @@ pub extern "C" fn z_keyexpr_includes(
     l.includes(r)
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if the keyexpr contains wildcards (``*``, ``**`` or ``$*``), i.e. if it defines a set of several keys,
+/// ``false`` if it is a concrete key.
+///
+/// This can be used to validate upfront the key expressions which must be concrete, such as the ones of publishers.
+#[no_mangle]
+pub extern "C" fn z_keyexpr_has_wildcards(this_: &z_loaned_keyexpr_t) -> bool {
+    this_.as_rust_type_ref().as_str().contains('*')
+}
+
 /// Constructs key expression by concatenation of key expression in `left` with a string in `right`.
 /// Returns 0 in case of success, negative error code otherwise.
 ///
@@ -14,7 +14,11 @@
 
 #![allow(non_camel_case_types)]
 
-use std::{cmp::min, slice};
+use std::{
+    cmp::min,
+    slice,
+    sync::atomic::{AtomicU8, Ordering},
+};
 
 use libc::c_void;
 
@@ -51,6 +55,10 @@ mod querier;
 pub use crate::querier::*;
 mod queryable;
 pub use crate::queryable::*;
+#[cfg(feature = "unstable")]
+mod selector;
+#[cfg(feature = "unstable")]
+pub use crate::selector::*;
 mod put;
 pub use crate::put::*;
 mod scouting;
@@ -147,10 +155,15 @@ pub unsafe extern "C" fn zc_init_log_from_env_or(
     }
 }
 
+static LOG_CALLBACK_MIN_SEVERITY: AtomicU8 = AtomicU8::new(zc_log_severity_t::TRACE as u8);
+
 /// Initializes the zenoh runtime logger with custom callback.
 ///
+/// All zenoh and zenoh-c log messages (including the ones emitted by zenoh-c closures and handlers)
+/// are routed to the `callback`, which allows to integrate them into the application's own logging system.
+///
 /// @param min_severity: Minimum severity level of log message to be be passed to the `callback`.
-/// Messages with lower severity levels will be ignored.
+/// Messages with lower severity levels will be ignored. It can be changed later with `zc_log_set_min_severity()`.
 /// @param callback: A closure that will be called with each log message severity level and content.
 #[no_mangle]
 pub extern "C" fn zc_init_log_with_callback(
@@ -158,8 +171,12 @@ pub extern "C" fn zc_init_log_with_callback(
     callback: &mut zc_moved_closure_log_t,
 ) {
     let callback = callback.take_rust_type();
+    LOG_CALLBACK_MIN_SEVERITY.store(min_severity as u8, Ordering::Relaxed);
     zenoh_util::log::init_log_with_callback(
-        move |meta| min_severity <= (*meta.level()).into(),
+        move |meta| {
+            zc_log_severity_t::from(*meta.level()) as u8
+                >= LOG_CALLBACK_MIN_SEVERITY.load(Ordering::Relaxed)
+        },
         move |record| {
             if let Some(s) = record.message.as_ref() {
                 let c = CStringView::new_borrowed_from_slice(s.as_bytes());
@@ -173,6 +190,20 @@ pub extern "C" fn zc_init_log_with_callback(
     );
 }
 
+/// Sets the minimum severity level of log messages passed to the callback installed by `zc_init_log_with_callback()`.
+///
+/// Can be called at any time, e.g. to temporarily increase the verbosity of a running application.
+/// Messages with lower severity levels will be ignored.
+///
+/// The filter of the loggers initialized with `zc_try_init_log_from_env()` or `zc_init_log_from_env_or()` is fixed
+/// at initialization and is not affected by this function, so applications that need to change the verbosity at runtime
+/// should install their logger with `zc_init_log_with_callback()`.
+#[no_mangle]
+pub extern "C" fn zc_log_set_min_severity(min_severity: zc_log_severity_t) {
+    LOG_CALLBACK_MIN_SEVERITY.store(min_severity as u8, Ordering::Relaxed);
+    tracing::callsite::rebuild_interest_cache();
+}
+
 // Test should be runned with `cargo test --no-default-features`
 #[test]
 #[cfg(not(feature = "default"))]
@@ -21,14 +21,27 @@ use zenoh::{
     Wait,
 };
 
+#[cfg(feature = "unstable")]
+use std::sync::{Arc, Condvar, Mutex};
+
 use crate::{
     opaque_types::{z_loaned_liveliness_token_t, z_owned_liveliness_token_t},
     result,
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
-    z_closure_reply_call, z_closure_reply_loan, z_closure_sample_call, z_closure_sample_loan,
-    z_loaned_keyexpr_t, z_loaned_session_t, z_moved_closure_reply_t, z_moved_closure_sample_t,
-    z_moved_liveliness_token_t, z_owned_subscriber_t,
+    z_closure_sample_call, z_closure_sample_loan, z_loaned_keyexpr_t, z_loaned_session_t,
+    z_moved_closure_reply_t, z_moved_closure_sample_t, z_moved_liveliness_token_t,
+    z_owned_subscriber_t,
+};
+#[cfg(feature = "unstable")]
+use crate::{
+    opaque_types::{
+        zc_loaned_liveliness_token_batch_t, zc_moved_liveliness_token_batch_t,
+        zc_owned_liveliness_token_batch_t,
+    },
+    z_owned_reply_t,
 };
+#[cfg(not(feature = "unstable"))]
+use crate::{z_closure_reply_call, z_closure_reply_loan};
 decl_c_type!(
     owned(z_owned_liveliness_token_t, option LivelinessToken),
     loaned(z_loaned_liveliness_token_t),
@@ -126,6 +139,40 @@ pub extern "C" fn z_liveliness_undeclare_token(
     result::Z_OK
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Moves a liveliness token to a new key expression.
+///
+/// The token on `key_expr` is declared before the previous one is undeclared, so that liveliness subscribers never observe a gap
+/// during which neither token is alive: both tokens are briefly alive at the same time instead.
+///
+/// @param this_: The liveliness token to move. If it is in gravestone state, a token is simply declared on `key_expr`.
+/// @param session: The Zenoh session to declare the new liveliness token on.
+/// @param key_expr: The new key expression of the token.
+/// @return 0 in case of success, negative error code otherwise (in this case `this_` is left unchanged).
+#[no_mangle]
+pub extern "C" fn zc_liveliness_token_redeclare(
+    this_: &mut z_owned_liveliness_token_t,
+    session: &z_loaned_session_t,
+    key_expr: &z_loaned_keyexpr_t,
+) -> result::z_result_t {
+    let session = session.as_rust_type_ref();
+    let key_expr = key_expr.as_rust_type_ref();
+    let token = match session.liveliness().declare_token(key_expr).wait() {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("Failed to declare liveliness token: {e}");
+            return result::Z_EGENERIC;
+        }
+    };
+    if let Some(old) = this_.as_rust_type_mut().replace(token) {
+        if let Err(e) = old.undeclare().wait() {
+            tracing::error!("Failed to undeclare token: {e}");
+        }
+    }
+    result::Z_OK
+}
+
 /// @brief The options for `z_liveliness_declare_subscriber()`
 #[repr(C)]
 pub struct z_liveliness_subscriber_options_t {
@@ -171,6 +218,8 @@ fn _liveliness_declare_subscriber_inner<'a, 'b>(
 /// @param subscriber: An uninitialized memory location where subscriber will be constructed.
 /// @param key_expr: The key expression to subscribe to.
 /// @param callback: The callback function that will be called each time a liveliness token status is changed.
+/// The send end of a sample channel (e.g. constructed with `z_ring_channel_sample_new()`) can be passed to poll the changes from its handler instead:
+/// a PUT sample is received when a token is declared and a DELETE sample when it is undeclared.
 /// @param options: The options to be passed to the liveliness subscriber declaration.
 ///
 /// @return 0 in case of success, negative error values otherwise.
@@ -186,7 +235,7 @@ pub extern "C" fn z_liveliness_declare_subscriber(
     let subscriber = _liveliness_declare_subscriber_inner(session, key_expr, callback, options);
     match subscriber.wait() {
         Ok(subscriber) => {
-            this.write(Some(subscriber));
+            this.write(Some(subscriber.into()));
             result::Z_OK
         }
         Err(e) => {
@@ -242,6 +291,8 @@ pub extern "C" fn z_liveliness_get_options_default(
 /// @param session: The Zenoh session.
 /// @param key_expr: The key expression to query liveliness tokens for.
 /// @param callback: The callback function that will be called for each received reply.
+/// The send end of a reply channel (e.g. constructed with `z_fifo_channel_reply_new()`) can be passed to receive the replies from its handler instead:
+/// the handler gets disconnected once all the replies have been received or the query timed out.
 /// @param options: Additional options for the liveliness get operation.
 #[no_mangle]
 pub extern "C" fn z_liveliness_get(
@@ -253,15 +304,21 @@ pub extern "C" fn z_liveliness_get(
     let session = session.as_rust_type_ref();
     let key_expr = key_expr.as_rust_type_ref();
     let callback = callback.take_rust_type();
+    #[cfg(feature = "unstable")]
+    let callback = session.pending_gets().track(callback);
     let liveliness = session.liveliness();
     let mut builder = liveliness.get(key_expr).callback(move |response| {
         let mut owned_response = Some(response);
-        z_closure_reply_call(z_closure_reply_loan(&callback), unsafe {
+        let reply = unsafe {
             owned_response
                 .as_mut()
                 .unwrap_unchecked()
                 .as_loaned_c_type_mut()
-        })
+        };
+        #[cfg(feature = "unstable")]
+        callback.call(reply);
+        #[cfg(not(feature = "unstable"))]
+        z_closure_reply_call(z_closure_reply_loan(&callback), reply);
     });
     if let Some(options) = options {
         builder = builder.timeout(core::time::Duration::from_millis(options.timeout_ms));
@@ -274,3 +331,246 @@ pub extern "C" fn z_liveliness_get(
         }
     }
 }
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Synchronously queries liveliness tokens currently on the network with a key expression intersecting with `key_expr`,
+/// collecting the replies into an array.
+///
+/// The function blocks until all replies have been received or the query timed out.
+/// At most `max` replies are stored, any additional reply is dropped.
+///
+/// @param session: The Zenoh session.
+/// @param key_expr: The key expression to query liveliness tokens for.
+/// @param out: Pointer to an array of `max` uninitialized replies, the first `*out_len` of which will be constructed.
+/// @param max: Capacity of `out` array.
+/// @param out_len: Will receive the number of replies written into `out`.
+/// @param timeout_ms: The timeout for the liveliness query in milliseconds. 0 means default query timeout from zenoh configuration.
+///
+/// @return 0 in case of success, negative error values otherwise.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_liveliness_get_collect(
+    session: &z_loaned_session_t,
+    key_expr: &z_loaned_keyexpr_t,
+    out: *mut MaybeUninit<z_owned_reply_t>,
+    max: usize,
+    out_len: &mut usize,
+    timeout_ms: u64,
+) -> result::z_result_t {
+    *out_len = 0;
+    if out.is_null() && max > 0 {
+        tracing::error!("Output array is NULL");
+        return result::Z_EINVAL;
+    }
+    let session = session.as_rust_type_ref();
+    let key_expr = key_expr.as_rust_type_ref();
+    let liveliness = session.liveliness();
+    let mut builder = liveliness.get(key_expr);
+    if timeout_ms != 0 {
+        builder = builder.timeout(core::time::Duration::from_millis(timeout_ms));
+    }
+    let replies = match builder.wait() {
+        Ok(replies) => replies,
+        Err(e) => {
+            tracing::error!("Failed to query liveliness tokens: {e}");
+            return result::Z_EGENERIC;
+        }
+    };
+    while let Ok(reply) = replies.recv() {
+        if *out_len < max {
+            (*out.add(*out_len))
+                .as_rust_type_mut_uninit()
+                .write(Some(reply));
+            *out_len += 1;
+        }
+    }
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+#[derive(Default)]
+struct DeferredTokensState {
+    pending: usize,
+    failed: usize,
+    tokens: Vec<LivelinessToken>,
+}
+
+#[cfg(feature = "unstable")]
+#[derive(Default)]
+struct DeferredTokens {
+    state: Mutex<DeferredTokensState>,
+    flushed: Condvar,
+}
+
+#[cfg(feature = "unstable")]
+impl DeferredTokens {
+    fn wait_pending(&self) -> std::sync::MutexGuard<'_, DeferredTokensState> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.flushed
+            .wait_while(state, |state| state.pending > 0)
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Liveliness tokens declared with `zc_liveliness_declare_token_deferred()`, shared with the declaration tasks.
+#[cfg(feature = "unstable")]
+#[derive(Default)]
+pub struct LivelinessTokenBatch(Arc<DeferredTokens>);
+
+#[cfg(feature = "unstable")]
+impl Drop for LivelinessTokenBatch {
+    fn drop(&mut self) {
+        // Pending declarations are awaited, so that their tokens are undeclared too.
+        let mut state = self.0.wait_pending();
+        let tokens = std::mem::take(&mut state.tokens);
+        drop(state);
+        std::mem::drop(tokens);
+    }
+}
+
+#[cfg(feature = "unstable")]
+decl_c_type!(
+    owned(zc_owned_liveliness_token_batch_t, option LivelinessTokenBatch),
+    loaned(zc_loaned_liveliness_token_batch_t),
+);
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs an empty batch of liveliness tokens, to declare tokens into with `zc_liveliness_declare_token_deferred()`.
+#[no_mangle]
+pub extern "C" fn zc_liveliness_token_batch_new(
+    this_: &mut MaybeUninit<zc_owned_liveliness_token_batch_t>,
+) {
+    this_
+        .as_rust_type_mut_uninit()
+        .write(Some(LivelinessTokenBatch::default()));
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a batch of liveliness tokens in its gravestone state.
+#[no_mangle]
+pub extern "C" fn zc_internal_liveliness_token_batch_null(
+    this_: &mut MaybeUninit<zc_owned_liveliness_token_batch_t>,
+) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if batch of liveliness tokens is valid, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn zc_internal_liveliness_token_batch_check(
+    this_: &zc_owned_liveliness_token_batch_t,
+) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Borrows batch of liveliness tokens.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn zc_liveliness_token_batch_loan(
+    this_: &zc_owned_liveliness_token_batch_t,
+) -> &zc_loaned_liveliness_token_batch_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Waits for the pending declarations of the batch, then undeclares all of its tokens and resets it to its gravestone state.
+#[no_mangle]
+pub extern "C" fn zc_liveliness_token_batch_drop(this_: &mut zc_moved_liveliness_token_batch_t) {
+    std::mem::drop(this_.take_rust_type())
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns the number of liveliness tokens of the batch whose declaration completed successfully.
+#[no_mangle]
+pub extern "C" fn zc_liveliness_token_batch_len(
+    this_: &zc_loaned_liveliness_token_batch_t,
+) -> usize {
+    this_
+        .as_rust_type_ref()
+        .0
+        .state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .tokens
+        .len()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Declares a liveliness token on the network without waiting for the declaration to complete.
+///
+/// This allows to pipeline the declarations of many tokens, and to wait for all of them at once with `zc_liveliness_flush()`.
+/// The declared token is added to `batch`: it stays alive until the batch is dropped. The session is only retained until the
+/// declaration completes.
+///
+/// @param session: A Zenoh session to declare the liveliness token.
+/// @param batch: The batch to add the liveliness token to.
+/// @param key_expr: A keyexpr to declare a liveliness token for.
+/// @param _options: Liveliness token declaration properties, as for `z_liveliness_declare_token()`.
+/// @return 0 if the declaration was issued, `Z_EGENERIC` if the session is closed.
+#[no_mangle]
+pub extern "C" fn zc_liveliness_declare_token_deferred(
+    session: &z_loaned_session_t,
+    batch: &zc_loaned_liveliness_token_batch_t,
+    key_expr: &z_loaned_keyexpr_t,
+    _options: Option<&z_liveliness_token_options_t>,
+) -> result::z_result_t {
+    let session = session.as_rust_type_ref();
+    if session.is_closed() {
+        tracing::error!("Failed to declare liveliness token: the session is closed");
+        return result::Z_EGENERIC;
+    }
+    let session = (**session).clone();
+    let key_expr = key_expr.as_rust_type_ref().clone();
+    let deferred = batch.as_rust_type_ref().0.clone();
+    deferred
+        .state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .pending += 1;
+    zenoh_runtime::ZRuntime::Application.spawn(async move {
+        let token = session.liveliness().declare_token(key_expr).await;
+        drop(session);
+        let mut state = deferred.state.lock().unwrap_or_else(|e| e.into_inner());
+        match token {
+            Ok(token) => state.tokens.push(token),
+            Err(e) => {
+                tracing::error!("Failed to declare liveliness token: {e}");
+                state.failed += 1;
+            }
+        }
+        state.pending -= 1;
+        if state.pending == 0 {
+            deferred.flushed.notify_all();
+        }
+    });
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Blocks until all the liveliness token declarations issued into the batch by `zc_liveliness_declare_token_deferred()` are completed.
+///
+/// @return 0 in case of success, `Z_EGENERIC` if any of the declarations completed since the previous flush failed.
+#[no_mangle]
+pub extern "C" fn zc_liveliness_flush(
+    batch: &zc_loaned_liveliness_token_batch_t,
+) -> result::z_result_t {
+    let mut state = batch.as_rust_type_ref().0.wait_pending();
+    if std::mem::take(&mut state.failed) > 0 {
+        return result::Z_EGENERIC;
+    }
+    result::Z_OK
+}
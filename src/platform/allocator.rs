@@ -0,0 +1,133 @@
+//
+// Copyright (c) 2017, 2024 ZettaScale Technology.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh team, <zenoh@zettascale.tech>
+//
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use libc::c_void;
+
+use crate::result;
+
+struct AllocatorHooks {
+    alloc: extern "C" fn(size: usize, align: usize, context: *mut c_void) -> *mut c_void,
+    free: extern "C" fn(ptr: *mut c_void, context: *mut c_void),
+    realloc: Option<
+        extern "C" fn(
+            ptr: *mut c_void,
+            new_size: usize,
+            align: usize,
+            context: *mut c_void,
+        ) -> *mut c_void,
+    >,
+    context: *mut c_void,
+}
+
+unsafe impl Send for AllocatorHooks {}
+unsafe impl Sync for AllocatorHooks {}
+
+static HOOKS: spin::Once<AllocatorHooks> = spin::Once::new();
+/// Set once the system allocator has been used, after which the hooks can no longer be installed,
+/// since memory allocated by the system allocator would otherwise be freed by the hooks.
+static SYSTEM_ALLOCATOR_USED: AtomicBool = AtomicBool::new(false);
+
+/// Global allocator forwarding to the hooks installed by `zc_set_global_allocator()`, or to the system allocator if none are installed.
+struct HookAllocator;
+
+unsafe impl GlobalAlloc for HookAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match HOOKS.get() {
+            Some(hooks) => (hooks.alloc)(layout.size(), layout.align(), hooks.context) as *mut u8,
+            None => {
+                if !SYSTEM_ALLOCATOR_USED.load(Ordering::Relaxed) {
+                    SYSTEM_ALLOCATOR_USED.store(true, Ordering::Relaxed);
+                }
+                System.alloc(layout)
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        match HOOKS.get() {
+            Some(hooks) => (hooks.free)(ptr as *mut c_void, hooks.context),
+            None => System.dealloc(ptr, layout),
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let Some(hooks) = HOOKS.get() else {
+            return System.realloc(ptr, layout, new_size);
+        };
+        if let Some(realloc) = hooks.realloc {
+            return realloc(ptr as *mut c_void, new_size, layout.align(), hooks.context) as *mut u8;
+        }
+        let new_ptr = (hooks.alloc)(new_size, layout.align(), hooks.context) as *mut u8;
+        if !new_ptr.is_null() {
+            std::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            (hooks.free)(ptr as *mut c_void, hooks.context);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: HookAllocator = HookAllocator;
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Installs custom allocation functions used for all the memory allocations performed by zenoh-c, e.g. to allocate from an arena during startup.
+///
+/// This function must be called before any other zenoh-c function, since memory allocated before the hooks are installed could not be freed by them.
+/// The hooks can be installed only once, and stay installed until the process exits.
+/// They may be called concurrently from any thread, including zenoh internal threads, and must be thread-safe.
+///
+/// @param alloc: Allocates `size` bytes aligned on `align` (a power of two) bytes. Returning NULL aborts the process.
+/// @param free: Frees memory allocated by `alloc` or `realloc`.
+/// @param realloc: Resizes memory allocated by `alloc` or `realloc` to `new_size` bytes, preserving its content and its `align` alignment.
+/// Can be NULL, in which case `alloc`, a copy and `free` are used instead.
+/// @param context: An arbitrary user context passed to the hooks.
+/// @return 0 in case of success, `Z_EGENERIC` if hooks were already installed or if zenoh-c already allocated memory.
+#[no_mangle]
+pub extern "C" fn zc_set_global_allocator(
+    alloc: extern "C" fn(size: usize, align: usize, context: *mut c_void) -> *mut c_void,
+    free: extern "C" fn(ptr: *mut c_void, context: *mut c_void),
+    realloc: Option<
+        extern "C" fn(
+            ptr: *mut c_void,
+            new_size: usize,
+            align: usize,
+            context: *mut c_void,
+        ) -> *mut c_void,
+    >,
+    context: *mut c_void,
+) -> result::z_result_t {
+    if SYSTEM_ALLOCATOR_USED.load(Ordering::Relaxed) || HOOKS.is_completed() {
+        return result::Z_EGENERIC;
+    }
+    let mut installed = false;
+    HOOKS.call_once(|| {
+        installed = true;
+        AllocatorHooks {
+            alloc,
+            free,
+            realloc,
+            context,
+        }
+    });
+    if installed {
+        result::Z_OK
+    } else {
+        result::Z_EGENERIC
+    }
+}
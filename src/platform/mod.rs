@@ -21,3 +21,8 @@ mod synchronization;
 
 pub use random::*;
 mod random;
+
+#[cfg(feature = "unstable")]
+pub use allocator::*;
+#[cfg(feature = "unstable")]
+mod allocator;
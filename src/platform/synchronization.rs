@@ -1,10 +1,15 @@
 use std::{
+    ffi::CStr,
     mem::MaybeUninit,
-    sync::{Condvar, Mutex, MutexGuard},
-    thread::{self, JoinHandle},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError,
+    },
+    thread::{self, JoinHandle, ThreadId},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use libc::c_void;
+use libc::{c_char, c_void};
 
 pub use crate::opaque_types::{z_loaned_mutex_t, z_moved_mutex_t, z_owned_mutex_t};
 use crate::{
@@ -108,16 +113,320 @@ pub unsafe extern "C" fn z_mutex_try_lock(
     result::Z_OK
 }
 
+/// Locks mutex. If mutex is already locked, blocks the thread until it aquires the lock or until the timeout expires.
+///
+/// The lock is polled with an exponential backoff (up to 1 ms between attempts), so the function is not fair:
+/// it is intended to guard against deadlocks, e.g. in watchdog code, rather than for heavily contended locks.
+/// @param this_: The mutex to lock.
+/// @param timeout_ms: The maximum time to wait in milliseconds.
+/// @return 0 in case of success, `Z_ETIMEOUT` if the lock could not be aquired before the timeout, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_mutex_lock_timeout(
+    this_: &'static mut z_loaned_mutex_t,
+    timeout_ms: u64,
+) -> result::z_result_t {
+    let this = this_.as_rust_type_mut();
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+    let mut backoff = Duration::from_micros(1);
+    loop {
+        match this.0.try_lock() {
+            Ok(new_lock) => {
+                let old_lock = this.1.replace(new_lock);
+                std::mem::forget(old_lock);
+                return result::Z_OK;
+            }
+            Err(TryLockError::Poisoned(_)) => return result::Z_EPOISON_MUTEX,
+            Err(TryLockError::WouldBlock) => {}
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return result::Z_ETIMEOUT;
+        }
+        thread::sleep(backoff.min(deadline - now));
+        backoff = (backoff * 2).min(Duration::from_millis(1));
+    }
+}
+
+pub use crate::opaque_types::{z_loaned_rmutex_t, z_moved_rmutex_t, z_owned_rmutex_t};
+decl_c_type_inequal!(
+    owned(z_owned_rmutex_t, option(Mutex<(Option<ThreadId>, usize)>, Condvar)),
+    loaned(z_loaned_rmutex_t),
+);
+
+/// Constructs a recursive mutex.
+///
+/// Ownership of a recursive mutex is tracked by thread id: the thread holding the lock can lock it again
+/// without blocking, and must then call `z_rmutex_unlock()` once per successful lock to release it.
+/// @return 0 in case of success, negative error code otherwise.
+#[no_mangle]
+pub extern "C" fn z_rmutex_init(this_: &mut MaybeUninit<z_owned_rmutex_t>) -> result::z_result_t {
+    this_
+        .as_rust_type_mut_uninit()
+        .write(Some((Mutex::new((None, 0)), Condvar::new())));
+    result::Z_OK
+}
+
+/// Drops recursive mutex and resets it to its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_rmutex_drop(this_: &mut z_moved_rmutex_t) {
+    let _ = this_.take_rust_type();
+}
+
+/// Returns ``true`` if recursive mutex is valid, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn z_internal_rmutex_check(this_: &z_owned_rmutex_t) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+/// Constructs recursive mutex in a gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_rmutex_null(this_: &mut MaybeUninit<z_owned_rmutex_t>) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+/// Borrows recursive mutex.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_rmutex_loan(this_: &z_owned_rmutex_t) -> &z_loaned_rmutex_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+/// Locks recursive mutex. If mutex is already locked by another thread, blocks the thread until it aquires the lock.
+/// If mutex is already locked by the current thread, the lock count is incremented and the function returns immediately.
+/// @return 0 in case of success, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_rmutex_lock(this_: &z_loaned_rmutex_t) -> result::z_result_t {
+    let (state, condvar) = this_.as_rust_type_ref();
+    let current = thread::current().id();
+    let Ok(state) = state.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    let Ok(mut state) = condvar.wait_while(state, |(owner, _)| owner.is_some_and(|o| o != current))
+    else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    state.0 = Some(current);
+    state.1 += 1;
+    result::Z_OK
+}
+
+/// Tries to lock recursive mutex. If mutex is already locked by another thread, return immediately.
+/// @return 0 in case of success, negative value if failed to aquire the lock.
+#[no_mangle]
+pub extern "C" fn z_rmutex_try_lock(this_: &z_loaned_rmutex_t) -> result::z_result_t {
+    let (state, _) = this_.as_rust_type_ref();
+    let current = thread::current().id();
+    let Ok(mut state) = state.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    if state.0.is_some_and(|o| o != current) {
+        return result::Z_EBUSY_MUTEX;
+    }
+    state.0 = Some(current);
+    state.1 += 1;
+    result::Z_OK
+}
+
+/// Unlocks recursive mutex once. The mutex is released when it has been unlocked as many times as it was locked.
+/// @return 0 in case of success, `Z_EINVAL_MUTEX` if mutex is not locked by the current thread.
+#[no_mangle]
+pub extern "C" fn z_rmutex_unlock(this_: &z_loaned_rmutex_t) -> result::z_result_t {
+    let (state, condvar) = this_.as_rust_type_ref();
+    let Ok(mut state) = state.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    if state.0 != Some(thread::current().id()) {
+        return result::Z_EINVAL_MUTEX;
+    }
+    state.1 -= 1;
+    if state.1 == 0 {
+        state.0 = None;
+        condvar.notify_one();
+    }
+    result::Z_OK
+}
+
+type RwLockState = (
+    RwLock<()>,
+    Mutex<Vec<RwLockReadGuard<'static, ()>>>,
+    Option<RwLockWriteGuard<'static, ()>>,
+);
+
+pub use crate::opaque_types::{z_loaned_rwlock_t, z_moved_rwlock_t, z_owned_rwlock_t};
+decl_c_type!(
+    owned(z_owned_rwlock_t, option RwLockState),
+    loaned(z_loaned_rwlock_t),
+);
+
+/// Constructs a read-write lock.
+/// @return 0 in case of success, negative error code otherwise.
+#[no_mangle]
+pub extern "C" fn z_rwlock_init(this_: &mut MaybeUninit<z_owned_rwlock_t>) -> result::z_result_t {
+    this_.as_rust_type_mut_uninit().write(Some((
+        RwLock::<()>::new(()),
+        Mutex::new(Vec::new()),
+        None::<RwLockWriteGuard<'static, ()>>,
+    )));
+    result::Z_OK
+}
+
+/// Drops read-write lock and resets it to its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_rwlock_drop(this_: &mut z_moved_rwlock_t) {
+    let _ = this_.take_rust_type();
+}
+
+/// Returns ``true`` if read-write lock is valid, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn z_internal_rwlock_check(this_: &z_owned_rwlock_t) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+/// Constructs read-write lock in a gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_rwlock_null(this_: &mut MaybeUninit<z_owned_rwlock_t>) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+/// Mutably borrows read-write lock.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_rwlock_loan_mut(this_: &mut z_owned_rwlock_t) -> &mut z_loaned_rwlock_t {
+    this_
+        .as_rust_type_mut()
+        .as_mut()
+        .unwrap_unchecked()
+        .as_loaned_c_type_mut()
+}
+
+fn rwlock_push_read_guard(
+    this: &'static RwLockState,
+    guard: RwLockReadGuard<'static, ()>,
+) -> result::z_result_t {
+    match this.1.lock() {
+        Ok(mut guards) => {
+            guards.push(guard);
+            result::Z_OK
+        }
+        Err(_) => result::Z_EPOISON_MUTEX,
+    }
+}
+
+/// Locks read-write lock for shared reading. Several threads can hold the read lock at the same time.
+/// If the lock is held for writing, blocks the thread until it aquires the lock.
+/// @return 0 in case of success, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_rwlock_read_lock(this_: &'static mut z_loaned_rwlock_t) -> result::z_result_t {
+    let this: &'static RwLockState = this_.as_rust_type_mut();
+    match this.0.read() {
+        Ok(guard) => rwlock_push_read_guard(this, guard),
+        Err(_) => result::Z_EPOISON_MUTEX,
+    }
+}
+
+/// Tries to lock read-write lock for shared reading. If the lock is held for writing, return immediately.
+/// @return 0 in case of success, negative value if failed to aquire the lock.
+#[no_mangle]
+pub extern "C" fn z_rwlock_try_read_lock(
+    this_: &'static mut z_loaned_rwlock_t,
+) -> result::z_result_t {
+    let this: &'static RwLockState = this_.as_rust_type_mut();
+    match this.0.try_read() {
+        Ok(guard) => rwlock_push_read_guard(this, guard),
+        Err(TryLockError::Poisoned(_)) => result::Z_EPOISON_MUTEX,
+        Err(TryLockError::WouldBlock) => result::Z_EBUSY_MUTEX,
+    }
+}
+
+/// Releases a read lock previously aquired with `z_rwlock_read_lock()` or `z_rwlock_try_read_lock()`.
+/// @return 0 in case of success, negative error code if the lock was not held for reading.
+#[no_mangle]
+pub extern "C" fn z_rwlock_read_unlock(this_: &mut z_loaned_rwlock_t) -> result::z_result_t {
+    let this = this_.as_rust_type_mut();
+    let Ok(mut guards) = this.1.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    match guards.pop() {
+        Some(_) => result::Z_OK,
+        None => result::Z_EINVAL_MUTEX,
+    }
+}
+
+/// Locks read-write lock for exclusive writing. If the lock is already held, blocks the thread until it aquires the lock.
+/// @return 0 in case of success, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_rwlock_write_lock(this_: &'static mut z_loaned_rwlock_t) -> result::z_result_t {
+    let this = this_.as_rust_type_mut();
+    match this.0.write() {
+        Ok(new_lock) => {
+            let old_lock = this.2.replace(new_lock);
+            std::mem::forget(old_lock);
+        }
+        Err(_) => {
+            return result::Z_EPOISON_MUTEX;
+        }
+    }
+    result::Z_OK
+}
+
+/// Tries to lock read-write lock for exclusive writing. If the lock is already held, return immediately.
+/// @return 0 in case of success, negative value if failed to aquire the lock.
+#[no_mangle]
+pub extern "C" fn z_rwlock_try_write_lock(
+    this_: &'static mut z_loaned_rwlock_t,
+) -> result::z_result_t {
+    let this = this_.as_rust_type_mut();
+    match this.0.try_write() {
+        Ok(new_lock) => {
+            let old_lock = this.2.replace(new_lock);
+            std::mem::forget(old_lock);
+        }
+        Err(TryLockError::Poisoned(_)) => return result::Z_EPOISON_MUTEX,
+        Err(TryLockError::WouldBlock) => return result::Z_EBUSY_MUTEX,
+    }
+    result::Z_OK
+}
+
+/// Releases the write lock previously aquired with `z_rwlock_write_lock()` or `z_rwlock_try_write_lock()`.
+/// If the lock was not aquired by the current thread, the behaviour is undefined.
+/// @return 0 in case of success, negative error code if the lock was not held for writing.
+#[no_mangle]
+pub extern "C" fn z_rwlock_write_unlock(this_: &mut z_loaned_rwlock_t) -> result::z_result_t {
+    let this = this_.as_rust_type_mut();
+    if this.2.is_none() {
+        return result::Z_EINVAL_MUTEX;
+    } else {
+        this.2.take();
+    }
+    result::Z_OK
+}
+
 pub use crate::opaque_types::{z_loaned_condvar_t, z_moved_condvar_t, z_owned_condvar_t};
 decl_c_type_inequal!(
-    owned(z_owned_condvar_t, option Condvar),
+    owned(z_owned_condvar_t, option(Condvar, AtomicUsize)),
     loaned(z_loaned_condvar_t),
 );
 
+// Binds the conditional variable to the semaphore it is first waited with by `z_condvar_wait_semaphore()` (identified by the
+// address of its mutex), so that waiting with another semaphore is reported as an error instead of panicking inside `Condvar`.
+fn condvar_bind<T>(bound: &AtomicUsize, mutex: &Mutex<T>) -> bool {
+    let addr = mutex as *const Mutex<T> as usize;
+    match bound.compare_exchange(0, addr, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => true,
+        Err(current) => current == addr,
+    }
+}
+
 /// Constructs conditional variable.
 #[no_mangle]
 pub extern "C" fn z_condvar_init(this_: &mut MaybeUninit<z_owned_condvar_t>) {
-    this_.as_rust_type_mut_uninit().write(Some(Condvar::new()));
+    this_
+        .as_rust_type_mut_uninit()
+        .write(Some((Condvar::new(), AtomicUsize::new(0))));
 }
 
 /// Constructs conditional variable in a gravestone state.
@@ -165,11 +474,20 @@ pub unsafe extern "C" fn z_condvar_loan_mut(
 /// @return 0 in case of success, negative error code in case of failure.
 #[no_mangle]
 pub extern "C" fn z_condvar_signal(this_: &z_loaned_condvar_t) -> result::z_result_t {
-    let this = this_.as_rust_type_ref();
+    let (this, _) = this_.as_rust_type_ref();
     this.notify_one();
     result::Z_OK
 }
 
+/// Wakes up all the threads blocked waiting on this conditional variable.
+/// @return 0 in case of success, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_condvar_signal_all(this_: &z_loaned_condvar_t) -> result::z_result_t {
+    let (this, _) = this_.as_rust_type_ref();
+    this.notify_all();
+    result::Z_OK
+}
+
 /// Blocks the current thread until the conditional variable receives a notification.
 ///
 /// The function atomically unlocks the guard mutex `m` and blocks the current thread.
@@ -181,7 +499,7 @@ pub unsafe extern "C" fn z_condvar_wait(
     this: &z_loaned_condvar_t,
     m: &mut z_loaned_mutex_t,
 ) -> result::z_result_t {
-    let this = this.as_rust_type_ref();
+    let (this, _) = this.as_rust_type_ref();
     let m = m.as_rust_type_mut();
     if m.1.is_none() {
         return result::Z_EINVAL_MUTEX; // lock was not aquired prior to wait call
@@ -196,14 +514,230 @@ pub unsafe extern "C" fn z_condvar_wait(
     result::Z_OK
 }
 
+/// Blocks the current thread until the conditional variable receives a notification, or until the deadline is reached.
+///
+/// The function atomically unlocks the guard mutex `m` and blocks the current thread.
+/// When the function returns the lock will have been re-aquired again, whether the wait timed out or not.
+/// Note: The function may be subject to spurious wakeups.
+/// @param this_: The conditional variable to wait on.
+/// @param m: The mutex, locked by the current thread.
+/// @param abstime_ms: The deadline, in milliseconds since the UNIX epoch (i.e. on the same system clock as `z_time_now()`).
+/// @return 0 in case of notification, `Z_ETIMEOUT` if the deadline was reached, negative error code in case of failure.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_condvar_wait_until(
+    this: &z_loaned_condvar_t,
+    m: &mut z_loaned_mutex_t,
+    abstime_ms: u64,
+) -> result::z_result_t {
+    let (this, _) = this.as_rust_type_ref();
+    let m = m.as_rust_type_mut();
+    if m.1.is_none() {
+        return result::Z_EINVAL_MUTEX; // lock was not aquired prior to wait call
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO);
+    let timeout = Duration::from_millis(abstime_ms).saturating_sub(now);
+    let lock = m.1.take().unwrap();
+    match this.wait_timeout(lock, timeout) {
+        Ok((new_lock, res)) => {
+            m.1 = Some(new_lock);
+            if res.timed_out() {
+                return result::Z_ETIMEOUT;
+            }
+        }
+        Err(_) => return result::Z_EPOISON_MUTEX,
+    }
+
+    result::Z_OK
+}
+
+pub use crate::opaque_types::{z_loaned_semaphore_t, z_moved_semaphore_t, z_owned_semaphore_t};
+decl_c_type_inequal!(
+    owned(z_owned_semaphore_t, option(Mutex<usize>, Condvar)),
+    loaned(z_loaned_semaphore_t),
+);
+
+/// Constructs a counting semaphore with `permits` initially available permits.
+/// @return 0 in case of success, negative error code otherwise.
+#[no_mangle]
+pub extern "C" fn z_semaphore_init(
+    this_: &mut MaybeUninit<z_owned_semaphore_t>,
+    permits: usize,
+) -> result::z_result_t {
+    this_
+        .as_rust_type_mut_uninit()
+        .write(Some((Mutex::new(permits), Condvar::new())));
+    result::Z_OK
+}
+
+/// Constructs semaphore in a gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_semaphore_null(this_: &mut MaybeUninit<z_owned_semaphore_t>) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+/// Drops semaphore and resets it to its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_semaphore_drop(this_: &mut z_moved_semaphore_t) {
+    let _ = this_.take_rust_type();
+}
+
+/// Returns ``true`` if semaphore is valid, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn z_internal_semaphore_check(this_: &z_owned_semaphore_t) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+/// Borrows semaphore.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_semaphore_loan(this_: &z_owned_semaphore_t) -> &z_loaned_semaphore_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+/// Acquires a permit from the semaphore. If no permit is available, blocks the thread until one is released.
+/// @return 0 in case of success, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_semaphore_acquire(this_: &z_loaned_semaphore_t) -> result::z_result_t {
+    let (count, condvar) = this_.as_rust_type_ref();
+    let Ok(mut count) = count.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    while *count == 0 {
+        match condvar.wait(count) {
+            Ok(c) => count = c,
+            Err(_) => return result::Z_EPOISON_MUTEX,
+        }
+    }
+    *count -= 1;
+    result::Z_OK
+}
+
+/// Tries to acquire a permit from the semaphore. If no permit is available, return immediately.
+/// @return 0 in case of success, `Z_EBUSY_MUTEX` if no permit is available, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_semaphore_try_acquire(this_: &z_loaned_semaphore_t) -> result::z_result_t {
+    let (count, _) = this_.as_rust_type_ref();
+    let Ok(mut count) = count.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    if *count == 0 {
+        return result::Z_EBUSY_MUTEX;
+    }
+    *count -= 1;
+    result::Z_OK
+}
+
+/// Acquires a permit from the semaphore. If no permit is available, blocks the thread until one is released
+/// or until the timeout expires.
+/// @param this_: The semaphore to acquire a permit from.
+/// @param timeout_ms: The maximum time to wait in milliseconds.
+/// @return 0 in case of success, `Z_ETIMEOUT` if no permit could be acquired before the timeout, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_semaphore_acquire_timeout(
+    this_: &z_loaned_semaphore_t,
+    timeout_ms: u64,
+) -> result::z_result_t {
+    let (count, condvar) = this_.as_rust_type_ref();
+    let Ok(count) = count.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    match condvar.wait_timeout_while(count, Duration::from_millis(timeout_ms), |c| *c == 0) {
+        Ok((mut count, _)) => {
+            if *count == 0 {
+                return result::Z_ETIMEOUT;
+            }
+            *count -= 1;
+            result::Z_OK
+        }
+        Err(_) => result::Z_EPOISON_MUTEX,
+    }
+}
+
+/// Releases a permit to the semaphore, waking up one thread blocked on acquiring it, if any.
+/// @return 0 in case of success, negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_semaphore_release(this_: &z_loaned_semaphore_t) -> result::z_result_t {
+    let (count, condvar) = this_.as_rust_type_ref();
+    let Ok(mut count) = count.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    *count += 1;
+    condvar.notify_one();
+    result::Z_OK
+}
+
+/// Blocks the current thread until the conditional variable receives a notification, giving up a semaphore permit while waiting.
+///
+/// The function atomically releases one permit of the semaphore `sem` (which must have been acquired by the calling thread)
+/// and blocks the current thread. When the function returns, a permit will have been re-acquired again.
+/// Since the permit is released and the wait started under the semaphore internal lock, a thread that acquires a permit,
+/// updates the awaited condition and then calls `z_condvar_signal()` can not miss the waiting thread (no lost wake-up).
+///
+/// The conditional variable must only be used with `z_condvar_wait_semaphore()` on this same semaphore.
+/// Note: The function may be subject to spurious wakeups.
+/// @return 0 in case of success, `Z_EINVAL_MUTEX` if the conditional variable was already waited on with another semaphore
+/// (the permit is kept in this case), negative error code in case of failure.
+#[no_mangle]
+pub extern "C" fn z_condvar_wait_semaphore(
+    this: &z_loaned_condvar_t,
+    sem: &z_loaned_semaphore_t,
+) -> result::z_result_t {
+    let (this, bound) = this.as_rust_type_ref();
+    let (count, sem_condvar) = sem.as_rust_type_ref();
+    if !condvar_bind(bound, count) {
+        return result::Z_EINVAL_MUTEX;
+    }
+    let Ok(mut count) = count.lock() else {
+        return result::Z_EPOISON_MUTEX;
+    };
+    *count += 1;
+    sem_condvar.notify_one();
+    count = match this.wait(count) {
+        Ok(c) => c,
+        Err(_) => return result::Z_EPOISON_MUTEX,
+    };
+    while *count == 0 {
+        match sem_condvar.wait(count) {
+            Ok(c) => count = c,
+            Err(_) => return result::Z_EPOISON_MUTEX,
+        }
+    }
+    *count -= 1;
+    result::Z_OK
+}
+
 pub use crate::opaque_types::{z_moved_task_t, z_owned_task_t};
 decl_c_type!(
     owned(z_owned_task_t, option JoinHandle<()>),
 );
 
+/// Attributes of a task, passed to `z_task_init()`. Zero values mean platform defaults.
 #[repr(C)]
 #[derive(Clone, Copy)]
-pub struct z_task_attr_t(usize);
+pub struct z_task_attr_t {
+    /// The stack size of the task thread in bytes, or 0 for the default stack size.
+    pub stack_size: usize,
+    /// The null-terminated name of the task thread, or NULL for an unnamed thread.
+    /// Some platforms truncate thread names (e.g. to 15 characters on Linux).
+    pub name: *const c_char,
+}
+
+/// Constructs the default task attributes.
+#[no_mangle]
+pub extern "C" fn z_task_attr_default(this_: &mut MaybeUninit<z_task_attr_t>) {
+    this_.write(z_task_attr_t {
+        stack_size: 0,
+        name: std::ptr::null(),
+    });
+}
 
 /// Constructs task in a gravestone state.
 #[no_mangle]
@@ -218,6 +752,7 @@ pub extern "C" fn z_task_detach(this_: &mut z_moved_task_t) {
 }
 
 /// Joins the task and releases all allocated resources
+/// @return 0 in case of success, `Z_ETASK_PANIC` if the task panicked.
 #[no_mangle]
 pub extern "C" fn z_task_join(this_: &mut z_moved_task_t) -> result::z_result_t {
     let Some(task) = this_.take_rust_type() else {
@@ -225,10 +760,21 @@ pub extern "C" fn z_task_join(this_: &mut z_moved_task_t) -> result::z_result_t
     };
     match task.join() {
         Ok(_) => result::Z_OK,
-        Err(_) => result::Z_EINVAL_MUTEX,
+        Err(_) => result::Z_ETASK_PANIC,
     }
 }
 
+/// Returns ``true`` if the task function has returned, ``false`` if it is still running. Does not block.
+///
+/// The task still has to be joined or detached to release its resources. A task in gravestone state is reported as finished.
+#[no_mangle]
+pub extern "C" fn z_task_is_finished(this_: &z_owned_task_t) -> bool {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .is_none_or(|task| task.is_finished())
+}
+
 /// Drop the task. Same as `z_task_detach`. Use `z_task_join` to wait for the task completion.
 #[no_mangle]
 pub extern "C" fn z_task_drop(this_: &mut z_moved_task_t) {
@@ -257,25 +803,39 @@ unsafe impl Send for FunArgPair {}
 /// Constructs a new task.
 ///
 /// @param this_: An uninitialized memory location where task will be constructed.
-/// @param _attr: Attributes of the task (currently unused).
+/// @param attr: Attributes of the task, or NULL to use the default ones.
 /// @param fun: Function to be executed by the task.
 /// @param arg: Argument that will be passed to the function `fun`.
+/// @return 0 in case of success, `Z_ETASK_SPAWN` if the task thread could not be spawned (in this case the task is in its gravestone state).
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn z_task_init(
     this: &mut MaybeUninit<z_owned_task_t>,
-    _attr: *const z_task_attr_t,
+    attr: *const z_task_attr_t,
     fun: unsafe extern "C" fn(arg: *mut c_void) -> *mut c_void,
     arg: *mut c_void,
 ) -> result::z_result_t {
     let this = this.as_rust_type_mut_uninit();
     let fun_arg_pair = FunArgPair { fun, arg };
 
-    match thread::Builder::new().spawn(move || fun_arg_pair.call()) {
+    let mut builder = thread::Builder::new();
+    if let Some(attr) = attr.as_ref() {
+        if attr.stack_size != 0 {
+            builder = builder.stack_size(attr.stack_size);
+        }
+        if !attr.name.is_null() {
+            builder = builder.name(CStr::from_ptr(attr.name).to_string_lossy().into_owned());
+        }
+    }
+    match builder.spawn(move || fun_arg_pair.call()) {
         Ok(join_handle) => {
             this.write(Some(join_handle));
         }
-        Err(_) => return result::Z_EAGAIN_MUTEX,
+        Err(e) => {
+            tracing::error!("Failed to spawn task: {}", e);
+            this.write(None);
+            return result::Z_ETASK_SPAWN;
+        }
     }
     result::Z_OK
 }
@@ -12,10 +12,14 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::sync::RwLock;
+use std::{mem::MaybeUninit, ops::Deref};
 
 #[cfg(feature = "unstable")]
-use zenoh::{handlers::Callback, matching::MatchingStatus};
+use libc::c_void;
+#[cfg(feature = "unstable")]
+use zenoh::{bytes::ZBytes, handlers::Callback, matching::MatchingStatus};
 use zenoh::{
     internal::traits::{EncodingBuilderTrait, SampleBuilderTrait, TimestampBuilderTrait},
     pubsub::{Publisher, PublisherBuilder},
@@ -35,8 +39,8 @@ use crate::{
 #[cfg(feature = "unstable")]
 use crate::{
     transmute::IntoCType, z_closure_matching_status_call, z_closure_matching_status_loan,
-    z_entity_global_id_t, z_reliability_default, z_reliability_t, zc_locality_default,
-    zc_locality_t,
+    z_entity_global_id_t, z_loaned_bytes_t, z_owned_bytes_t, z_reliability_default,
+    z_reliability_t, zc_locality_default, zc_locality_t, PayloadTransform,
 };
 #[cfg(feature = "unstable")]
 use crate::{z_matching_status_t, z_moved_source_info_t, z_owned_matching_listener_t};
@@ -84,9 +88,50 @@ pub extern "C" fn z_publisher_options_default(this_: &mut MaybeUninit<z_publishe
     this_.write(z_publisher_options_t::default());
 }
 
+/// A publisher declared with `z_declare_publisher()`, along with the payload transform set with `z_publisher_set_transform()`.
+pub struct CPublisher {
+    publisher: Publisher<'static>,
+    #[cfg(feature = "unstable")]
+    transform: RwLock<Option<PayloadTransform>>,
+}
+
+impl From<Publisher<'static>> for CPublisher {
+    fn from(publisher: Publisher<'static>) -> Self {
+        CPublisher {
+            publisher,
+            #[cfg(feature = "unstable")]
+            transform: RwLock::new(None),
+        }
+    }
+}
+
+impl Deref for CPublisher {
+    type Target = Publisher<'static>;
+
+    fn deref(&self) -> &Publisher<'static> {
+        &self.publisher
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl CPublisher {
+    /// Applies the transform set on the publisher to the payload, returning the original payload as an error if it was rejected.
+    fn transform_payload(&self, payload: ZBytes) -> Result<ZBytes, ZBytes> {
+        match self
+            .transform
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            Some(transform) => transform.apply(&payload).ok_or(payload),
+            None => Ok(payload),
+        }
+    }
+}
+
 pub use crate::opaque_types::{z_loaned_publisher_t, z_moved_publisher_t, z_owned_publisher_t};
 decl_c_type!(
-    owned(z_owned_publisher_t, option Publisher<'static>),
+    owned(z_owned_publisher_t, option CPublisher),
     loaned(z_loaned_publisher_t),
 );
 
@@ -143,7 +188,7 @@ pub extern "C" fn z_declare_publisher(
             result::Z_EGENERIC
         }
         Ok(publisher) => {
-            this.write(Some(publisher));
+            this.write(Some(publisher.into()));
             result::Z_OK
         }
     }
@@ -244,11 +289,18 @@ pub(crate) fn _apply_pubisher_put_options<
 ///
 /// The payload and all owned options fields are consumed upon function return.
 ///
+/// A successful return means that the message was handed over to the transmission queues, not that it has left the node.
+/// Zenoh does not provide an explicit flush of these queues: to minimize the delay before critical messages are sent,
+/// declare the publisher with `z_publisher_options_t::is_express` set to ``true``, which disables batching, and with
+/// `Z_CONGESTION_CONTROL_BLOCK`, so that they are not dropped when the queues are full. On shutdown, `z_close()` sends
+/// the messages still queued before closing the links.
+///
 /// @param this_: The publisher.
 /// @param payload: The data to publish. Will be consumed.
 /// @param options: The publisher put options. All owned fields will be consumed.
 ///
-/// @return 0 in case of success, negative error values in case of failure.
+/// @return 0 in case of success, negative error values in case of failure (in particular `Z_EGENERIC` if the put was aborted
+/// by the transform set with `z_publisher_set_transform()`).
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub unsafe extern "C" fn z_publisher_put(
@@ -258,10 +310,20 @@ pub unsafe extern "C" fn z_publisher_put(
 ) -> result::z_result_t {
     let publisher = this.as_rust_type_ref();
     let payload = payload.take_rust_type();
+    #[cfg(feature = "unstable")]
+    let (payload, rejected) = match publisher.transform_payload(payload) {
+        Ok(payload) => (payload, false),
+        Err(payload) => (payload, true),
+    };
     let mut put = publisher.put(payload);
     if let Some(options) = options {
         put = _apply_pubisher_put_options(put, options);
     }
+    #[cfg(feature = "unstable")]
+    if rejected {
+        tracing::debug!("Put aborted by the publisher transform");
+        return result::Z_EGENERIC;
+    }
 
     match put.wait() {
         Ok(_) => result::Z_OK,
@@ -273,13 +335,103 @@ pub unsafe extern "C" fn z_publisher_put(
     }
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Sends a sequence of `PUT` messages onto the publisher's key expression in a single call.
+///
+/// Payloads are published in array order, and this order is preserved on the wire, allowing zenoh to coalesce
+/// them into as few network batches as possible. Publication stops at the first failed put: the payloads that were
+/// not sent are dropped. All payloads are consumed upon function return, regardless of the result.
+///
+/// @param this_: The publisher.
+/// @param payloads: Pointer to an array of `len` payloads to publish. Will be consumed.
+/// @param len: Number of payloads in the array.
+///
+/// @return The number of payloads successfully accepted for publication.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_publisher_put_batch(
+    this: &z_loaned_publisher_t,
+    payloads: *mut z_moved_bytes_t,
+    len: usize,
+) -> usize {
+    if payloads.is_null() || len == 0 {
+        return 0;
+    }
+    let publisher = this.as_rust_type_ref();
+    let payloads = std::slice::from_raw_parts_mut(payloads, len);
+    let mut accepted = 0;
+    let mut failed = false;
+    for payload in payloads.iter_mut() {
+        // Always take the payload, so that the ones following a failure are consumed too.
+        let payload = payload.take_rust_type();
+        if failed {
+            continue;
+        }
+        let Ok(payload) = publisher.transform_payload(payload) else {
+            tracing::debug!("Put aborted by the publisher transform");
+            failed = true;
+            continue;
+        };
+        match publisher.put(payload).wait() {
+            Ok(_) => accepted += 1,
+            Err(e) => {
+                tracing::error!("{}", e);
+                failed = true;
+            }
+        }
+    }
+    accepted
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Sets a transform applied to the payload of each put issued by the publisher, e.g. to compress or encrypt it.
+///
+/// `transform` is called by `z_publisher_put()` and `z_publisher_put_batch()` with the payload passed by the user, and
+/// should construct the payload to publish into `out`, returning ``true``. If it returns ``false``, `out` should be left
+/// uninitialized and the put is aborted. Delete messages are not affected.
+/// The transform is removed when the publisher is dropped or undeclared, or when another transform is set.
+///
+/// @param this_: The publisher.
+/// @param transform: The payload transform, or NULL to remove the current one.
+/// @param context: The transform context. It should outlive the publisher.
+#[no_mangle]
+pub extern "C" fn z_publisher_set_transform(
+    this: &z_loaned_publisher_t,
+    transform: Option<
+        extern "C" fn(
+            payload: &z_loaned_bytes_t,
+            out: &mut MaybeUninit<z_owned_bytes_t>,
+            context: *mut c_void,
+        ) -> bool,
+    >,
+    context: *mut c_void,
+) {
+    *this
+        .as_rust_type_ref()
+        .transform
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) =
+        transform.map(|transform| PayloadTransform::new(transform, context));
+}
+
 /// Represents the set of options that can be applied to the delete operation by a previously declared publisher,
 /// whenever issued via `z_publisher_delete()`.
+///
+/// Priority and congestion control of the delete message are the ones of the publisher, set with `z_publisher_options_t`.
 #[repr(C)]
 #[derive(Default)]
 pub struct z_publisher_delete_options_t {
     /// The timestamp of this message.
     pub timestamp: Option<&'static z_timestamp_t>,
+    #[cfg(feature = "unstable")]
+    /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+    ///
+    /// The source info for the delete message.
+    pub source_info: Option<&'static mut z_moved_source_info_t>,
+    /// The attachment to attach to the delete message.
+    pub attachment: Option<&'static mut z_moved_bytes_t>,
 }
 
 /// Constructs the default values for the delete operation via a publisher entity.
@@ -291,11 +443,18 @@ pub extern "C" fn z_publisher_delete_options_default(
     this.write(z_publisher_delete_options_t::default());
 }
 
-pub(crate) fn _apply_pubisher_delete_options<T: TimestampBuilderTrait>(
+pub(crate) fn _apply_pubisher_delete_options<T: SampleBuilderTrait + TimestampBuilderTrait>(
     builder: T,
     options: &mut z_publisher_delete_options_t,
 ) -> T {
     let mut builder = builder;
+    #[cfg(feature = "unstable")]
+    if let Some(source_info) = options.source_info.take() {
+        builder = builder.source_info(source_info.take_rust_type());
+    };
+    if let Some(attachment) = options.attachment.take() {
+        builder = builder.attachment(attachment.take_rust_type());
+    }
     if let Some(timestamp) = options.timestamp {
         builder = builder.timestamp(Some(*timestamp.as_rust_type_ref()));
     }
@@ -304,6 +463,9 @@ pub(crate) fn _apply_pubisher_delete_options<T: TimestampBuilderTrait>(
 
 /// Sends a `DELETE` message onto the publisher's key expression.
 ///
+/// @param publisher: The publisher.
+/// @param options: The publisher delete options. All owned fields will be consumed.
+///
 /// @return 0 in case of success, negative error code in case of failure.
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
@@ -451,7 +613,7 @@ pub extern "C" fn z_publisher_drop(this: &mut z_moved_publisher_t) {
 /// @return 0 in case of success, negative error code otherwise.
 pub extern "C" fn z_undeclare_publisher(this_: &mut z_moved_publisher_t) -> result::z_result_t {
     if let Some(p) = this_.take_rust_type() {
-        if let Err(e) = p.undeclare().wait() {
+        if let Err(e) = p.publisher.undeclare().wait() {
             tracing::error!("{}", e);
             return result::Z_ENETWORK;
         }
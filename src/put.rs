@@ -157,6 +157,11 @@ pub struct z_delete_options_t {
     ///
     /// The allowed destination of this message.
     pub allowed_destination: zc_locality_t,
+    #[cfg(feature = "unstable")]
+    /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+    ///
+    /// The source info for the message.
+    pub source_info: Option<&'static mut z_moved_source_info_t>,
 }
 
 /// Constructs the default value for `z_delete_options_t`.
@@ -172,6 +177,8 @@ pub unsafe extern "C" fn z_delete_options_default(this_: &mut MaybeUninit<z_dele
         reliability: z_reliability_default(),
         #[cfg(feature = "unstable")]
         allowed_destination: zc_locality_default(),
+        #[cfg(feature = "unstable")]
+        source_info: None,
     });
 }
 
@@ -179,7 +186,7 @@ pub unsafe extern "C" fn z_delete_options_default(this_: &mut MaybeUninit<z_dele
 ///
 /// @param session: The zenoh session.
 /// @param key_expr: The key expression to delete.
-/// @param options: The delete options.
+/// @param options: The delete options. All owned fields will be consumed.
 ///
 /// @return 0 in case of success, negative values in case of failure.
 #[no_mangle]
@@ -206,6 +213,9 @@ pub extern "C" fn z_delete(
             del = del
                 .reliability(options.reliability.into())
                 .allowed_destination(options.allowed_destination.into());
+            if let Some(source_info) = options.source_info.take() {
+                del = del.source_info(source_info.take_rust_type());
+            }
         }
     }
 
@@ -12,7 +12,7 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::{ffi::CStr, mem::MaybeUninit};
+use std::{ffi::CStr, mem::MaybeUninit, ops::Deref, sync::Arc};
 
 use libc::c_char;
 #[cfg(feature = "unstable")]
@@ -25,12 +25,12 @@ use zenoh::{
 };
 
 use crate::{
+    get::PendingGets,
     result,
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
-    z_closure_reply_call, z_closure_reply_loan, z_congestion_control_t, z_loaned_keyexpr_t,
-    z_loaned_querier_t, z_loaned_session_t, z_moved_bytes_t, z_moved_closure_reply_t,
-    z_moved_encoding_t, z_moved_querier_t, z_owned_querier_t, z_priority_t,
-    z_query_consolidation_t, z_query_target_t,
+    z_congestion_control_t, z_loaned_keyexpr_t, z_loaned_querier_t, z_loaned_session_t,
+    z_moved_bytes_t, z_moved_closure_reply_t, z_moved_encoding_t, z_moved_querier_t,
+    z_owned_querier_t, z_priority_t, z_query_consolidation_t, z_query_target_t,
 };
 #[cfg(feature = "unstable")]
 use crate::{
@@ -86,8 +86,22 @@ pub extern "C" fn z_querier_options_default(this_: &mut MaybeUninit<z_querier_op
     });
 }
 
+/// A querier, along with the pending get operations of the session it was declared on, which its queries are added to.
+pub struct CQuerier {
+    querier: Querier<'static>,
+    pending_gets: Arc<PendingGets>,
+}
+
+impl Deref for CQuerier {
+    type Target = Querier<'static>;
+
+    fn deref(&self) -> &Querier<'static> {
+        &self.querier
+    }
+}
+
 decl_c_type!(
-    owned(z_owned_querier_t, option Querier<'static>),
+    owned(z_owned_querier_t, option CQuerier),
     loaned(z_loaned_querier_t),
 );
 
@@ -138,7 +152,10 @@ pub extern "C" fn z_declare_querier(
             result::Z_EGENERIC
         }
         Ok(querier) => {
-            this.write(Some(querier));
+            this.write(Some(CQuerier {
+                querier,
+                pending_gets: session.pending_gets().clone(),
+            }));
             result::Z_OK
         }
     }
@@ -235,7 +252,7 @@ pub unsafe extern "C" fn z_querier_get(
     options: Option<&mut z_querier_get_options_t>,
 ) -> result::z_result_t {
     let querier = querier.as_rust_type_ref();
-    let callback = callback.take_rust_type();
+    let callback = querier.pending_gets.track(callback.take_rust_type());
     let mut get = querier.get();
     if let Some(options) = options {
         if let Some(payload) = options.payload.take() {
@@ -258,8 +275,7 @@ pub unsafe extern "C" fn z_querier_get(
     match get
         .callback(move |response| {
             let mut owned_response = Some(response);
-            z_closure_reply_call(
-                z_closure_reply_loan(&callback),
+            callback.call(
                 owned_response
                     .as_mut()
                     .unwrap_unchecked()
@@ -404,7 +420,7 @@ pub extern "C" fn z_querier_drop(this: &mut z_moved_querier_t) {
 #[no_mangle]
 pub extern "C" fn z_undeclare_querier(this_: &mut z_moved_querier_t) -> result::z_result_t {
     if let Some(q) = this_.take_rust_type() {
-        if let Err(e) = q.undeclare().wait() {
+        if let Err(e) = q.querier.undeclare().wait() {
             tracing::error!("{}", e);
             return result::Z_ENETWORK;
         }
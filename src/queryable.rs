@@ -11,11 +11,22 @@
 // Contributors:
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
-use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::ffi::CStr;
+use std::{
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
+#[cfg(feature = "unstable")]
+use libc::{c_char, c_void};
 use zenoh::{
     bytes::Encoding,
-    handlers::Callback,
+    handlers::{Callback, DefaultHandler},
     qos::{CongestionControl, Priority},
     query::{Query, Queryable, QueryableBuilder},
     Wait,
@@ -32,9 +43,11 @@ use crate::{
 };
 #[cfg(feature = "unstable")]
 use crate::{
-    transmute::IntoCType, z_entity_global_id_t, z_moved_source_info_t, zc_locality_default,
-    zc_locality_t,
+    transmute::IntoCType, z_entity_global_id_t, z_moved_source_info_t,
+    z_owned_fifo_handler_query_t, zc_locality_default, zc_locality_t, QueryFifoHandler,
 };
+#[cfg(feature = "unstable")]
+use zenoh::{bytes::ZBytes, key_expr::KeyExpr};
 decl_c_type!(
     owned(z_owned_queryable_t, option Queryable<()>),
     loaned(z_loaned_queryable_t),
@@ -57,9 +70,101 @@ pub unsafe extern "C" fn z_queryable_loan(this_: &z_owned_queryable_t) -> &z_loa
         .as_loaned_c_type_ref()
 }
 
+/// Decrements the number of pending queries of a queryable once the last copy of a query is dropped.
+struct PendingQuery(Arc<AtomicUsize>);
+
+impl Drop for PendingQuery {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A query along with the guard accounting for it in the pending queries of its queryable, if it has a limit.
+#[derive(Clone)]
+pub struct CQuery {
+    query: Query,
+    _pending: Option<Arc<PendingQuery>>,
+}
+
+impl CQuery {
+    fn empty() -> Self {
+        Query::empty().into()
+    }
+}
+
+impl From<Query> for CQuery {
+    fn from(query: Query) -> Self {
+        CQuery {
+            query,
+            _pending: None,
+        }
+    }
+}
+
+impl Deref for CQuery {
+    type Target = Query;
+
+    fn deref(&self) -> &Query {
+        &self.query
+    }
+}
+
+impl DerefMut for CQuery {
+    fn deref_mut(&mut self) -> &mut Query {
+        &mut self.query
+    }
+}
+
+/// Admits incoming queries while the number of pending ones stays below `max` (no limit if `max` is 0),
+/// finalizing the others with an error reply.
+struct QueryAdmission {
+    max: usize,
+    pending: Arc<AtomicUsize>,
+    rejected: AtomicUsize,
+}
+
+impl QueryAdmission {
+    fn new(max: usize) -> Self {
+        QueryAdmission {
+            max,
+            pending: Arc::new(AtomicUsize::new(0)),
+            rejected: AtomicUsize::new(0),
+        }
+    }
+
+    fn admit(&self, query: Query) -> Option<CQuery> {
+        if self.max == 0 {
+            return Some(query.into());
+        }
+        let admitted = self
+            .pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.max).then_some(n + 1)
+            })
+            .is_ok();
+        if admitted {
+            return Some(CQuery {
+                query,
+                _pending: Some(Arc::new(PendingQuery(self.pending.clone()))),
+            });
+        }
+        let rejected = self.rejected.fetch_add(1, Ordering::Relaxed) + 1;
+        tracing::warn!(
+            "Rejecting query on {}: {} queries are already pending ({} rejected so far)",
+            query.key_expr(),
+            self.max,
+            rejected
+        );
+        if let Err(e) = query.reply_err("too many pending queries").wait() {
+            tracing::error!("{}", e);
+        }
+        None
+    }
+}
+
 pub use crate::opaque_types::{z_loaned_query_t, z_moved_query_t, z_owned_query_t};
 decl_c_type!(
-    owned(z_owned_query_t, option Query),
+    owned(z_owned_query_t, option CQuery),
     loaned(z_loaned_query_t),
 );
 
@@ -107,7 +212,7 @@ pub unsafe extern "C" fn z_query_take_from_loaned(
 ) {
     let dst = dst.as_rust_type_mut_uninit();
     let src = src.as_rust_type_mut();
-    let src = std::mem::replace(src, Query::empty());
+    let src = std::mem::replace(src, CQuery::empty());
     dst.write(Some(src));
 }
 
@@ -118,6 +223,10 @@ pub extern "C" fn z_query_drop(this_: &mut z_moved_query_t) {
 }
 /// Constructs a shallow copy of the query, allowing to keep it in an "open" state past the callback's return.
 ///
+/// The copy can be moved to another thread and answered later with `z_query_reply()`. Note that every open query keeps
+/// the querier waiting for the final reply and retains its parameters, payload and attachment in memory: copies should be
+/// dropped as soon as they are answered, and holding many of them open at once should be avoided.
+///
 /// This operation is infallible, but may return a gravestone value if `query` itself was a gravestone value (which cannot be the case in a callback).
 #[no_mangle]
 pub extern "C" fn z_query_clone(dst: &mut MaybeUninit<z_owned_query_t>, this_: &z_loaned_query_t) {
@@ -129,15 +238,23 @@ pub extern "C" fn z_query_clone(dst: &mut MaybeUninit<z_owned_query_t>, this_: &
 #[allow(non_camel_case_types)]
 #[repr(C)]
 pub struct z_queryable_options_t {
-    /// The completeness of the Queryable.
+    /// The completeness of the Queryable. A complete queryable advertises that it is able to answer
+    /// any query for its whole key expression, so that queries with `Z_QUERY_TARGET_ALL_COMPLETE` target
+    /// can be routed to it. Default is ``false``.
     pub complete: bool,
     #[cfg(feature = "unstable")]
     /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
     /// Restricts the matching requests that will be received by this Queryable to the ones
     /// that have the compatible allowed_destination.
     pub allowed_origin: zc_locality_t,
+    #[cfg(feature = "unstable")]
+    /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+    /// The maximal number of queries that can be pending at once, i.e. received by the Queryable but not yet finalized
+    /// by dropping them (and all their copies). Queries received past this limit are immediately finalized with an error reply,
+    /// without being passed to the callback. Default is 0, meaning no limit.
+    pub max_pending_queries: usize,
 }
-/// Constructs the default value for `z_query_reply_options_t`.
+/// Constructs the default value for `z_queryable_options_t`.
 #[no_mangle]
 #[allow(clippy::missing_safety_doc)]
 pub extern "C" fn z_queryable_options_default(this_: &mut MaybeUninit<z_queryable_options_t>) {
@@ -145,6 +262,8 @@ pub extern "C" fn z_queryable_options_default(this_: &mut MaybeUninit<z_queryabl
         complete: false,
         #[cfg(feature = "unstable")]
         allowed_origin: zc_locality_default(),
+        #[cfg(feature = "unstable")]
+        max_pending_queries: 0,
     });
 }
 
@@ -245,24 +364,39 @@ pub extern "C" fn z_query_reply_del_options_default(
     });
 }
 
-fn _declare_queryable_inner<'a, 'b>(
+fn _queryable_builder<'a, 'b>(
     session: &'a z_loaned_session_t,
     key_expr: &'b z_loaned_keyexpr_t,
-    callback: &mut z_moved_closure_query_t,
     options: Option<&mut z_queryable_options_t>,
-) -> QueryableBuilder<'a, 'b, Callback<Query>> {
+) -> (QueryableBuilder<'a, 'b, DefaultHandler>, QueryAdmission) {
     let session = session.as_rust_type_ref();
     let keyexpr = key_expr.as_rust_type_ref();
-    let callback = callback.take_rust_type();
     let mut builder = session.declare_queryable(keyexpr);
+    #[allow(unused_mut)]
+    let mut max_pending_queries = 0;
     if let Some(options) = options {
         builder = builder.complete(options.complete);
         #[cfg(feature = "unstable")]
         {
-            builder = builder.allowed_origin(options.allowed_origin.into())
+            builder = builder.allowed_origin(options.allowed_origin.into());
+            max_pending_queries = options.max_pending_queries;
         }
     }
+    (builder, QueryAdmission::new(max_pending_queries))
+}
+
+fn _declare_queryable_inner<'a, 'b>(
+    session: &'a z_loaned_session_t,
+    key_expr: &'b z_loaned_keyexpr_t,
+    callback: &mut z_moved_closure_query_t,
+    options: Option<&mut z_queryable_options_t>,
+) -> QueryableBuilder<'a, 'b, Callback<Query>> {
+    let callback = callback.take_rust_type();
+    let (builder, admission) = _queryable_builder(session, key_expr, options);
     let queryable = builder.callback(move |query| {
+        let Some(query) = admission.admit(query) else {
+            return;
+        };
         let mut owned_query = Some(query);
         z_closure_query_call(z_closure_query_loan(&callback), unsafe {
             owned_query
@@ -306,6 +440,55 @@ pub extern "C" fn z_declare_queryable(
     }
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a Queryable for the given key expression, buffering incoming queries into a fifo handler.
+///
+/// This is a shortcut for creating a fifo channel with `z_fifo_channel_query_new()` and passing its send end to
+/// `z_declare_queryable()`: queries can then be received from `handler` and answered by a worker thread at its own pace.
+/// Queries still present in the handler when it is dropped are finalized without any further reply.
+///
+/// @param session: A Zenoh session.
+/// @param queryable: An uninitialized memory location where queryable will be constructed.
+/// @param handler: An uninitialized memory location where the fifo handler receiving the queries will be constructed.
+/// @param key_expr: The key expression the Queryable will reply to.
+/// @param capacity: The capacity of the fifo channel. Once it is full, the delivery of new queries blocks until some are received.
+/// @param options: Options for the queryable.
+///
+/// @return 0 in case of success, negative error code otherwise (in this case both `queryable` and `handler` are in gravestone state).
+#[no_mangle]
+pub extern "C" fn z_declare_queryable_with_handler(
+    session: &z_loaned_session_t,
+    queryable: &mut MaybeUninit<z_owned_queryable_t>,
+    handler: &mut MaybeUninit<z_owned_fifo_handler_query_t>,
+    key_expr: &z_loaned_keyexpr_t,
+    capacity: usize,
+    options: Option<&mut z_queryable_options_t>,
+) -> result::z_result_t {
+    let this = queryable.as_rust_type_mut_uninit();
+    let handler = handler.as_rust_type_mut_uninit();
+    let (callback, h) = QueryFifoHandler::new(capacity);
+    let (builder, admission) = _queryable_builder(session, key_expr, options);
+    let builder = builder.callback(move |query| {
+        if let Some(query) = admission.admit(query) {
+            callback(query)
+        }
+    });
+    match builder.wait() {
+        Ok(q) => {
+            this.write(Some(q));
+            handler.write(Some(h));
+            result::Z_OK
+        }
+        Err(e) => {
+            tracing::error!("{}", e);
+            this.write(None);
+            handler.write(None);
+            result::Z_EGENERIC
+        }
+    }
+}
+
 /// Declares a background queryable for a given keyexpr. The queryable callback will be be called
 /// to proccess incoming queries until the corresponding session is closed or dropped.
 ///
@@ -361,6 +544,11 @@ pub extern "C" fn z_queryable_id(queryable: &z_loaned_queryable_t) -> z_entity_g
 /// be called multiple times to send multiple replies to a query. The reply
 /// will be considered complete when the Queryable callback returns.
 ///
+/// To reply after the callback returns (e.g. from another thread), keep the query open with `z_query_clone()`:
+/// the query stays answerable until the last owned copy is dropped, at which point the reply is finalized.
+/// Zenoh does not notify the queryable when the querier stops waiting (e.g. on query timeout): replies sent
+/// after that are silently discarded by the querier, so such late replies are harmless.
+///
 /// @param this_: The query to reply to.
 /// @param key_expr: The key of this reply.
 /// @param payload: The payload of this reply. Will be consumed.
@@ -405,6 +593,127 @@ pub extern "C" fn z_query_reply(
     result::Z_OK
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief An entry of a batch of replies, sent via `z_query_reply_many()`.
+#[repr(C)]
+pub struct z_query_reply_entry_t {
+    /// The key of this reply.
+    pub key_expr: &'static z_loaned_keyexpr_t,
+    /// The payload of this reply.
+    pub payload: &'static mut z_moved_bytes_t,
+    /// The encoding of the reply payload.
+    pub encoding: Option<&'static mut z_moved_encoding_t>,
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Sends a batch of replies to a query.
+///
+/// This is equivalent to calling `z_query_reply()` with default options for each entry, but with a single call
+/// across the FFI boundary, which is more efficient when answering e.g. a wildcard query with many stored values.
+/// Replies are sent in order. If sending one of them fails, the following ones are not sent.
+/// All payloads and encodings are consumed, including the ones of replies which were not sent.
+///
+/// @param this_: The query to reply to.
+/// @param entries: Pointer to an array of `len` reply entries.
+/// @param len: The number of entries.
+///
+/// @return 0 in case of success, negative error code otherwise.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn z_query_reply_many(
+    this: &z_loaned_query_t,
+    entries: *mut z_query_reply_entry_t,
+    len: usize,
+) -> result::z_result_t {
+    if entries.is_null() || len == 0 {
+        return result::Z_OK;
+    }
+    let query = this.as_rust_type_ref();
+    let entries = std::slice::from_raw_parts_mut(entries, len);
+    let mut res = result::Z_OK;
+    for entry in entries.iter_mut() {
+        // Always take the payload and encoding, so that the ones following a failure are consumed too.
+        let payload = entry.payload.take_rust_type();
+        let encoding = entry.encoding.take().map(|e| e.take_rust_type());
+        if res != result::Z_OK {
+            continue;
+        }
+        let mut reply = query.reply(entry.key_expr.as_rust_type_ref(), payload);
+        if let Some(encoding) = encoding {
+            reply = reply.encoding(encoding);
+        }
+        if let Err(e) = reply.wait() {
+            tracing::error!("{}", e);
+            res = result::Z_EGENERIC;
+        }
+    }
+    res
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Sends a reply to a query for each (key expression, payload) pair produced by a user iterator.
+///
+/// This is equivalent to calling `z_query_reply()` with default options for each pair, but the pairs are pulled one at a time,
+/// so that e.g. a storage answering a wildcard query can stream the stored values without building an intermediate array.
+/// Iteration stops as soon as `next` returns ``false`` or a reply fails to be sent.
+///
+/// @param this_: The query to reply to.
+/// @param next: The iterator function. On each call it should either set the null-terminated key expression, the payload
+/// and the payload length of the next reply and return ``true``, or return ``false`` if there are no more replies.
+/// The returned key expression and payload are copied, they only need to stay valid until the next call.
+/// @param context: The context passed to `next`.
+///
+/// @return 0 in case of success, `Z_EINVAL` if `next` produced an invalid key expression, negative error code otherwise.
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn z_query_reply_from_iterator(
+    this: &z_loaned_query_t,
+    next: extern "C" fn(
+        context: *mut c_void,
+        key_expr: &mut *const c_char,
+        payload: &mut *const u8,
+        len: &mut usize,
+    ) -> bool,
+    context: *mut c_void,
+) -> result::z_result_t {
+    let query = this.as_rust_type_ref();
+    loop {
+        let mut key_expr: *const c_char = std::ptr::null();
+        let mut payload: *const u8 = std::ptr::null();
+        let mut len = 0;
+        if !next(context, &mut key_expr, &mut payload, &mut len) {
+            return result::Z_OK;
+        }
+        if key_expr.is_null() {
+            tracing::error!("The reply iterator produced a null key expression");
+            return result::Z_EINVAL;
+        }
+        let Ok(key_expr) = CStr::from_ptr(key_expr).to_str() else {
+            tracing::error!("The reply iterator produced a non UTF-8 key expression");
+            return result::Z_EINVAL;
+        };
+        let key_expr = match KeyExpr::try_from(key_expr) {
+            Ok(key_expr) => key_expr,
+            Err(e) => {
+                tracing::error!("{}", e);
+                return result::Z_EINVAL;
+            }
+        };
+        let payload = if payload.is_null() || len == 0 {
+            ZBytes::new()
+        } else {
+            ZBytes::from(std::slice::from_raw_parts(payload, len).to_vec())
+        };
+        if let Err(e) = query.reply(key_expr, payload).wait() {
+            tracing::error!("{}", e);
+            return result::Z_EGENERIC;
+        }
+    }
+}
+
 /// Sends a error reply to a query.
 ///
 /// This function must be called inside of a Queryable callback passing the
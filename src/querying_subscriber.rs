@@ -153,7 +153,7 @@ pub unsafe extern "C" fn ze_declare_querying_subscriber(
     let sub = _declare_querying_subscriber_inner(session, key_expr, callback, options);
     match sub.wait() {
         Ok(sub) => {
-            let session = session.as_rust_type_ref();
+            let session: &'static Session = session.as_rust_type_ref();
             this.write(Some((sub, session)));
             result::Z_OK
         }
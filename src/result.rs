@@ -27,6 +27,9 @@ pub const Z_EUNAVAILABLE: z_result_t = -6;
 pub const Z_EDESERIALIZE: z_result_t = -7;
 pub const Z_ESESSION_CLOSED: z_result_t = -8;
 pub const Z_EUTF8: z_result_t = -9;
+pub const Z_ETIMEOUT: z_result_t = -10;
+pub const Z_ETASK_SPAWN: z_result_t = -12;
+pub const Z_ETASK_PANIC: z_result_t = -13;
 // negative pthread error codes (due to convention to return negative values on error)
 pub const Z_EBUSY_MUTEX: z_result_t = -16;
 pub const Z_EINVAL_MUTEX: z_result_t = -22;
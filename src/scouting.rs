@@ -176,6 +176,10 @@ pub extern "C" fn z_scout_options_default(this_: &mut MaybeUninit<z_scout_option
 
 /// Scout for routers and/or peers.
 ///
+/// The function blocks for the scouting duration (`z_scout_options_t::timeout_ms`), then drops the callback.
+/// To process hello messages in a pull-based fashion, pass a callback constructed with `z_fifo_channel_hello_new()`
+/// or `z_ring_channel_hello_new()`: the handler can then be drained once the function returns.
+///
 /// @param config: A set of properties to configure scouting session.
 /// @param callback: A closure that will be called on each hello message received from discoverd Zenoh entities.
 /// @param options: A set of scouting options
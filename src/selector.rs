@@ -0,0 +1,313 @@
+//
+// Copyright (c) 2017, 2024 ZettaScale Technology.
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh team, <zenoh@zettascale.tech>
+//
+
+use std::{ffi::CStr, fmt::Write, mem::MaybeUninit};
+
+use libc::c_char;
+use zenoh::query::Selector;
+
+pub use crate::opaque_types::{z_loaned_selector_t, z_moved_selector_t, z_owned_selector_t};
+use crate::{
+    result,
+    transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
+    z_loaned_keyexpr_t, z_owned_string_t, z_view_string_from_substr, z_view_string_t,
+};
+decl_c_type!(
+    owned(z_owned_selector_t, option Selector<'static>),
+    loaned(z_loaned_selector_t),
+);
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a selector from a key expression and parameters.
+///
+/// @param this_: An uninitialized memory location where selector will be constructed.
+/// @param key_expr: The key expression of the selector.
+/// @param parameters: A null-terminated string with the selector parameters, similar to a url's query segment. Can be `NULL`.
+/// @return 0 in case of success, negative error code otherwise (in this case selector will be in its gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_selector_new(
+    this_: &mut MaybeUninit<z_owned_selector_t>,
+    key_expr: &z_loaned_keyexpr_t,
+    parameters: *const c_char,
+) -> result::z_result_t {
+    let this = this_.as_rust_type_mut_uninit();
+    let p = if parameters.is_null() {
+        ""
+    } else {
+        match CStr::from_ptr(parameters).to_str() {
+            Ok(p) => p,
+            Err(e) => {
+                tracing::error!("Invalid selector parameters: {}", e);
+                this.write(None);
+                return result::Z_EINVAL;
+            }
+        }
+    };
+    let key_expr = key_expr.as_rust_type_ref().clone();
+    this.write(Some(Selector::from((key_expr, p.to_owned()))));
+    result::Z_OK
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns the key expression of the selector.
+#[no_mangle]
+pub extern "C" fn z_selector_keyexpr(this_: &z_loaned_selector_t) -> &z_loaned_keyexpr_t {
+    this_.as_rust_type_ref().key_expr().as_loaned_c_type_ref()
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Gets the selector parameters.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_selector_parameters(
+    this_: &z_loaned_selector_t,
+    parameters: &mut MaybeUninit<z_view_string_t>,
+) {
+    let params = this_.as_rust_type_ref().parameters().as_str();
+    unsafe { z_view_string_from_substr(parameters, params.as_ptr() as _, params.len()) };
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Formats the selector into a string of the form `key_expr?parameters`.
+#[no_mangle]
+pub extern "C" fn z_selector_to_string(
+    this_: &z_loaned_selector_t,
+    dst: &mut MaybeUninit<z_owned_string_t>,
+) {
+    dst.as_rust_type_mut_uninit()
+        .write(this_.as_rust_type_ref().to_string().into());
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a copy of the selector.
+#[no_mangle]
+pub extern "C" fn z_selector_clone(
+    dst: &mut MaybeUninit<z_owned_selector_t>,
+    this_: &z_loaned_selector_t,
+) {
+    dst.as_rust_type_mut_uninit()
+        .write(Some(this_.as_rust_type_ref().clone()));
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Borrows selector.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_selector_loan(this_: &z_owned_selector_t) -> &z_loaned_selector_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs selector in its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_selector_null(this_: &mut MaybeUninit<z_owned_selector_t>) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if selector is valid, ``false`` if it is in gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_selector_check(this_: &z_owned_selector_t) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Frees memory and resets selector to its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_selector_drop(this_: &mut z_moved_selector_t) {
+    let _ = this_.take_rust_type();
+}
+
+pub use crate::opaque_types::{
+    z_loaned_query_parameters_builder_t, z_moved_query_parameters_builder_t,
+    z_owned_query_parameters_builder_t,
+};
+decl_c_type!(
+    owned(z_owned_query_parameters_builder_t, option String),
+    loaned(z_loaned_query_parameters_builder_t),
+);
+
+/// Percent-encodes the characters having a special meaning in selector parameters (`;`, `=`, `|`, `&`, `?`, `#`, `%`),
+/// as well as space, control and non-ascii characters.
+fn percent_encode_parameter(s: &str, dst: &mut String) {
+    for b in s.bytes() {
+        if b.is_ascii_graphic() && !b";=|&?#%".contains(&b) {
+            dst.push(b as char);
+        } else {
+            let _ = write!(dst, "%{:02X}", b);
+        }
+    }
+}
+
+unsafe fn query_parameters_builder_add(
+    this: &mut z_loaned_query_parameters_builder_t,
+    key: *const c_char,
+    value: Option<&str>,
+) -> result::z_result_t {
+    let key = match (!key.is_null()).then(|| CStr::from_ptr(key).to_str()) {
+        Some(Ok(key)) if !key.is_empty() => key,
+        Some(Err(e)) => {
+            tracing::error!("Invalid parameter key: {}", e);
+            return result::Z_EINVAL;
+        }
+        _ => {
+            tracing::error!("Parameter key should not be empty");
+            return result::Z_EINVAL;
+        }
+    };
+    let params = this.as_rust_type_mut();
+    if !params.is_empty() {
+        params.push(';');
+    }
+    percent_encode_parameter(key, params);
+    if let Some(value) = value {
+        params.push('=');
+        percent_encode_parameter(value, params);
+    }
+    result::Z_OK
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs an empty query parameters builder.
+///
+/// The builder assembles a well-formed parameters string (to be passed to e.g. `z_get()` or `z_selector_new()`) from typed fields,
+/// percent-encoding the characters which have a special meaning in parameters. Queryables should percent-decode the values they read.
+#[no_mangle]
+pub extern "C" fn z_query_parameters_builder_empty(
+    this_: &mut MaybeUninit<z_owned_query_parameters_builder_t>,
+) {
+    this_.as_rust_type_mut_uninit().write(Some(String::new()));
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Appends a `key=value` parameter with a string value.
+///
+/// @param this_: The query parameters builder.
+/// @param key: A non-empty null-terminated string with the parameter key.
+/// @param value: A null-terminated string with the parameter value.
+/// @return 0 in case of success, `Z_EINVAL` if `key` is empty or if `key` or `value` is not a valid UTF-8 string.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_query_parameters_builder_add_str(
+    this_: &mut z_loaned_query_parameters_builder_t,
+    key: *const c_char,
+    value: *const c_char,
+) -> result::z_result_t {
+    let value = match (!value.is_null()).then(|| CStr::from_ptr(value).to_str()) {
+        Some(Ok(value)) => value,
+        Some(Err(e)) => {
+            tracing::error!("Invalid parameter value: {}", e);
+            return result::Z_EINVAL;
+        }
+        None => "",
+    };
+    query_parameters_builder_add(this_, key, Some(value))
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Appends a `key=value` parameter with an integer value.
+///
+/// @return 0 in case of success, `Z_EINVAL` if `key` is empty or is not a valid UTF-8 string.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_query_parameters_builder_add_int(
+    this_: &mut z_loaned_query_parameters_builder_t,
+    key: *const c_char,
+    value: i64,
+) -> result::z_result_t {
+    query_parameters_builder_add(this_, key, Some(&value.to_string()))
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Appends a flag parameter, i.e. a key without value.
+///
+/// @return 0 in case of success, `Z_EINVAL` if `key` is empty or is not a valid UTF-8 string.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_query_parameters_builder_add_flag(
+    this_: &mut z_loaned_query_parameters_builder_t,
+    key: *const c_char,
+) -> result::z_result_t {
+    query_parameters_builder_add(this_, key, None)
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Finishes building, consuming the builder and constructing a string with the assembled parameters.
+#[no_mangle]
+pub extern "C" fn z_query_parameters_builder_finish(
+    this_: &mut z_moved_query_parameters_builder_t,
+    parameters: &mut MaybeUninit<z_owned_string_t>,
+) {
+    parameters
+        .as_rust_type_mut_uninit()
+        .write(this_.take_rust_type().unwrap_or_default().into());
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Borrows query parameters builder.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_query_parameters_builder_loan(
+    this_: &z_owned_query_parameters_builder_t,
+) -> &z_loaned_query_parameters_builder_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Mutably borrows query parameters builder.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_query_parameters_builder_loan_mut(
+    this_: &mut z_owned_query_parameters_builder_t,
+) -> &mut z_loaned_query_parameters_builder_t {
+    this_
+        .as_rust_type_mut()
+        .as_mut()
+        .unwrap_unchecked()
+        .as_loaned_c_type_mut()
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs query parameters builder in its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_query_parameters_builder_null(
+    this_: &mut MaybeUninit<z_owned_query_parameters_builder_t>,
+) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if query parameters builder is valid, ``false`` if it is in gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_query_parameters_builder_check(
+    this_: &z_owned_query_parameters_builder_t,
+) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Drops query parameters builder and resets it to its gravestone state.
+#[no_mangle]
+pub extern "C" fn z_query_parameters_builder_drop(this_: &mut z_moved_query_parameters_builder_t) {
+    let _ = this_.take_rust_type();
+}
@@ -12,22 +12,72 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::{
+    ffi::c_void,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+use std::{mem::MaybeUninit, ops::Deref};
 
+#[cfg(feature = "unstable")]
+use zenoh::sample::{Locality, SampleKind};
 use zenoh::{Session, Wait};
 
 #[cfg(all(feature = "shared-memory", feature = "unstable"))]
 use crate::z_loaned_shm_client_storage_t;
 #[cfg(feature = "unstable")]
-use crate::zc_owned_concurrent_close_handle_t;
+use crate::{
+    get::PendingGets, z_internal_string_null, z_owned_string_t, z_string_copy_from_substr,
+    zc_owned_concurrent_close_handle_t,
+};
 use crate::{
     opaque_types::{z_loaned_session_t, z_owned_session_t},
     result,
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_moved_config_t, z_moved_session_t,
 };
+/// A session, along with the get operations issued on it that can be aborted with `z_session_abort_pending()`.
+pub struct CSession {
+    session: Session,
+    #[cfg(feature = "unstable")]
+    pending_gets: Arc<PendingGets>,
+}
+
+impl From<Session> for CSession {
+    fn from(session: Session) -> Self {
+        CSession {
+            session,
+            #[cfg(feature = "unstable")]
+            pending_gets: Arc::new(PendingGets::default()),
+        }
+    }
+}
+
+impl Deref for CSession {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        &self.session
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl CSession {
+    pub(crate) fn pending_gets(&self) -> &Arc<PendingGets> {
+        &self.pending_gets
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for CSession {
+    fn drop(&mut self) {
+        self.pending_gets.abort();
+    }
+}
+
 decl_c_type!(
-    owned(z_owned_session_t, option Session),
+    owned(z_owned_session_t, option CSession),
     loaned(z_loaned_session_t),
 );
 
@@ -76,6 +126,11 @@ pub extern "C" fn z_open_options_default(this_: &mut MaybeUninit<z_open_options_
 
 /// Constructs and opens a new Zenoh session.
 ///
+/// All the sessions of a process share the same set of async runtimes, so opening more sessions does not spawn more worker threads.
+/// The number of threads of these runtimes can not be set per session: it is configured once per process with the `ZENOH_RUNTIME`
+/// environment variable, which must be set before the first session is opened, e.g.
+/// `ZENOH_RUNTIME='(app: (worker_threads: 2), rx: (worker_threads: 1), tx: (worker_threads: 1))'`.
+///
 /// @return 0 in case of success, negative error code otherwise (in this case the session will be in its gravestone state).
 #[allow(clippy::missing_safety_doc)]
 #[no_mangle]
@@ -92,7 +147,7 @@ pub extern "C" fn z_open(
     };
     match zenoh::open(config).wait() {
         Ok(s) => {
-            this.write(Some(s));
+            this.write(Some(s.into()));
             result::Z_OK
         }
         Err(e) => {
@@ -126,7 +181,7 @@ pub extern "C" fn z_open_with_custom_shm_clients(
         .wait()
     {
         Ok(s) => {
-            this.write(Some(s));
+            this.write(Some(s.into()));
             result::Z_OK
         }
         Err(e) => {
@@ -186,8 +241,11 @@ pub extern "C" fn z_close(
     session: &mut z_loaned_session_t,
     #[allow(unused)] options: Option<&mut z_close_options_t>,
 ) -> result::z_result_t {
+    let session = session.as_rust_type_mut();
+    #[cfg(feature = "unstable")]
+    session.pending_gets.abort();
     #[allow(unused_mut)]
-    let mut close_builder = session.as_rust_type_mut().close();
+    let mut close_builder = session.close();
 
     #[cfg(feature = "unstable")]
     if let Some(options) = options {
@@ -213,6 +271,65 @@ pub extern "C" fn z_close(
     }
 }
 
+/// The completion callback of `z_close_async()`.
+#[cfg(feature = "unstable")]
+struct CloseCallback {
+    on_closed: extern "C" fn(result: result::z_result_t, context: *mut c_void),
+    context: *mut c_void,
+}
+
+#[cfg(feature = "unstable")]
+unsafe impl Send for CloseCallback {}
+
+#[cfg(feature = "unstable")]
+impl CloseCallback {
+    fn call(self, result: result::z_result_t) {
+        (self.on_closed)(result, self.context)
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Initiates the closure of Zenoh session without blocking, calling `on_closed` once it is complete.
+///
+/// The session is closed on the Zenoh runtime, with the same effects as `z_close()`. Until `on_closed` is called the session
+/// should be treated as closing: no new operations should be issued on it. It can however be dropped before the closure
+/// completes, in which case the closure carries on.
+///
+/// @param session: The session to close.
+/// @param on_closed: The callback called from a runtime thread once the session is closed, with `0` in case of success or
+/// a negative value if an error occured while closing the session. Can be NULL.
+/// @param context: The context passed to `on_closed`.
+/// @return `0` if the closure was initiated, `Z_EGENERIC` if the session is already closed (in this case `on_closed` is not called).
+#[no_mangle]
+pub extern "C" fn z_close_async(
+    session: &z_loaned_session_t,
+    on_closed: Option<extern "C" fn(result: result::z_result_t, context: *mut c_void)>,
+    context: *mut c_void,
+) -> result::z_result_t {
+    let session = session.as_rust_type_ref();
+    if session.is_closed() {
+        tracing::error!("The session is already closed");
+        return result::Z_EGENERIC;
+    }
+    session.pending_gets.abort();
+    let session = session.session.clone();
+    let callback = on_closed.map(|on_closed| CloseCallback { on_closed, context });
+    zenoh_runtime::ZRuntime::Application.spawn(async move {
+        let result = match session.close().await {
+            Ok(_) => result::Z_OK,
+            Err(e) => {
+                tracing::error!("Error closing session: {}", e);
+                result::Z_EGENERIC
+            }
+        };
+        if let Some(callback) = callback {
+            callback.call(result);
+        }
+    });
+    result::Z_OK
+}
+
 /// Checks if zenoh session is closed.
 ///
 /// @return `true` if session is closed, `false` otherwise.
@@ -222,6 +339,142 @@ pub extern "C" fn z_session_is_closed(session: &z_loaned_session_t) -> bool {
     s.is_closed()
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Aborts all the queries issued by `z_get()`, `z_liveliness_get()` and `z_querier_get()` on this session that are still
+/// waiting for replies.
+///
+/// The reply callbacks of these queries are dropped without waiting for the remaining replies or for the query timeout.
+/// In particular, the corresponding reply channels get disconnected, so any blocking `recv` on their handlers returns.
+/// They are also aborted by `z_close()`, `z_close_async()` and when the session is dropped, which makes the session shutdown
+/// deterministic.
+///
+/// This function does not wait for the reply callbacks that are currently running: such a callback is dropped once its running
+/// calls return. Hence it can be called from within a reply callback, or while a reply callback is blocked on a full channel.
+#[no_mangle]
+pub extern "C" fn z_session_abort_pending(session: &z_loaned_session_t) {
+    session.as_rust_type_ref().pending_gets.abort();
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Blocks until the session has at least one established transport (to a router or a peer), or until the timeout expires.
+///
+/// This can be used after `z_open()` to make sure that the first publications are not lost because no link is up yet.
+/// Transport establishment is observed through the session admin space, so it should be enabled in the session configuration.
+///
+/// @param session: the session to wait for.
+/// @param timeout_ms: the maximum time to wait in milliseconds.
+/// @return 0 if the session is connected, `Z_ETIMEOUT` if no transport was established before the timeout,
+/// negative error code otherwise.
+#[no_mangle]
+pub extern "C" fn z_session_wait_connected(
+    session: &z_loaned_session_t,
+    timeout_ms: u64,
+) -> result::z_result_t {
+    let session = session.as_rust_type_ref();
+    let state = Arc::new((Mutex::new(false), Condvar::new()));
+    let listener_state = state.clone();
+    // Declare the listener before checking the current transports, so that no transport event can be missed.
+    let listener = match session
+        .declare_subscriber(format!("@/{}/session/transport/unicast/*", session.zid()))
+        .allowed_origin(Locality::SessionLocal)
+        .callback(move |s| {
+            if s.kind() != SampleKind::Put {
+                return;
+            }
+            let (connected, cv) = &*listener_state;
+            *connected.lock().unwrap_or_else(|e| e.into_inner()) = true;
+            cv.notify_all();
+        })
+        .wait()
+    {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to declare connectivity listener: {}", e);
+            return result::Z_EGENERIC;
+        }
+    };
+    let info = session.info();
+    if info.routers_zid().wait().next().is_some() || info.peers_zid().wait().next().is_some() {
+        return result::Z_OK;
+    }
+    let (connected, cv) = &*state;
+    let guard = connected.lock().unwrap_or_else(|e| e.into_inner());
+    let (guard, _) = cv
+        .wait_timeout_while(guard, Duration::from_millis(timeout_ms), |connected| {
+            !*connected
+        })
+        .unwrap_or_else(|e| e.into_inner());
+    let res = if *guard {
+        result::Z_OK
+    } else {
+        result::Z_ETIMEOUT
+    };
+    drop(guard);
+    let _ = listener.undeclare().wait();
+    res
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a json5 string with the effective configuration of the session.
+///
+/// Contrary to the configuration passed to `z_open()`, it includes the values filled by zenoh (defaults, resolved endpoints, etc.)
+/// and the modifications applied since the session was opened. It is intended for diagnostics and reproducing deployments.
+///
+/// @param session: The session.
+/// @param out_config_string: An uninitialized memory location where the string will be constructed.
+/// @return 0 in case of success, negative error code otherwise (in this case the string is set to its gravestone state).
+#[allow(clippy::missing_safety_doc)]
+#[no_mangle]
+pub unsafe extern "C" fn zc_session_config_to_string(
+    session: &z_loaned_session_t,
+    out_config_string: &mut MaybeUninit<z_owned_string_t>,
+) -> result::z_result_t {
+    let config = session.as_rust_type_ref().config().lock();
+    match json5::to_string(&*config) {
+        Ok(s) => {
+            z_string_copy_from_substr(
+                out_config_string,
+                s.as_ptr() as *const libc::c_char,
+                s.len(),
+            );
+            result::Z_OK
+        }
+        Err(e) => {
+            tracing::error!("Session config is not a valid json5: {}", e);
+            z_internal_string_null(out_config_string);
+            result::Z_EPARSE
+        }
+    }
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if the publications of the session are timestamped with its HLC, ``false`` otherwise.
+///
+/// The value is resolved from the `timestamping/enabled` key of the session configuration (see `zc_config_set_timestamping_enabled()`)
+/// for the mode of the session, falling back to zenoh defaults (enabled for routers only).
+#[no_mangle]
+pub extern "C" fn z_session_timestamping_enabled(session: &z_loaned_session_t) -> bool {
+    let config = match serde_json::to_value(&*session.as_rust_type_ref().config().lock()) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!("Failed to serialize session config: {}", e);
+            return false;
+        }
+    };
+    let mode = config["mode"].as_str().unwrap_or("peer");
+    let enabled = &config["timestamping"]["enabled"];
+    let enabled = if enabled.is_object() {
+        &enabled[mode]
+    } else {
+        enabled
+    };
+    enabled.as_bool().unwrap_or(mode == "router")
+}
+
 /// Closes and invalidates the session.
 #[no_mangle]
 pub extern "C" fn z_session_drop(this_: &mut z_moved_session_t) {
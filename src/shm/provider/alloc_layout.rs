@@ -20,7 +20,7 @@ use zenoh::shm::{
 };
 
 use super::{
-    alloc_layout_impl::{alloc, alloc_async, alloc_layout_new},
+    alloc_layout_impl::{alloc, alloc_async, alloc_layout_new, alloc_or_heap},
     shm_provider_backend::DynamicShmProviderBackend,
     types::{z_alloc_alignment_t, z_buf_alloc_result_t},
 };
@@ -30,7 +30,7 @@ use crate::{
     shm::protocol_implementations::posix::posix_shm_provider::PosixAllocLayout,
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_loaned_alloc_layout_t, z_loaned_shm_provider_t, z_moved_alloc_layout_t,
-    z_owned_alloc_layout_t,
+    z_owned_alloc_layout_t, z_owned_bytes_t,
 };
 
 pub type DynamicAllocLayout =
@@ -106,6 +106,34 @@ pub extern "C" fn z_alloc_layout_alloc(
     alloc::<JustAlloc>(out_result, layout);
 }
 
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Make allocation without any additional actions and copy `data` into it, falling back to a heap buffer if the allocation fails.
+///
+/// This allows to publish `data` even if the SHM provider is exhausted, in which case it is sent as a regular payload.
+///
+/// @param out_buf: An uninitialized memory location where the payload will be constructed.
+/// @param layout: The layout to allocate with. Its size must be equal to `len`.
+/// @param data: The data to copy into the payload.
+/// @param len: The length of `data`.
+/// @param out_shm: Will be set to ``true`` if the payload is backed by an SHM buffer, ``false`` if it is backed by a heap buffer.
+/// @return 0 in case of success, `Z_EINVAL` if `len` does not match the layout size (in this case `out_buf` is set to an empty payload).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_alloc_layout_alloc_or_heap(
+    out_buf: &mut MaybeUninit<z_owned_bytes_t>,
+    layout: &z_loaned_alloc_layout_t,
+    data: *const u8,
+    len: usize,
+    out_shm: &mut bool,
+) -> z_result_t {
+    let data = if len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data, len)
+    };
+    alloc_or_heap::<JustAlloc>(out_buf, layout, data, out_shm)
+}
+
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// @brief Make allocation performing garbage collection if needed.
 #[no_mangle]
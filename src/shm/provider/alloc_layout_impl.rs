@@ -16,9 +16,11 @@ use std::mem::MaybeUninit;
 
 use libc::c_void;
 use zenoh::{
+    bytes::ZBytes,
     shm::{
-        AllocLayout, AllocPolicy, AsyncAllocPolicy, DynamicProtocolID, PosixShmProviderBackend,
-        ProtocolIDSource, ShmProviderBackend, StaticProtocolID, POSIX_PROTOCOL_ID,
+        AllocLayout, AllocPolicy, AsyncAllocPolicy, BufAllocResult, DynamicProtocolID,
+        PosixShmProviderBackend, ProtocolIDSource, ShmProviderBackend, StaticProtocolID,
+        POSIX_PROTOCOL_ID,
     },
     Wait,
 };
@@ -32,7 +34,7 @@ use crate::{
     result::{z_result_t, Z_EINVAL, Z_OK},
     shm::provider::types::z_buf_alloc_result_t,
     transmute::{IntoRustType, RustTypeRef, RustTypeRefUninit},
-    z_loaned_alloc_layout_t, z_loaned_shm_provider_t, z_owned_alloc_layout_t,
+    z_loaned_alloc_layout_t, z_loaned_shm_provider_t, z_owned_alloc_layout_t, z_owned_bytes_t,
 };
 
 pub(crate) fn alloc_layout_new(
@@ -86,11 +88,8 @@ pub(crate) fn alloc_layout_new(
     Z_OK
 }
 
-pub(crate) fn alloc<Policy: AllocPolicy>(
-    out_result: &mut MaybeUninit<z_buf_alloc_result_t>,
-    layout: &z_loaned_alloc_layout_t,
-) {
-    let result = match layout.as_rust_type_ref() {
+fn alloc_buf<Policy: AllocPolicy>(layout: &z_loaned_alloc_layout_t) -> BufAllocResult {
+    match layout.as_rust_type_ref() {
         super::alloc_layout::CSHMLayout::Posix(layout) => {
             layout.alloc().with_policy::<Policy>().wait()
         }
@@ -100,8 +99,49 @@ pub(crate) fn alloc<Policy: AllocPolicy>(
         super::alloc_layout::CSHMLayout::DynamicThreadsafe(layout) => {
             layout.alloc().with_policy::<Policy>().wait()
         }
-    };
-    out_result.write(result.into());
+    }
+}
+
+pub(crate) fn alloc<Policy: AllocPolicy>(
+    out_result: &mut MaybeUninit<z_buf_alloc_result_t>,
+    layout: &z_loaned_alloc_layout_t,
+) {
+    out_result.write(alloc_buf::<Policy>(layout).into());
+}
+
+pub(crate) fn alloc_or_heap<Policy: AllocPolicy>(
+    out_buf: &mut MaybeUninit<z_owned_bytes_t>,
+    layout: &z_loaned_alloc_layout_t,
+    data: &[u8],
+    out_shm: &mut bool,
+) -> z_result_t {
+    let out_buf = out_buf.as_rust_type_mut_uninit();
+    *out_shm = false;
+    match alloc_buf::<Policy>(layout) {
+        Ok(mut buf) => {
+            if buf.len() != data.len() {
+                tracing::error!(
+                    "Data length {} does not match the layout size {}",
+                    data.len(),
+                    buf.len()
+                );
+                out_buf.write(ZBytes::default());
+                return Z_EINVAL;
+            }
+            buf.as_mut().copy_from_slice(data);
+            out_buf.write(buf.into());
+            *out_shm = true;
+        }
+        Err(e) => {
+            tracing::debug!(
+                "SHM allocation of {} bytes failed: {:?}, falling back to heap",
+                data.len(),
+                e
+            );
+            out_buf.write(data.to_vec().into());
+        }
+    }
+    Z_OK
 }
 
 pub(crate) fn alloc_async<Policy: AsyncAllocPolicy>(
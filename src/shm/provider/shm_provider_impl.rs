@@ -17,8 +17,9 @@ use std::mem::MaybeUninit;
 use libc::c_void;
 use zenoh::{
     shm::{
-        AllocPolicy, AsyncAllocPolicy, DynamicProtocolID, PosixShmProviderBackend,
-        ProtocolIDSource, ShmProvider, ShmProviderBackend, StaticProtocolID, POSIX_PROTOCOL_ID,
+        AllocPolicy, AsyncAllocPolicy, BufLayoutAllocResult, DynamicProtocolID,
+        PosixShmProviderBackend, ProtocolIDSource, ShmProvider, ShmProviderBackend,
+        StaticProtocolID, POSIX_PROTOCOL_ID,
     },
     Wait,
 };
@@ -60,6 +61,23 @@ pub(crate) fn alloc<Policy: AllocPolicy>(
     }
 }
 
+pub(crate) fn alloc_buf<Policy: AllocPolicy>(
+    provider: &z_loaned_shm_provider_t,
+    size: usize,
+) -> BufLayoutAllocResult {
+    match provider.as_rust_type_ref() {
+        super::shm_provider::CSHMProvider::Posix(provider) => {
+            provider.alloc(size).with_policy::<Policy>().wait()
+        }
+        super::shm_provider::CSHMProvider::Dynamic(provider) => {
+            provider.alloc(size).with_policy::<Policy>().wait()
+        }
+        super::shm_provider::CSHMProvider::DynamicThreadsafe(provider) => {
+            provider.alloc(size).with_policy::<Policy>().wait()
+        }
+    }
+}
+
 pub(crate) fn alloc_async<Policy: AsyncAllocPolicy>(
     out_result: &'static mut MaybeUninit<z_buf_layout_alloc_result_t>,
     provider: &'static z_loaned_shm_provider_t,
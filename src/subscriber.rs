@@ -12,7 +12,15 @@
 //   ZettaScale Zenoh team, <zenoh@zettascale.tech>
 //
 
-use std::mem::MaybeUninit;
+#[cfg(feature = "unstable")]
+use std::{
+    ffi::c_void,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+use std::{mem::MaybeUninit, ops::Deref};
 
 use zenoh::{
     handlers::Callback,
@@ -21,18 +29,51 @@ use zenoh::{
     Wait,
 };
 
+#[cfg(feature = "unstable")]
+pub use crate::opaque_types::{
+    z_loaned_subscriber_group_t, z_moved_subscriber_group_t, z_owned_subscriber_group_t,
+};
 pub use crate::opaque_types::{z_loaned_subscriber_t, z_moved_subscriber_t, z_owned_subscriber_t};
 use crate::{
     keyexpr::*,
     result,
     transmute::{LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
     z_closure_sample_call, z_closure_sample_loan, z_loaned_session_t, z_moved_closure_sample_t,
+    z_owned_closure_sample_t,
 };
 #[cfg(feature = "unstable")]
-use crate::{transmute::IntoCType, z_entity_global_id_t, zc_locality_default, zc_locality_t};
+use crate::{
+    transmute::IntoCType, z_entity_global_id_t, z_loaned_bytes_t, z_loaned_sample_t,
+    z_owned_bytes_t, zc_locality_default, zc_locality_t, PayloadTransform,
+};
+
+/// A subscriber, along with the replaceable callback of the ones declared with `z_declare_subscriber()`.
+pub struct CSubscriber {
+    subscriber: Subscriber<()>,
+    #[cfg(feature = "unstable")]
+    callback: Option<Arc<SubscriberCallback>>,
+}
+
+impl From<Subscriber<()>> for CSubscriber {
+    fn from(subscriber: Subscriber<()>) -> Self {
+        CSubscriber {
+            subscriber,
+            #[cfg(feature = "unstable")]
+            callback: None,
+        }
+    }
+}
+
+impl Deref for CSubscriber {
+    type Target = Subscriber<()>;
+
+    fn deref(&self) -> &Subscriber<()> {
+        &self.subscriber
+    }
+}
 
 decl_c_type!(
-    owned(z_owned_subscriber_t, option Subscriber<()>),
+    owned(z_owned_subscriber_t, option CSubscriber),
     loaned(z_loaned_subscriber_t),
 );
 
@@ -84,16 +125,24 @@ pub extern "C" fn z_subscriber_options_default(this_: &mut MaybeUninit<z_subscri
     this_.write(z_subscriber_options_t::default());
 }
 
-#[allow(unused_variables, unused_mut)]
 pub(crate) fn _declare_subscriber_inner<'a, 'b>(
     session: &'a z_loaned_session_t,
     key_expr: &'b z_loaned_keyexpr_t,
     callback: &mut z_moved_closure_sample_t,
     options: Option<&mut z_subscriber_options_t>,
+) -> SubscriberBuilder<'a, 'b, Callback<Sample>> {
+    _declare_subscriber_with_closure(session, key_expr, callback.take_rust_type(), options)
+}
+
+#[allow(unused_variables, unused_mut)]
+fn _declare_subscriber_with_closure<'a, 'b>(
+    session: &'a z_loaned_session_t,
+    key_expr: &'b z_loaned_keyexpr_t,
+    callback: z_owned_closure_sample_t,
+    options: Option<&mut z_subscriber_options_t>,
 ) -> SubscriberBuilder<'a, 'b, Callback<Sample>> {
     let session = session.as_rust_type_ref();
     let key_expr = key_expr.as_rust_type_ref();
-    let callback = callback.take_rust_type();
     let mut subscriber = session
         .declare_subscriber(key_expr)
         .callback(move |sample| {
@@ -130,10 +179,18 @@ pub extern "C" fn z_declare_subscriber(
     options: Option<&mut z_subscriber_options_t>,
 ) -> result::z_result_t {
     let this = subscriber.as_rust_type_mut_uninit();
-    let s = _declare_subscriber_inner(session, key_expr, callback, options);
+    #[cfg(not(feature = "unstable"))]
+    let callback = callback.take_rust_type();
+    #[cfg(feature = "unstable")]
+    let (callback, replaceable) = replaceable_closure(callback.take_rust_type());
+    let s = _declare_subscriber_with_closure(session, key_expr, callback, options);
     match s.wait() {
         Ok(sub) => {
-            this.write(Some(sub));
+            this.write(Some(CSubscriber {
+                subscriber: sub,
+                #[cfg(feature = "unstable")]
+                callback: Some(replaceable),
+            }));
             result::Z_OK
         }
         Err(e) => {
@@ -199,7 +256,7 @@ pub extern "C" fn z_internal_subscriber_check(this_: &z_owned_subscriber_t) -> b
 #[no_mangle]
 pub extern "C" fn z_undeclare_subscriber(this_: &mut z_moved_subscriber_t) -> result::z_result_t {
     if let Some(s) = this_.take_rust_type() {
-        if let Err(e) = s.undeclare().wait() {
+        if let Err(e) = s.subscriber.undeclare().wait() {
             tracing::error!("{}", e);
             return result::Z_EGENERIC;
         }
@@ -210,7 +267,278 @@ pub extern "C" fn z_undeclare_subscriber(this_: &mut z_moved_subscriber_t) -> re
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// @brief Returns the ID of the subscriber.
+///
+/// The ID is made of the Zenoh ID of the session and of an entity ID unique within this session, so it identifies
+/// the subscriber across nodes. It does not change during the lifetime of the subscriber, which makes it suitable
+/// to correlate logs and traces.
 #[no_mangle]
 pub extern "C" fn z_subscriber_id(subscriber: &z_loaned_subscriber_t) -> z_entity_global_id_t {
     subscriber.as_rust_type_ref().id().into_c_type()
 }
+
+#[cfg(feature = "unstable")]
+struct SubscriberCallbackState {
+    callback: z_owned_closure_sample_t,
+    transform: Option<PayloadTransform>,
+}
+
+/// The callback and payload transform of a subscriber declared with `z_declare_subscriber()`, which can be replaced
+/// with `z_subscriber_replace_handler()` and `z_subscriber_set_transform()` respectively.
+#[cfg(feature = "unstable")]
+struct SubscriberCallback {
+    state: RwLock<SubscriberCallbackState>,
+    rejected: AtomicUsize,
+}
+
+#[cfg(feature = "unstable")]
+impl SubscriberCallback {
+    fn call(&self, sample: &mut z_loaned_sample_t) {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(transform) = state.transform.as_ref() {
+            let sample = sample.as_rust_type_mut();
+            match transform.apply(sample.payload()) {
+                Some(payload) => *sample.payload_mut() = payload,
+                None => {
+                    self.rejected.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+        z_closure_sample_call(z_closure_sample_loan(&state.callback), sample)
+    }
+}
+
+#[cfg(feature = "unstable")]
+impl Drop for SubscriberCallback {
+    fn drop(&mut self) {
+        let rejected = self.rejected.load(Ordering::Relaxed);
+        if rejected > 0 {
+            tracing::debug!("Subscriber transform dropped {} sample(s)", rejected);
+        }
+    }
+}
+
+/// Wraps `callback` into a closure forwarding samples to whichever callback is currently stored in the returned cell.
+#[cfg(feature = "unstable")]
+fn replaceable_closure(
+    callback: z_owned_closure_sample_t,
+) -> (z_owned_closure_sample_t, Arc<SubscriberCallback>) {
+    let replaceable = Arc::new(SubscriberCallback {
+        state: RwLock::new(SubscriberCallbackState {
+            callback,
+            transform: None,
+        }),
+        rejected: AtomicUsize::new(0),
+    });
+    let current = replaceable.clone();
+    let forward = move |sample: &mut z_loaned_sample_t| current.call(sample);
+    (forward.into(), replaceable)
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Replaces the callback of a subscriber, without undeclaring and redeclaring it on the network.
+///
+/// This can be used to swap the send end of a channel (e.g. created by `z_fifo_channel_sample_new()`) when the consumer thread
+/// of the previous channel is restarted. The previous callback is dropped once it is no longer running.
+/// This function must not be called from within the subscriber callback.
+///
+/// @param this_: The subscriber, declared with `z_declare_subscriber()`.
+/// @param callback: The new callback. Will be consumed.
+/// @return 0 in case of success, `Z_EINVAL` if the subscriber was not declared with `z_declare_subscriber()` (in this case `callback` is dropped).
+#[no_mangle]
+pub extern "C" fn z_subscriber_replace_handler(
+    this_: &z_loaned_subscriber_t,
+    callback: &mut z_moved_closure_sample_t,
+) -> result::z_result_t {
+    let callback = callback.take_rust_type();
+    let Some(replaceable) = this_.as_rust_type_ref().callback.as_ref() else {
+        tracing::error!("The subscriber callback can not be replaced");
+        return result::Z_EINVAL;
+    };
+    let previous = std::mem::replace(
+        &mut replaceable
+            .state
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .callback,
+        callback,
+    );
+    std::mem::drop(previous);
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Sets a transform applied to the payload of each sample received by the subscriber, e.g. to decompress or decrypt it.
+///
+/// `transform` is called with the received payload before the sample is passed to the subscriber callback, and should
+/// construct the payload to deliver into `out`, returning ``true``. If it returns ``false``, `out` should be left
+/// uninitialized and the sample is dropped; the number of dropped samples is logged at debug level when the subscriber is dropped.
+/// The transform is removed when the subscriber is dropped or undeclared, or when another transform is set.
+/// This function must not be called from within the subscriber callback.
+///
+/// @param this_: The subscriber, declared with `z_declare_subscriber()`.
+/// @param transform: The payload transform, or NULL to remove the current one.
+/// @param context: The transform context. It should outlive the subscriber.
+/// @return 0 in case of success, `Z_EINVAL` if the subscriber was not declared with `z_declare_subscriber()`.
+#[no_mangle]
+pub extern "C" fn z_subscriber_set_transform(
+    this_: &z_loaned_subscriber_t,
+    transform: Option<
+        extern "C" fn(
+            payload: &z_loaned_bytes_t,
+            out: &mut MaybeUninit<z_owned_bytes_t>,
+            context: *mut c_void,
+        ) -> bool,
+    >,
+    context: *mut c_void,
+) -> result::z_result_t {
+    let Some(replaceable) = this_.as_rust_type_ref().callback.as_ref() else {
+        tracing::error!("The subscriber transform can not be set");
+        return result::Z_EINVAL;
+    };
+    replaceable
+        .state
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .transform = transform.map(|transform| PayloadTransform::new(transform, context));
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+decl_c_type!(
+    owned(z_owned_subscriber_group_t, option Vec<Subscriber<()>>),
+    loaned(z_loaned_subscriber_group_t),
+);
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs a subscriber group in a gravestone state.
+#[no_mangle]
+pub extern "C" fn z_internal_subscriber_group_null(
+    this_: &mut MaybeUninit<z_owned_subscriber_group_t>,
+) {
+    this_.as_rust_type_mut_uninit().write(None);
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns ``true`` if subscriber group is valid, ``false`` otherwise.
+#[no_mangle]
+pub extern "C" fn z_internal_subscriber_group_check(this_: &z_owned_subscriber_group_t) -> bool {
+    this_.as_rust_type_ref().is_some()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Borrows subscriber group.
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_subscriber_group_loan(
+    this_: &z_owned_subscriber_group_t,
+) -> &z_loaned_subscriber_group_t {
+    this_
+        .as_rust_type_ref()
+        .as_ref()
+        .unwrap_unchecked()
+        .as_loaned_c_type_ref()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Undeclares all the subscribers of the group and resets it to its gravestone state.
+/// This is equivalent to calling `z_undeclare_subscriber_group()` and discarding its return value.
+#[no_mangle]
+pub extern "C" fn z_subscriber_group_drop(this_: &mut z_moved_subscriber_group_t) {
+    std::mem::drop(this_.take_rust_type())
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Returns the number of subscribers in the group, i.e. the number of key expressions it was declared with.
+#[no_mangle]
+pub extern "C" fn z_subscriber_group_len(this_: &z_loaned_subscriber_group_t) -> usize {
+    this_.as_rust_type_ref().len()
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Constructs and declares a group of subscribers, one per key expression, sharing the same callback.
+/// Dropping the group undeclares all of its subscribers.
+///
+/// The callback is dropped once all the subscribers of the group are undeclared. It may be called concurrently
+/// for samples received on different key expressions.
+///
+/// @param session: The zenoh session.
+/// @param subscriber_group: An uninitialized location in memory, where subscriber group will be constructed.
+/// @param key_exprs: An array of `len` key expressions to subscribe.
+/// @param len: The number of key expressions.
+/// @param callback: The callback function that will be called each time a data matching any of the subscribed expressions is received.
+/// @param options: The options to be passed to each subscriber declaration.
+///
+/// @return 0 in case of success, `Z_EINVAL` if `key_exprs` is empty, negative error code otherwise
+/// (in this case the subscribers declared so far are undeclared and subscriber group will be in its gravestone state).
+#[no_mangle]
+#[allow(clippy::missing_safety_doc)]
+pub unsafe extern "C" fn z_declare_subscriber_multi(
+    session: &z_loaned_session_t,
+    subscriber_group: &mut MaybeUninit<z_owned_subscriber_group_t>,
+    key_exprs: *const &z_loaned_keyexpr_t,
+    len: usize,
+    callback: &mut z_moved_closure_sample_t,
+    mut options: Option<&mut z_subscriber_options_t>,
+) -> result::z_result_t {
+    let this = subscriber_group.as_rust_type_mut_uninit();
+    let callback = Arc::new(callback.take_rust_type());
+    if key_exprs.is_null() || len == 0 {
+        tracing::error!("A subscriber group requires at least one key expression");
+        this.write(None);
+        return result::Z_EINVAL;
+    }
+    let key_exprs = std::slice::from_raw_parts(key_exprs, len);
+    let mut subscribers = Vec::with_capacity(len);
+    for key_expr in key_exprs {
+        let shared = callback.clone();
+        let forward = move |sample: &mut z_loaned_sample_t| {
+            z_closure_sample_call(z_closure_sample_loan(&shared), sample)
+        };
+        let s = _declare_subscriber_with_closure(
+            session,
+            key_expr,
+            forward.into(),
+            options.as_deref_mut(),
+        );
+        match s.wait() {
+            Ok(sub) => subscribers.push(sub),
+            Err(e) => {
+                tracing::error!("{}", e);
+                this.write(None);
+                return result::Z_EGENERIC;
+            }
+        }
+    }
+    this.write(Some(subscribers));
+    result::Z_OK
+}
+
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// @brief Undeclares all the subscribers of the group.
+///
+/// @return 0 in case of success, negative error code otherwise (all the subscribers are still undeclared, the first error is returned).
+#[no_mangle]
+pub extern "C" fn z_undeclare_subscriber_group(
+    this_: &mut z_moved_subscriber_group_t,
+) -> result::z_result_t {
+    let mut res = result::Z_OK;
+    for s in this_.take_rust_type().into_iter().flatten() {
+        if let Err(e) = s.undeclare().wait() {
+            tracing::error!("{}", e);
+            if res == result::Z_OK {
+                res = result::Z_EGENERIC;
+            }
+        }
+    }
+    res
+}
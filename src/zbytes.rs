@@ -30,6 +30,8 @@ use zenoh::{
 pub use crate::opaque_types::{z_loaned_bytes_t, z_owned_bytes_t};
 #[cfg(all(feature = "shared-memory", feature = "unstable"))]
 use crate::result::Z_ENULL;
+#[cfg(feature = "unstable")]
+use crate::{z_view_string_t, CStringView};
 use crate::{
     result::{self, z_result_t, Z_EINVAL, Z_EIO, Z_OK},
     transmute::{Gravestone, LoanedCTypeRef, RustTypeRef, RustTypeRefUninit, TakeRustType},
@@ -53,6 +55,46 @@ impl Gravestone for ZBytes {
     }
 }
 
+/// A user function transforming payloads, e.g. to compress them before publication or decompress them on reception.
+#[cfg(feature = "unstable")]
+pub(crate) struct PayloadTransform {
+    transform: extern "C" fn(
+        payload: &z_loaned_bytes_t,
+        out: &mut MaybeUninit<z_owned_bytes_t>,
+        context: *mut c_void,
+    ) -> bool,
+    context: *mut c_void,
+}
+
+#[cfg(feature = "unstable")]
+unsafe impl Send for PayloadTransform {}
+#[cfg(feature = "unstable")]
+unsafe impl Sync for PayloadTransform {}
+
+#[cfg(feature = "unstable")]
+impl PayloadTransform {
+    pub(crate) fn new(
+        transform: extern "C" fn(
+            payload: &z_loaned_bytes_t,
+            out: &mut MaybeUninit<z_owned_bytes_t>,
+            context: *mut c_void,
+        ) -> bool,
+        context: *mut c_void,
+    ) -> Self {
+        PayloadTransform { transform, context }
+    }
+
+    /// Returns the transformed payload, or `None` if the transform rejected it.
+    pub(crate) fn apply(&self, payload: &ZBytes) -> Option<ZBytes> {
+        let mut out = MaybeUninit::<z_owned_bytes_t>::uninit();
+        if !(self.transform)(payload.as_loaned_c_type_ref(), &mut out, self.context) {
+            return None;
+        }
+        // SAFETY: the transform constructs the output payload when it returns true
+        Some(unsafe { out.as_rust_type_mut_uninit().assume_init_read() })
+    }
+}
+
 /// The gravestone value for `z_owned_bytes_t`.
 #[no_mangle]
 extern "C" fn z_internal_bytes_null(this: &mut MaybeUninit<z_owned_bytes_t>) {
@@ -103,7 +145,7 @@ extern "C" fn z_bytes_clone(dst: &mut MaybeUninit<z_owned_bytes_t>, this: &z_loa
         .write(this.as_rust_type_ref().clone());
 }
 
-/// Returns total number of bytes in the payload.
+/// Returns total number of bytes in the payload, summed over all the slices it is made of.
 #[no_mangle]
 extern "C" fn z_bytes_len(this: &z_loaned_bytes_t) -> usize {
     this.as_rust_type_ref().len()
@@ -136,6 +178,10 @@ pub unsafe extern "C" fn z_bytes_to_string(
 
 /// Converts data into an owned slice.
 ///
+/// The data is copied into a single contiguous allocation, whatever the number of slices `this_` is made of,
+/// so the result can be passed to APIs expecting one pointer and a length (see `z_slice_data()` and `z_slice_len()`).
+/// When the data is not fragmented, `z_bytes_get_contiguous_view()` provides the same access without copying.
+///
 /// @param this_: Data to convert.
 /// @param dst: An uninitialized memory location where to construct a slice.
 #[no_mangle]
@@ -489,15 +535,51 @@ pub extern "C" fn z_bytes_slice_iterator_next(
     }
 }
 
+/// Returns the number of raw bytes slices contained in the `z_loaned_bytes_t`.
+///
+/// Together with `z_bytes_slice_at()`, this allows to know the memory layout of the data in advance,
+/// e.g. to build an I/O vector for scatter-gather operations.
+#[no_mangle]
+pub extern "C" fn z_bytes_slice_count(this: &z_loaned_bytes_t) -> usize {
+    this.as_rust_type_ref().slices().count()
+}
+
+/// Gets the raw bytes slice at the given position.
+/// No copy is performed: the view borrows the data of `this_` and stays valid as long as it is not modified or dropped.
+///
+/// Note that this function iterates over slices up to `index`, so `z_bytes_get_slice_iterator()` should be preferred
+/// to visit all slices of data made of a large number of them.
+/// @param this_: An instance of Zenoh data.
+/// @param index: The index of the slice, should be lower than `z_bytes_slice_count()`.
+/// @param slice: An unitialized memory location where the view for the slice will be constructed.
+/// @return `false` if `index` is out of bounds (in this case slice will stay unchanged), `true` otherwise.
+#[no_mangle]
+pub extern "C" fn z_bytes_slice_at(
+    this: &'static z_loaned_bytes_t,
+    index: usize,
+    slice: &mut MaybeUninit<z_view_slice_t>,
+) -> bool {
+    match this.as_rust_type_ref().slices().nth(index) {
+        Some(s) => {
+            slice
+                .as_rust_type_mut_uninit()
+                .write(CSliceView::from_slice(s));
+            true
+        }
+        None => false,
+    }
+}
+
 #[cfg(feature = "unstable")]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// Attempts to get a contiguous view to the underlying bytes.
 /// This is only possible if data is not fragmented, otherwise the function will fail.
-/// In case of fragmented data, consider using `z_bytes_get_slice_iterator()`.
+/// No copy is performed: the view borrows the data of `this_` and stays valid as long as it is not modified or dropped.
+/// In case of fragmented data, consider using `z_bytes_get_slice_iterator()`, or `z_bytes_to_slice()` to obtain a contiguous copy.
 ///
 /// @param this_: An instance of Zenoh data.
 /// @param view: An uninitialized memory location where a contiguous view on data will be constructed.
-/// @return  ​0​ upon success, negative error code otherwise.
+/// @return  ​0​ upon success, `Z_EINVAL` if data is fragmented and a copy would be required.
 #[no_mangle]
 pub extern "C" fn z_bytes_get_contiguous_view(
     this: &'static z_loaned_bytes_t,
@@ -514,6 +596,37 @@ pub extern "C" fn z_bytes_get_contiguous_view(
     }
 }
 
+#[cfg(feature = "unstable")]
+/// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
+/// Attempts to get a contiguous string view to the underlying bytes.
+/// This is only possible if data is not fragmented and is a valid UTF-8 string, otherwise the function will fail.
+/// No copy is performed: the view borrows the data of `this_` and stays valid as long as it is not modified or dropped.
+/// The string is delimited by its length and is not null-terminated.
+/// In case of fragmented data, consider using `z_bytes_to_string()` to obtain a contiguous copy.
+///
+/// @param this_: An instance of Zenoh data.
+/// @param view: An uninitialized memory location where a contiguous string view on data will be constructed.
+/// @return  ​0​ upon success, `Z_EINVAL` if data is fragmented and a copy would be required, `Z_EUTF8` if data is not a valid UTF-8 string.
+#[no_mangle]
+pub extern "C" fn z_bytes_get_contiguous_string_view(
+    this: &'static z_loaned_bytes_t,
+    view: &mut MaybeUninit<z_view_string_t>,
+) -> result::z_result_t {
+    let payload = this.as_rust_type_ref();
+    match payload.to_bytes() {
+        std::borrow::Cow::Borrowed(s) => {
+            if let Err(e) = std::str::from_utf8(s) {
+                tracing::error!("Payload is not a valid utf-8 string: {}", e);
+                return result::Z_EUTF8;
+            }
+            view.as_rust_type_mut_uninit()
+                .write(CStringView::new_borrowed_from_slice(s));
+            result::Z_OK
+        }
+        std::borrow::Cow::Owned(_) => result::Z_EINVAL,
+    }
+}
+
 #[cfg(all(feature = "shared-memory", feature = "unstable"))]
 /// @warning This API has been marked as unstable: it works as advertised, but it may be changed in a future release.
 /// @brief Converts from an immutable SHM buffer consuming it.
@@ -0,0 +1,75 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+volatile unsigned int allocs = 0;
+volatile unsigned int frees = 0;
+
+// The original malloc pointer is stored right before the aligned block.
+void *test_alloc(size_t size, size_t align, void *context) {
+    allocs++;
+    uint8_t *raw = (uint8_t *)malloc(size + align + sizeof(void *));
+    if (raw == NULL) {
+        return NULL;
+    }
+    uintptr_t start = (uintptr_t)(raw + sizeof(void *));
+    uint8_t *ptr = (uint8_t *)((start + align - 1) & ~(uintptr_t)(align - 1));
+    ((void **)ptr)[-1] = raw;
+    return ptr;
+}
+
+void test_free(void *ptr, void *context) {
+    frees++;
+    free(((void **)ptr)[-1]);
+}
+
+void *dummy_alloc(size_t size, size_t align, void *context) { return NULL; }
+
+void dummy_free(void *ptr, void *context) {}
+
+void global_allocator() {
+    assert(zc_set_global_allocator(test_alloc, test_free, NULL, NULL) == Z_OK);
+    assert(zc_set_global_allocator(dummy_alloc, dummy_free, NULL, NULL) != Z_OK);
+
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "payload");
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/allocator");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    z_drop(z_move(s));
+
+    assert(allocs > 0);
+    assert(frees > 0);
+}
+#endif
+
+int main(int argc, char **argv) {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    global_allocator();
+#endif
+    return 0;
+}
@@ -36,7 +36,82 @@ void insert_get() {
     z_drop(z_move(config));
 }
 
+#if defined(Z_FEATURE_UNSTABLE_API)
+void link_options() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    zc_link_options_t options;
+    zc_link_options_default(&options);
+    options.lease_ms = 6000;
+    options.keep_alive_interval_ms = 2000;
+    options.open_timeout_ms = 3000;
+    assert(zc_config_set_link_options(z_loan_mut(config), &options) == Z_OK);
+
+    z_owned_string_t value;
+    zc_config_get_from_str(z_loan(config), "transport/link/tx/lease", &value);
+    assert(strncmp(z_string_data(z_loan(value)), "6000", z_string_len(z_loan(value))) == 0);
+    z_drop(z_move(value));
+    zc_config_get_from_str(z_loan(config), "transport/link/tx/keep_alive", &value);
+    assert(strncmp(z_string_data(z_loan(value)), "3", z_string_len(z_loan(value))) == 0);
+    z_drop(z_move(value));
+    zc_config_get_from_str(z_loan(config), "transport/unicast/open_timeout", &value);
+    assert(strncmp(z_string_data(z_loan(value)), "3000", z_string_len(z_loan(value))) == 0);
+    z_drop(z_move(value));
+
+    options.keep_alive_interval_ms = 0;
+    assert(zc_config_set_link_options(z_loan_mut(config), &options) == Z_EINVAL);
+    z_drop(z_move(config));
+}
+
+void adminspace_toggle() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    assert(zc_config_set_adminspace_enabled(z_loan_mut(config), false) == Z_OK);
+    z_owned_string_t value;
+    zc_config_get_from_str(z_loan(config), "adminspace/enabled", &value);
+    assert(strncmp(z_string_data(z_loan(value)), "false", z_string_len(z_loan(value))) == 0);
+    z_drop(z_move(value));
+    z_drop(z_move(config));
+}
+
+void metadata() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    assert(zc_config_set_metadata(z_loan_mut(config), "role", "gateway") == Z_OK);
+    assert(zc_config_set_metadata(z_loan_mut(config), "site", "lab") == Z_OK);
+    z_owned_string_t value;
+    zc_config_get_from_str(z_loan(config), "metadata", &value);
+    const char *expected = "{\"role\":\"gateway\",\"site\":\"lab\"}";
+    assert(z_string_len(z_loan(value)) == strlen(expected));
+    assert(strncmp(z_string_data(z_loan(value)), expected, strlen(expected)) == 0);
+    z_drop(z_move(value));
+    z_drop(z_move(config));
+}
+
+void timestamping() {
+    // peers do not timestamp their publications by default
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    assert(!z_session_timestamping_enabled(z_loan(s)));
+    z_drop(z_move(s));
+
+    z_config_default(&config);
+    assert(zc_config_set_timestamping_enabled(z_loan_mut(config), true) == Z_OK);
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    assert(z_session_timestamping_enabled(z_loan(s)));
+    z_drop(z_move(s));
+}
+#endif
+
 int main(int argc, char **argv) {
     zc_try_init_log_from_env();
     insert_get();
+#if defined(Z_FEATURE_UNSTABLE_API)
+    link_options();
+    adminspace_toggle();
+    metadata();
+    timestamping();
+#endif
 }
@@ -114,6 +114,27 @@ void test_equals() {
     z_drop(z_move(e));
 }
 
+#if defined(Z_FEATURE_UNSTABLE_API)
+void test_registry() {
+    z_encoding_id_t id1 = z_encoding_register("my_encoding");
+    z_encoding_id_t id2 = z_encoding_register("text/plain;utf8");
+    assert(id1 != 0 && id2 != 0 && id1 != id2);
+    assert(z_encoding_register("my_encoding") == id1);
+
+    z_owned_encoding_t e;
+    assert(z_encoding_from_id(&e, id2) == Z_OK);
+    assert(z_encoding_id_of(z_encoding_loan(&e)) == id2);
+    z_encoding_drop(z_move(e));
+
+    z_encoding_from_str(&e, "unregistered_encoding");
+    assert(z_encoding_id_of(z_encoding_loan(&e)) == 0);
+    z_encoding_drop(z_move(e));
+
+    assert(z_encoding_from_id(&e, 0) == Z_EINVAL);
+    z_encoding_drop(z_move(e));
+}
+#endif
+
 int main(int argc, char **argv) {
     test_null_encoding();
     test_encoding_without_id();
@@ -121,4 +142,7 @@ int main(int argc, char **argv) {
     test_constants();
     test_with_schema();
     test_equals();
+#if defined(Z_FEATURE_UNSTABLE_API)
+    test_registry();
+#endif
 }
@@ -63,6 +63,17 @@ void canonize() {
     assert(strncmp(z_string_data(z_loan(key_exp_canonized_bytes)), "a/**/c", len_new) == 0);
 }
 
+void is_canon() {
+    const char *canon = "a/**/c";
+    const char *non_canon = "a/**/**/c";
+    assert(z_keyexpr_is_canon(canon, strlen(canon)) == Z_OK);
+    assert(z_keyexpr_is_canon(non_canon, strlen(non_canon)) == Z_EINVAL);
+    // Validation must not alter the passed string
+    assert(strcmp(non_canon, "a/**/**/c") == 0);
+    // Only the first `len` characters are validated
+    assert(z_keyexpr_is_canon(non_canon, 3) == Z_OK);
+}
+
 void includes() {
     z_view_keyexpr_t foobar, foostar;
     z_view_keyexpr_from_str(&foobar, "foo/bar");
@@ -109,14 +120,68 @@ void relation_to() {
     assert(z_keyexpr_relation_to(z_loan(foostar), z_loan(foostar)) == Z_KEYEXPR_INTERSECTION_LEVEL_EQUALS);
     assert(z_keyexpr_relation_to(z_loan(barstar), z_loan(foobar)) == Z_KEYEXPR_INTERSECTION_LEVEL_DISJOINT);
 }
+
+void selector() {
+    z_view_keyexpr_t foobar;
+    z_view_keyexpr_from_str(&foobar, "foo/bar");
+
+    z_owned_selector_t sel;
+    assert(z_selector_new(&sel, z_loan(foobar), "a=1;b=2") == Z_OK);
+    assert(z_keyexpr_equals(z_selector_keyexpr(z_loan(sel)), z_loan(foobar)));
+
+    z_view_string_t params;
+    z_selector_parameters(z_loan(sel), &params);
+    assert(strncmp(z_string_data(z_loan(params)), "a=1;b=2", z_string_len(z_loan(params))) == 0);
+
+    z_owned_string_t s;
+    z_selector_to_string(z_loan(sel), &s);
+    assert(strncmp(z_string_data(z_loan(s)), "foo/bar?a=1;b=2", z_string_len(z_loan(s))) == 0);
+    z_drop(z_move(s));
+
+    z_drop(z_move(sel));
+    assert(!z_internal_check(sel));
+}
+
+void has_wildcards() {
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "a/b/c");
+    assert(!z_keyexpr_has_wildcards(z_loan(ke)));
+    z_view_keyexpr_from_str(&ke, "a/*/c");
+    assert(z_keyexpr_has_wildcards(z_loan(ke)));
+    z_view_keyexpr_from_str(&ke, "a/**");
+    assert(z_keyexpr_has_wildcards(z_loan(ke)));
+    z_view_keyexpr_from_str(&ke, "a/b$*");
+    assert(z_keyexpr_has_wildcards(z_loan(ke)));
+}
+
+void query_parameters_builder() {
+    z_owned_query_parameters_builder_t builder;
+    z_query_parameters_builder_empty(&builder);
+    assert(z_query_parameters_builder_add_str(z_loan_mut(builder), "name", "a;b=c d") == Z_OK);
+    assert(z_query_parameters_builder_add_int(z_loan_mut(builder), "limit", -10) == Z_OK);
+    assert(z_query_parameters_builder_add_flag(z_loan_mut(builder), "verbose") == Z_OK);
+    assert(z_query_parameters_builder_add_flag(z_loan_mut(builder), "") == Z_EINVAL);
+
+    z_owned_string_t params;
+    z_query_parameters_builder_finish(z_move(builder), &params);
+    assert(!z_internal_check(builder));
+    const char *expected = "name=a%3Bb%3Dc%20d;limit=-10;verbose";
+    assert(z_string_len(z_loan(params)) == strlen(expected));
+    assert(strncmp(z_string_data(z_loan(params)), expected, strlen(expected)) == 0);
+    z_drop(z_move(params));
+}
 #endif
 
 int main(int argc, char **argv) {
     canonize();
+    is_canon();
     includes();
     intersects();
     undeclare();
 #if defined(Z_FEATURE_UNSTABLE_API)
     relation_to();
+    selector();
+    query_parameters_builder();
+    has_wildcards();
 #endif
 }
@@ -98,6 +98,54 @@ void test_liveliness_sub() {
     z_drop(z_move(s2));
 }
 
+void assert_liveliness_sample(const z_loaned_ring_handler_sample_t* handler, z_sample_kind_t kind) {
+    z_owned_sample_t sample;
+    assert(z_recv(handler, &sample) == Z_OK);
+    assert(z_sample_kind(z_loan(sample)) == kind);
+    z_view_string_t ks;
+    z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ks);
+    assert(strncmp(token1_expr, z_string_data(z_loan(ks)), z_string_len(z_loan(ks))) == 0);
+    z_drop(z_move(sample));
+}
+
+void test_liveliness_sub_ring_handler() {
+    const char* expr = "zenoh/liveliness/test/*";
+
+    z_owned_session_t s1, s2;
+    z_owned_config_t c1, c2;
+    z_config_default(&c1);
+    z_config_default(&c2);
+    z_view_keyexpr_t k, k1;
+    z_view_keyexpr_from_str(&k, expr);
+    z_view_keyexpr_from_str(&k1, token1_expr);
+
+    z_open(&s1, z_move(c1), NULL);
+    z_open(&s2, z_move(c2), NULL);
+
+    z_owned_closure_sample_t closure;
+    z_owned_ring_handler_sample_t handler;
+    z_ring_channel_sample_new(&closure, &handler, 4);
+    z_owned_subscriber_t sub;
+    assert(z_liveliness_declare_subscriber(z_loan(s2), &sub, z_loan(k), z_move(closure), NULL) == Z_OK);
+
+    z_sleep_s(1);
+    z_owned_liveliness_token_t t1;
+    z_liveliness_declare_token(z_loan(s1), &t1, z_loan(k1), NULL);
+    assert_liveliness_sample(z_loan(handler), Z_SAMPLE_KIND_PUT);
+
+    z_liveliness_undeclare_token(z_move(t1));
+    assert_liveliness_sample(z_loan(handler), Z_SAMPLE_KIND_DELETE);
+
+    z_owned_sample_t sample;
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+    z_drop(z_move(sub));
+    assert(z_recv(z_loan(handler), &sample) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(handler));
+    z_drop(z_move(s1));
+    z_drop(z_move(s2));
+}
+
 void test_liveliness_get() {
     const char* expr = "zenoh/liveliness/test/*";
 
@@ -146,7 +194,209 @@ void test_liveliness_get() {
     z_drop(z_move(s2));
 }
 
+void test_liveliness_get_fifo_drain() {
+    const char* expr = "zenoh/liveliness/test/*";
+
+    z_owned_session_t s1, s2;
+    z_owned_config_t c1, c2;
+    z_config_default(&c1);
+    z_config_default(&c2);
+    z_view_keyexpr_t k, k1, k2;
+    z_view_keyexpr_from_str(&k, expr);
+    z_view_keyexpr_from_str(&k1, token1_expr);
+    z_view_keyexpr_from_str(&k2, token2_expr);
+
+    z_open(&s1, z_move(c1), NULL);
+    z_open(&s2, z_move(c2), NULL);
+
+    z_sleep_s(1);
+    z_owned_liveliness_token_t t1, t2;
+    z_liveliness_declare_token(z_loan(s1), &t1, z_loan(k1), NULL);
+    z_liveliness_declare_token(z_loan(s1), &t2, z_loan(k2), NULL);
+    z_sleep_s(1);
+
+    z_owned_fifo_handler_reply_t handler;
+    z_owned_closure_reply_t cb;
+    z_fifo_channel_reply_new(&cb, &handler, 16);
+    assert(z_liveliness_get(z_loan(s2), z_loan(k), z_move(cb), NULL) == Z_OK);
+
+    context_t context = {false, false, false, false};
+    z_owned_reply_t reply;
+    while (z_fifo_handler_reply_recv(z_loan(handler), &reply) == Z_OK) {
+        assert(z_reply_is_ok(z_loan(reply)));
+        z_view_string_t ks;
+        z_keyexpr_as_view_string(z_sample_keyexpr(z_reply_ok(z_loan(reply))), &ks);
+        if (strncmp(token1_expr, z_string_data(z_loan(ks)), z_string_len(z_loan(ks))) == 0) {
+            context.token1_put = true;
+        } else if (strncmp(token2_expr, z_string_data(z_loan(ks)), z_string_len(z_loan(ks))) == 0) {
+            context.token2_put = true;
+        }
+        z_drop(z_move(reply));
+    }
+    assert(!z_internal_check(reply));
+    assert(context.token1_put);
+    assert(context.token2_put);
+
+    z_drop(z_move(handler));
+    z_drop(z_move(t1));
+    z_drop(z_move(t2));
+    z_drop(z_move(s1));
+    z_drop(z_move(s2));
+}
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+void test_liveliness_get_collect() {
+    const char* expr = "zenoh/liveliness/test/*";
+
+    z_owned_session_t s1, s2;
+    z_owned_config_t c1, c2;
+    z_config_default(&c1);
+    z_config_default(&c2);
+    z_view_keyexpr_t k, k1, k2;
+    z_view_keyexpr_from_str(&k, expr);
+    z_view_keyexpr_from_str(&k1, token1_expr);
+    z_view_keyexpr_from_str(&k2, token2_expr);
+
+    z_open(&s1, z_move(c1), NULL);
+    z_open(&s2, z_move(c2), NULL);
+
+    z_sleep_s(1);
+    z_owned_liveliness_token_t t1, t2;
+    z_liveliness_declare_token(z_loan(s1), &t1, z_loan(k1), NULL);
+    z_liveliness_declare_token(z_loan(s1), &t2, z_loan(k2), NULL);
+    z_sleep_s(1);
+
+    z_owned_reply_t replies[2];
+    size_t len = 0;
+    assert(zc_liveliness_get_collect(z_loan(s2), z_loan(k), replies, 2, &len, 0) == Z_OK);
+    assert(len == 2);
+    for (size_t i = 0; i < len; i++) {
+        assert(z_reply_is_ok(z_loan(replies[i])));
+        z_drop(z_move(replies[i]));
+    }
+
+    // Replies exceeding the array capacity are dropped
+    assert(zc_liveliness_get_collect(z_loan(s2), z_loan(k), replies, 1, &len, 0) == Z_OK);
+    assert(len == 1);
+    z_drop(z_move(replies[0]));
+
+    z_drop(z_move(t1));
+    z_drop(z_move(t2));
+    z_sleep_s(1);
+
+    assert(zc_liveliness_get_collect(z_loan(s2), z_loan(k), replies, 2, &len, 0) == Z_OK);
+    assert(len == 0);
+
+    z_drop(z_move(s1));
+    z_drop(z_move(s2));
+}
+
+void test_liveliness_declare_token_deferred() {
+    const char* expr = "zenoh/liveliness/test/*";
+
+    z_owned_session_t s1, s2;
+    z_owned_config_t c1, c2;
+    z_config_default(&c1);
+    z_config_default(&c2);
+    z_view_keyexpr_t k, k1, k2;
+    z_view_keyexpr_from_str(&k, expr);
+    z_view_keyexpr_from_str(&k1, token1_expr);
+    z_view_keyexpr_from_str(&k2, token2_expr);
+
+    z_open(&s1, z_move(c1), NULL);
+    z_open(&s2, z_move(c2), NULL);
+
+    z_owned_closure_sample_t closure;
+    context_t context = {false, false, false, false};
+    z_closure(&closure, on_receive, NULL, (void*)(&context));
+
+    z_owned_subscriber_t sub;
+    z_liveliness_declare_subscriber(z_loan(s2), &sub, z_loan(k), z_move(closure), NULL);
+
+    z_sleep_s(1);
+    zc_owned_liveliness_token_batch_t batch;
+    zc_liveliness_token_batch_new(&batch);
+    assert(zc_liveliness_declare_token_deferred(z_loan(s1), z_loan(batch), z_loan(k1), NULL) == Z_OK);
+    assert(zc_liveliness_declare_token_deferred(z_loan(s1), z_loan(batch), z_loan(k2), NULL) == Z_OK);
+    assert(zc_liveliness_flush(z_loan(batch)) == Z_OK);
+    assert(zc_liveliness_token_batch_len(z_loan(batch)) == 2);
+    z_sleep_s(1);
+
+    assert(context.token1_put);
+    assert(context.token2_put);
+
+    // dropping the batch undeclares its tokens while the session is still open
+    z_drop(z_move(batch));
+    assert(!z_internal_check(batch));
+    z_sleep_s(1);
+    assert(context.token1_drop);
+    assert(context.token2_drop);
+
+    // declarations on a closed session fail right away
+    zc_liveliness_token_batch_new(&batch);
+    z_close(z_loan_mut(s1), NULL);
+    assert(zc_liveliness_declare_token_deferred(z_loan(s1), z_loan(batch), z_loan(k1), NULL) != Z_OK);
+    assert(zc_liveliness_flush(z_loan(batch)) == Z_OK);
+    assert(zc_liveliness_token_batch_len(z_loan(batch)) == 0);
+    z_drop(z_move(batch));
+
+    z_drop(z_move(s1));
+    z_drop(z_move(sub));
+    z_drop(z_move(s2));
+}
+
+void test_liveliness_token_redeclare() {
+    const char* expr = "zenoh/liveliness/test/*";
+
+    z_owned_session_t s1, s2;
+    z_owned_config_t c1, c2;
+    z_config_default(&c1);
+    z_config_default(&c2);
+    z_view_keyexpr_t k, k1, k2;
+    z_view_keyexpr_from_str(&k, expr);
+    z_view_keyexpr_from_str(&k1, token1_expr);
+    z_view_keyexpr_from_str(&k2, token2_expr);
+
+    z_open(&s1, z_move(c1), NULL);
+    z_open(&s2, z_move(c2), NULL);
+
+    z_owned_closure_sample_t closure;
+    context_t context = {false, false, false, false};
+    z_closure(&closure, on_receive, NULL, (void*)(&context));
+
+    z_owned_subscriber_t sub;
+    z_liveliness_declare_subscriber(z_loan(s2), &sub, z_loan(k), z_move(closure), NULL);
+
+    z_sleep_s(1);
+    z_owned_liveliness_token_t t;
+    z_liveliness_declare_token(z_loan(s1), &t, z_loan(k1), NULL);
+    z_sleep_s(1);
+    assert(context.token1_put);
+
+    assert(zc_liveliness_token_redeclare(&t, z_loan(s1), z_loan(k2)) == Z_OK);
+    z_sleep_s(1);
+    assert(context.token2_put);
+    assert(context.token1_drop);
+    assert(!context.token2_drop);
+
+    z_liveliness_undeclare_token(z_move(t));
+    z_sleep_s(1);
+    assert(context.token2_drop);
+
+    z_drop(z_move(sub));
+    z_drop(z_move(s1));
+    z_drop(z_move(s2));
+}
+#endif
+
 int main(int argc, char** argv) {
     test_liveliness_sub();
+    test_liveliness_sub_ring_handler();
     test_liveliness_get();
+    test_liveliness_get_fifo_drain();
+#if defined(Z_FEATURE_UNSTABLE_API)
+    test_liveliness_get_collect();
+    test_liveliness_declare_token_deferred();
+    test_liveliness_token_redeclare();
+#endif
 }
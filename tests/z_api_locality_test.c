@@ -0,0 +1,183 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+size_t count_samples(const z_loaned_fifo_handler_sample_t *handler) {
+    size_t count = 0;
+    z_owned_sample_t sample;
+    while (z_try_recv(handler, &sample) == Z_OK) {
+        count++;
+        z_drop(z_move(sample));
+    }
+    return count;
+}
+
+void declare_subscriber_with_origin(const z_loaned_session_t *s, const z_loaned_keyexpr_t *ke,
+                                    zc_locality_t origin, z_owned_subscriber_t *sub,
+                                    z_owned_fifo_handler_sample_t *handler) {
+    z_owned_closure_sample_t callback;
+    z_fifo_channel_sample_new(&callback, handler, 16);
+    z_subscriber_options_t options;
+    z_subscriber_options_default(&options);
+    options.allowed_origin = origin;
+    assert(z_declare_subscriber(s, sub, ke, z_move(callback), &options) == Z_OK);
+}
+
+void subscriber_allowed_origin() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/locality/origin");
+
+    z_owned_subscriber_t any_sub, local_sub, remote_sub;
+    z_owned_fifo_handler_sample_t any_handler, local_handler, remote_handler;
+    declare_subscriber_with_origin(z_loan(s), z_loan(ke), ZC_LOCALITY_ANY, &any_sub, &any_handler);
+    declare_subscriber_with_origin(z_loan(s), z_loan(ke), ZC_LOCALITY_SESSION_LOCAL, &local_sub, &local_handler);
+    declare_subscriber_with_origin(z_loan(s), z_loan(ke), ZC_LOCALITY_REMOTE, &remote_sub, &remote_handler);
+
+    // samples published by the session itself are filtered out by the remote-only subscriber
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "data");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    z_sleep_ms(100);
+    assert(count_samples(z_loan(any_handler)) == 1);
+    assert(count_samples(z_loan(local_handler)) == 1);
+    assert(count_samples(z_loan(remote_handler)) == 0);
+
+    z_drop(z_move(any_sub));
+    z_drop(z_move(local_sub));
+    z_drop(z_move(remote_sub));
+    z_drop(z_move(any_handler));
+    z_drop(z_move(local_handler));
+    z_drop(z_move(remote_handler));
+    z_drop(z_move(s));
+}
+
+void publisher_allowed_destination() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/locality/destination");
+
+    z_owned_subscriber_t sub;
+    z_owned_fifo_handler_sample_t handler;
+    declare_subscriber_with_origin(z_loan(s), z_loan(ke), ZC_LOCALITY_ANY, &sub, &handler);
+
+    zc_locality_t destinations[] = {ZC_LOCALITY_ANY, ZC_LOCALITY_SESSION_LOCAL, ZC_LOCALITY_REMOTE};
+    size_t expected[] = {1, 1, 0};
+    for (int i = 0; i < 3; i++) {
+        z_publisher_options_t options;
+        z_publisher_options_default(&options);
+        options.allowed_destination = destinations[i];
+        z_owned_publisher_t pub;
+        assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), &options) == Z_OK);
+        z_owned_bytes_t payload;
+        z_bytes_copy_from_str(&payload, "data");
+        assert(z_publisher_put(z_loan(pub), z_move(payload), NULL) == Z_OK);
+        z_sleep_ms(100);
+        // a remote-only publisher does not deliver to the subscribers of its own session
+        assert(count_samples(z_loan(handler)) == expected[i]);
+        z_drop(z_move(pub));
+    }
+
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void put_with_source_info(const z_loaned_publisher_t *pub) {
+    z_entity_global_id_t id = z_publisher_id(pub);
+    z_owned_source_info_t source_info;
+    z_source_info_new(&source_info, &id, 0);
+    z_publisher_put_options_t options;
+    z_publisher_put_options_default(&options);
+    options.source_info = z_move(source_info);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "data");
+    assert(z_publisher_put(pub, z_move(payload), &options) == Z_OK);
+}
+
+bool recv_is_local(const z_loaned_fifo_handler_sample_t *handler, const z_loaned_session_t *s) {
+    z_owned_sample_t sample;
+    assert(z_fifo_handler_sample_recv_timeout(handler, &sample, 5000) == Z_OK);
+    bool local = z_sample_is_local(z_loan(sample), s);
+    z_drop(z_move(sample));
+    return local;
+}
+
+void sample_is_local() {
+    z_owned_config_t config1;
+    z_config_default(&config1);
+    zc_config_insert_json5(z_loan_mut(config1), "listen/endpoints", "[\"tcp/127.0.0.1:17470\"]");
+    zc_config_insert_json5(z_loan_mut(config1), "scouting/multicast/enabled", "false");
+    z_owned_session_t s1;
+    assert(z_open(&s1, z_move(config1), NULL) == Z_OK);
+    z_owned_config_t config2;
+    z_config_default(&config2);
+    zc_config_insert_json5(z_loan_mut(config2), "connect/endpoints", "[\"tcp/127.0.0.1:17470\"]");
+    zc_config_insert_json5(z_loan_mut(config2), "scouting/multicast/enabled", "false");
+    z_owned_session_t s2;
+    assert(z_open(&s2, z_move(config2), NULL) == Z_OK);
+    assert(z_session_wait_connected(z_loan(s2), 5000) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/locality/is_local");
+    z_owned_subscriber_t sub;
+    z_owned_fifo_handler_sample_t handler;
+    declare_subscriber_with_origin(z_loan(s1), z_loan(ke), ZC_LOCALITY_ANY, &sub, &handler);
+    z_owned_publisher_t local_pub, remote_pub;
+    assert(z_declare_publisher(z_loan(s1), &local_pub, z_loan(ke), NULL) == Z_OK);
+    assert(z_declare_publisher(z_loan(s2), &remote_pub, z_loan(ke), NULL) == Z_OK);
+    z_sleep_ms(500);
+
+    put_with_source_info(z_loan(local_pub));
+    assert(recv_is_local(z_loan(handler), z_loan(s1)));
+    put_with_source_info(z_loan(remote_pub));
+    assert(!recv_is_local(z_loan(handler), z_loan(s1)));
+    // from the publishing session point of view, the remote sample is local
+    put_with_source_info(z_loan(remote_pub));
+    assert(recv_is_local(z_loan(handler), z_loan(s2)));
+    // without source info the origin is unknown
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "data");
+    assert(z_publisher_put(z_loan(local_pub), z_move(payload), NULL) == Z_OK);
+    assert(!recv_is_local(z_loan(handler), z_loan(s1)));
+
+    z_drop(z_move(local_pub));
+    z_drop(z_move(remote_pub));
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s2));
+    z_drop(z_move(s1));
+}
+#endif
+
+int main(int argc, char **argv) {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    subscriber_allowed_origin();
+    publisher_allowed_destination();
+    sample_is_local();
+#endif
+    return 0;
+}
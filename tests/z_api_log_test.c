@@ -0,0 +1,75 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+typedef struct log_context_t {
+    int debug;
+    int error;
+} log_context_t;
+
+void on_log(zc_log_severity_t severity, const z_loaned_string_t *msg, void *arg) {
+    log_context_t *ctx = (log_context_t *)arg;
+    if (severity == ZC_LOG_SEVERITY_DEBUG) {
+        ctx->debug++;
+    } else if (severity == ZC_LOG_SEVERITY_ERROR) {
+        ctx->error++;
+    }
+}
+
+// logs at debug level
+void not_canon() {
+    const char *ke = "a/**/**";
+    assert(z_keyexpr_is_canon(ke, strlen(ke)) != Z_OK);
+}
+
+// logs at error level
+void invalid_config_key() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    assert(zc_config_insert_json5(z_loan_mut(config), "not/a/config/key", "1") != Z_OK);
+    z_drop(z_move(config));
+}
+
+void set_min_severity() {
+    log_context_t ctx = {0, 0};
+    zc_owned_closure_log_t callback;
+    zc_closure_log(&callback, on_log, NULL, &ctx);
+    zc_init_log_with_callback(ZC_LOG_SEVERITY_ERROR, z_move(callback));
+
+    not_canon();
+    invalid_config_key();
+    assert(ctx.debug == 0);
+    assert(ctx.error == 1);
+
+    zc_log_set_min_severity(ZC_LOG_SEVERITY_DEBUG);
+    not_canon();
+    assert(ctx.debug > 0);
+
+    int debug = ctx.debug;
+    zc_log_set_min_severity(ZC_LOG_SEVERITY_ERROR);
+    not_canon();
+    invalid_config_key();
+    assert(ctx.debug == debug);
+    assert(ctx.error == 2);
+}
+
+int main(int argc, char **argv) {
+    set_min_severity();
+    return 0;
+}
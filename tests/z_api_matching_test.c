@@ -0,0 +1,79 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+typedef struct matching_context_t {
+    int notifications;
+    bool matching;
+} matching_context_t;
+
+void on_matching_status(const z_matching_status_t *status, void *arg) {
+    matching_context_t *ctx = (matching_context_t *)arg;
+    ctx->notifications++;
+    ctx->matching = status->matching;
+}
+
+void on_sample(z_loaned_sample_t *sample, void *arg) {}
+
+void publisher_matching_listener() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/matching");
+
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+
+    matching_context_t ctx = {0, false};
+    z_owned_closure_matching_status_t callback;
+    z_closure(&callback, on_matching_status, NULL, &ctx);
+    z_owned_matching_listener_t listener;
+    assert(z_publisher_declare_matching_listener(z_loan(pub), &listener, z_move(callback)) == Z_OK);
+    z_sleep_ms(500);
+    assert(!ctx.matching);
+
+    z_owned_closure_sample_t sub_callback;
+    z_closure(&sub_callback, on_sample, NULL, NULL);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(sub_callback), NULL) == Z_OK);
+    z_sleep_ms(500);
+    assert(ctx.matching);
+    int notifications = ctx.notifications;
+
+    z_drop(z_move(sub));
+    z_sleep_ms(500);
+    assert(!ctx.matching);
+    assert(ctx.notifications == notifications + 1);
+
+    z_drop(z_move(listener));
+    z_drop(z_move(pub));
+    z_drop(z_move(s));
+}
+#endif
+
+int main(int argc, char **argv) {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    publisher_matching_listener();
+#endif
+    return 0;
+}
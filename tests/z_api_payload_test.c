@@ -118,6 +118,7 @@ void test_append(void) {
         assert(z_bytes_writer_append(z_loan_mut(writer), z_bytes_move(&b)) == 0);
     }
     z_bytes_writer_finish(z_move(writer), &payload);
+    assert(z_bytes_len(z_loan(payload)) == 10);
 
     z_bytes_reader_t reader = z_bytes_get_reader(z_loan(payload));
     z_bytes_reader_read(&reader, data_out, 10);
@@ -216,13 +217,21 @@ bool check_slice(const z_loaned_bytes_t *b, const uint8_t *data, size_t len) {
     uint8_t *data_out = (uint8_t *)malloc(len);
     z_view_slice_t v;
     size_t pos = 0;
+    size_t count = 0;
     while (z_bytes_slice_iterator_next(&it, &v)) {
         const uint8_t *slice_data = z_slice_data(z_loan(v));
         size_t slice_len = z_slice_len(z_loan(v));
+        z_view_slice_t at;
+        assert(z_bytes_slice_at(b, count, &at));
+        assert(z_slice_data(z_loan(at)) == slice_data);
+        assert(z_slice_len(z_loan(at)) == slice_len);
         memcpy(data_out + pos, slice_data, slice_len);
         pos += slice_len;
+        count++;
     }
     assert(pos == len);
+    assert(z_bytes_slice_count(b) == count);
+    assert(!z_bytes_slice_at(b, count, &v));
     assert(memcmp(data, data_out, len) == 0);
     free(data_out);
     return true;
@@ -239,6 +248,10 @@ void test_slices(void) {
     assert(z_bytes_get_contiguous_view(z_loan(payload), &view) == Z_OK);
     assert(z_slice_len(z_loan(view)) == 10);
     assert(memcmp(data, z_slice_data(z_loan(view)), 10) == 0);
+    z_view_string_t str_view;
+    assert(z_bytes_get_contiguous_string_view(z_loan(payload), &str_view) == Z_OK);
+    assert(z_string_len(z_loan(str_view)) == 10);
+    assert(z_string_data(z_loan(str_view)) == (const char *)z_slice_data(z_loan(view)));
 #endif
 
     z_drop(z_move(payload));
@@ -254,9 +267,17 @@ void test_slices(void) {
     }
     z_bytes_writer_finish(z_move(writer), &payload);
     assert(check_slice(z_loan(payload), data, 10));
+    // the length accounts for all the slices, not only the first one
+    assert(z_bytes_len(z_loan(payload)) == 10);
 #if defined(Z_FEATURE_UNSTABLE_API)
     assert(z_bytes_get_contiguous_view(z_loan(payload), &view) != Z_OK);
 #endif
+    // fragmented data is copied into a single contiguous slice
+    z_owned_slice_t contiguous;
+    assert(z_bytes_to_slice(z_loan(payload), &contiguous) == Z_OK);
+    assert(z_slice_len(z_loan(contiguous)) == 10);
+    assert(memcmp(data, z_slice_data(z_loan(contiguous)), 10) == 0);
+    z_drop(z_move(contiguous));
     z_drop(z_move(payload));
 }
 
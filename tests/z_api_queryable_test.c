@@ -0,0 +1,986 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+void get_with_payload() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/get_with_payload");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "{\"request\":1}");
+    opts.payload = z_move(payload);
+    z_owned_encoding_t encoding;
+    z_encoding_clone(&encoding, z_encoding_application_json());
+    opts.encoding = z_move(encoding);
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const z_loaned_bytes_t *query_payload = z_query_payload(z_loan(query));
+    assert(query_payload != NULL);
+    z_owned_string_t str;
+    z_bytes_to_string(query_payload, &str);
+    assert(strncmp(z_string_data(z_loan(str)), "{\"request\":1}", z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+    const z_loaned_encoding_t *query_encoding = z_query_encoding(z_loan(query));
+    assert(query_encoding != NULL);
+    assert(z_encoding_equals(query_encoding, z_encoding_application_json()));
+    z_drop(z_move(query));
+
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void get_with_ring_handler() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/get_with_ring_handler");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_ring_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_ring_channel_reply_new(&reply_callback, &replies, 1);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const char *values[] = {"1", "2", "3"};
+    for (int i = 0; i < 3; i++) {
+        z_owned_bytes_t payload;
+        z_bytes_copy_from_str(&payload, values[i]);
+        assert(z_query_reply(z_loan(query), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    }
+    z_drop(z_move(query));
+    z_sleep_ms(100);
+
+    // the ring buffer only keeps the most recent reply
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    z_owned_string_t str;
+    z_bytes_to_string(z_sample_payload(z_reply_ok(z_loan(reply))), &str);
+    assert(strncmp(z_string_data(z_loan(str)), "3", z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+    z_drop(z_move(reply));
+    assert(z_try_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    assert(!z_internal_check(reply));
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+typedef struct delayed_get_args_t {
+    const z_loaned_session_t *session;
+    const z_loaned_keyexpr_t *ke;
+    z_owned_closure_reply_t *callback;
+} delayed_get_args_t;
+
+void *delayed_get(void *arg) {
+    delayed_get_args_t *args = (delayed_get_args_t *)arg;
+    z_sleep_ms(100);
+    z_get(args->session, args->ke, "", z_move(*args->callback), NULL);
+    return NULL;
+}
+
+void query_recv_timeout() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/query_recv_timeout");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    // no query is sent yet
+    z_owned_query_t query;
+    assert(z_fifo_handler_query_len(z_loan(queries)) == 0);
+    z_clock_t start = z_clock_now();
+    assert(z_fifo_handler_query_recv_timeout(z_loan(queries), &query, 50) == Z_ETIMEOUT);
+    assert(z_clock_elapsed_ms(&start) >= 50);
+    assert(!z_internal_check(query));
+
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 4);
+    delayed_get_args_t args;
+    args.session = z_loan(s);
+    args.ke = z_loan(ke);
+    args.callback = &reply_callback;
+    z_owned_task_t task;
+    z_task_init(&task, NULL, delayed_get, &args);
+    assert(z_fifo_handler_query_recv_timeout(z_loan(queries), &query, 5000) == Z_OK);
+    assert(z_internal_check(query));
+    assert(z_fifo_handler_query_is_empty(z_loan(queries)));
+    z_drop(z_move(query));
+    z_task_join(z_move(task));
+
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(qable));
+    assert(z_fifo_handler_query_recv_timeout(z_loan(queries), &query, 50) == Z_CHANNEL_DISCONNECTED);
+    assert(!z_internal_check(query));
+
+    z_drop(z_move(replies));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_into_result() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/reply_into_result");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "value");
+    assert(z_query_reply(z_loan(query), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    z_bytes_copy_from_str(&payload, "error");
+    assert(z_query_reply_err(z_loan(query), z_move(payload), NULL) == Z_OK);
+    z_drop(z_move(query));
+
+    z_owned_reply_t reply;
+    z_owned_sample_t sample;
+    z_owned_reply_err_t err;
+    z_owned_string_t str;
+    // a successful reply is converted into a sample
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    assert(z_reply_into_result(z_move(reply), &sample, &err));
+    assert(!z_internal_check(reply));
+    assert(!z_internal_check(err));
+    z_bytes_to_string(z_sample_payload(z_loan(sample)), &str);
+    assert(strncmp(z_string_data(z_loan(str)), "value", z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+    z_drop(z_move(sample));
+
+    // an error reply is converted into a reply error
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    assert(!z_reply_into_result(z_move(reply), &sample, &err));
+    assert(!z_internal_check(reply));
+    assert(!z_internal_check(sample));
+    assert(z_internal_check(err));
+    z_bytes_to_string(z_reply_err_payload(z_loan(err)), &str);
+    assert(strncmp(z_string_data(z_loan(str)), "error", z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+    z_drop(z_move(err));
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    // a gravestone reply leaves both outputs in their gravestone state
+    z_internal_null(&reply);
+    assert(!z_reply_into_result(z_move(reply), &sample, &err));
+    assert(!z_internal_check(sample));
+    assert(!z_internal_check(err));
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_err_with_encoding(const z_loaned_query_t *query, const uint8_t *data, size_t len,
+                             const z_loaned_encoding_t *encoding) {
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_buf(&payload, data, len);
+    z_query_reply_err_options_t options;
+    z_query_reply_err_options_default(&options);
+    z_owned_encoding_t owned_encoding;
+    if (encoding != NULL) {
+        z_encoding_clone(&owned_encoding, encoding);
+        options.encoding = z_move(owned_encoding);
+    }
+    assert(z_query_reply_err(query, z_move(payload), &options) == Z_OK);
+}
+
+void reply_err_as_string() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/reply_err_as_string");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 8);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const uint8_t text[] = {'e', 'r', 'r'};
+    const uint8_t invalid_utf8[] = {'e', 0xff, 0xfe};
+    reply_err_with_encoding(z_loan(query), text, sizeof(text), NULL);
+    reply_err_with_encoding(z_loan(query), text, sizeof(text), z_encoding_text_plain());
+    reply_err_with_encoding(z_loan(query), text, sizeof(text), z_encoding_application_json());
+    reply_err_with_encoding(z_loan(query), text, sizeof(text), z_encoding_application_octet_stream());
+    reply_err_with_encoding(z_loan(query), invalid_utf8, sizeof(invalid_utf8), z_encoding_text_plain());
+    z_drop(z_move(query));
+
+    z_result_t expected[] = {Z_OK, Z_OK, Z_OK, Z_EINVAL, Z_EUTF8};
+    z_owned_reply_t reply;
+    for (int i = 0; i < 5; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        z_owned_string_t str;
+        assert(z_reply_err_as_string(z_reply_err(z_loan(reply)), &str) == expected[i]);
+        if (expected[i] == Z_OK) {
+            assert(z_string_len(z_loan(str)) == 3);
+            assert(strncmp(z_string_data(z_loan(str)), "err", 3) == 0);
+            z_drop(z_move(str));
+        } else {
+            assert(!z_internal_check(str));
+        }
+        z_drop(z_move(reply));
+    }
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+void queryable_with_handler() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/queryable_with_handler");
+    z_owned_queryable_t qable;
+    z_owned_fifo_handler_query_t queries;
+    assert(z_declare_queryable_with_handler(z_loan(s), &qable, &queries, z_loan(ke), 4, NULL) == Z_OK);
+
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t callback;
+    z_fifo_channel_reply_new(&callback, &replies, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(callback), NULL) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "reply");
+    assert(z_query_reply(z_loan(query), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    z_drop(z_move(query));
+
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    assert(z_reply_is_ok(z_loan(reply)));
+    z_owned_string_t str;
+    z_bytes_to_string(z_sample_payload(z_reply_ok(z_loan(reply))), &str);
+    assert(strncmp(z_string_data(z_loan(str)), "reply", z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+    z_drop(z_move(reply));
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void max_pending_queries() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/max_pending_queries");
+    z_queryable_options_t opts;
+    z_queryable_options_default(&opts);
+    opts.max_pending_queries = 1;
+    z_owned_queryable_t qable;
+    z_owned_fifo_handler_query_t queries;
+    assert(z_declare_queryable_with_handler(z_loan(s), &qable, &queries, z_loan(ke), 4, &opts) == Z_OK);
+
+    z_owned_fifo_handler_reply_t replies1, replies2, replies3;
+    z_owned_closure_reply_t callback;
+    z_fifo_channel_reply_new(&callback, &replies1, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(callback), NULL) == Z_OK);
+    z_fifo_channel_reply_new(&callback, &replies2, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(callback), NULL) == Z_OK);
+
+    // the second query is rejected while the first one is pending
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies2), &reply) == Z_OK);
+    assert(!z_reply_is_ok(z_loan(reply)));
+    z_drop(z_move(reply));
+    assert(z_recv(z_loan(replies2), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    z_owned_query_t none;
+    assert(z_try_recv(z_loan(queries), &none) == Z_CHANNEL_NODATA);
+    z_drop(z_move(query));
+    assert(z_recv(z_loan(replies1), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    // once the first query is finalized, new queries are accepted again
+    z_fifo_channel_reply_new(&callback, &replies3, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(callback), NULL) == Z_OK);
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    z_drop(z_move(query));
+    assert(z_recv(z_loan(replies3), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies1));
+    z_drop(z_move(replies2));
+    z_drop(z_move(replies3));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_drain_split() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/drain_split/**");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 8);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const char *keys[] = {"test/drain_split/a", "test/drain_split/b"};
+    for (int i = 0; i < 2; i++) {
+        z_view_keyexpr_t reply_ke;
+        z_view_keyexpr_from_str(&reply_ke, keys[i]);
+        z_owned_bytes_t payload;
+        z_bytes_copy_from_str(&payload, keys[i]);
+        assert(z_query_reply(z_loan(query), z_loan(reply_ke), z_move(payload), NULL) == Z_OK);
+    }
+    z_owned_bytes_t err_payload;
+    z_bytes_copy_from_str(&err_payload, "error");
+    assert(z_query_reply_err(z_loan(query), z_move(err_payload), NULL) == Z_OK);
+    z_drop(z_move(query));
+    z_sleep_ms(100);
+
+    z_owned_sample_t samples[4];
+    z_owned_reply_err_t errs[4];
+    size_t ok_count, err_count;
+    assert(z_fifo_handler_reply_drain_split(z_loan(replies), samples, errs, 4, &ok_count, &err_count) == Z_OK);
+    assert(ok_count == 2);
+    assert(err_count == 1);
+    for (size_t i = 0; i < ok_count; i++) {
+        z_view_string_t ke_str;
+        z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(samples[i])), &ke_str);
+        assert(strncmp(z_string_data(z_loan(ke_str)), keys[i], z_string_len(z_loan(ke_str))) == 0);
+        z_drop(z_move(samples[i]));
+    }
+    z_owned_string_t err_str;
+    z_bytes_to_string(z_reply_err_payload(z_loan(errs[0])), &err_str);
+    assert(strncmp(z_string_data(z_loan(err_str)), "error", z_string_len(z_loan(err_str))) == 0);
+    z_drop(z_move(err_str));
+    z_drop(z_move(errs[0]));
+
+    assert(z_fifo_handler_reply_drain_split(z_loan(replies), samples, errs, 4, &ok_count, &err_count) ==
+           Z_CHANNEL_DISCONNECTED);
+    assert(ok_count == 0 && err_count == 0);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+typedef struct high_water_context_t {
+    volatile int calls;
+    volatile size_t last_pending;
+    volatile int drops;
+} high_water_context_t;
+
+void on_high_water(size_t pending, void *arg) {
+    high_water_context_t *ctx = (high_water_context_t *)arg;
+    ctx->calls++;
+    ctx->last_pending = pending;
+}
+
+void on_high_water_drop(void *arg) { ((high_water_context_t *)arg)->drops++; }
+
+void reply_n(const z_loaned_query_t *query, int first, int n) {
+    for (int i = first; i < first + n; i++) {
+        char key[64];
+        snprintf(key, sizeof(key), "test/high_water/%d", i);
+        z_view_keyexpr_t reply_ke;
+        z_view_keyexpr_from_str(&reply_ke, key);
+        z_owned_bytes_t payload;
+        z_bytes_copy_from_str(&payload, key);
+        assert(z_query_reply(query, z_loan(reply_ke), z_move(payload), NULL) == Z_OK);
+    }
+}
+
+void reply_high_water() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/high_water/**");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    high_water_context_t ctx = {0, 0, 0};
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    zc_fifo_channel_reply_new_with_high_water(&reply_callback, &replies, 16, 3, on_high_water, on_high_water_drop,
+                                              &ctx);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+
+    // filling past the threshold notifies once
+    reply_n(z_loan(query), 0, 5);
+    z_sleep_ms(100);
+    z_owned_reply_t reply;
+    for (int i = 0; i < 5; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        z_drop(z_move(reply));
+    }
+    assert(ctx.calls == 1);
+    assert(ctx.last_pending == 3);
+
+    // crossing the threshold again after the consumer caught up notifies again
+    reply_n(z_loan(query), 5, 2);
+    z_sleep_ms(100);
+    for (int i = 0; i < 2; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        z_drop(z_move(reply));
+    }
+    assert(ctx.calls == 1);
+    reply_n(z_loan(query), 7, 4);
+    z_sleep_ms(100);
+    for (int i = 0; i < 4; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        z_drop(z_move(reply));
+    }
+    assert(ctx.calls == 2);
+
+    z_drop(z_move(query));
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    z_sleep_ms(100);
+    assert(ctx.drops == 1);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_recv_indexed() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/recv_indexed/**");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 8);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const char *keys[] = {"test/recv_indexed/a", "test/recv_indexed/b", "test/recv_indexed/c"};
+    for (int i = 0; i < 3; i++) {
+        z_view_keyexpr_t reply_ke;
+        z_view_keyexpr_from_str(&reply_ke, keys[i]);
+        z_owned_bytes_t payload;
+        z_bytes_copy_from_str(&payload, keys[i]);
+        assert(z_query_reply(z_loan(query), z_loan(reply_ke), z_move(payload), NULL) == Z_OK);
+    }
+    z_drop(z_move(query));
+
+    // reading a reply with the regular recv still consumes its index
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    z_drop(z_move(reply));
+    for (uint64_t expected = 1; expected < 3; expected++) {
+        uint64_t index = 0;
+        assert(z_fifo_handler_reply_recv_indexed(z_loan(replies), &reply, &index) == Z_OK);
+        assert(index == expected);
+        z_view_string_t ke_str;
+        z_keyexpr_as_view_string(z_sample_keyexpr(z_reply_ok(z_loan(reply))), &ke_str);
+        assert(strncmp(z_string_data(z_loan(ke_str)), keys[expected], z_string_len(z_loan(ke_str))) == 0);
+        z_drop(z_move(reply));
+    }
+    uint64_t index = 0;
+    assert(z_fifo_handler_reply_recv_indexed(z_loan(replies), &reply, &index) == Z_CHANNEL_DISCONNECTED);
+    assert(!z_internal_check(reply));
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void replies_reduce_latest() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    zc_config_insert_json5(z_loan_mut(config), "timestamping/enabled", "true");
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/reduce_latest");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t handler;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &handler, 8);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    z_timestamp_t older, newer;
+    z_timestamp_new(&older, z_loan(s));
+    z_sleep_ms(10);
+    z_timestamp_new(&newer, z_loan(s));
+    // the newest reply is neither the first nor the last one
+    z_timestamp_t *timestamps[] = {&older, &newer, &older};
+    const char *payloads[] = {"first", "newest", "last"};
+    for (int i = 0; i < 3; i++) {
+        z_query_reply_options_t reply_opts;
+        z_query_reply_options_default(&reply_opts);
+        reply_opts.timestamp = timestamps[i];
+        z_owned_bytes_t payload;
+        z_bytes_copy_from_str(&payload, payloads[i]);
+        assert(z_query_reply(z_loan(query), z_loan(ke), z_move(payload), &reply_opts) == Z_OK);
+    }
+    z_owned_bytes_t err_payload;
+    z_bytes_copy_from_str(&err_payload, "error");
+    assert(z_query_reply_err(z_loan(query), z_move(err_payload), NULL) == Z_OK);
+    z_drop(z_move(query));
+
+    z_owned_reply_t replies[4];
+    for (int i = 0; i < 4; i++) {
+        assert(z_recv(z_loan(handler), &replies[i]) == Z_OK);
+    }
+    z_owned_sample_t sample;
+    assert(z_replies_reduce_latest(replies, 4, &sample));
+    for (int i = 0; i < 4; i++) {
+        assert(!z_internal_check(replies[i]));
+    }
+    z_owned_string_t str;
+    z_bytes_to_string(z_sample_payload(z_loan(sample)), &str);
+    assert(strncmp(z_string_data(z_loan(str)), "newest", z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+    z_drop(z_move(sample));
+
+    assert(!z_replies_reduce_latest(replies, 4, &sample));
+    assert(!z_internal_check(sample));
+
+    z_drop(z_move(handler));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+typedef struct stored_values_t {
+    const char **keys;
+    size_t len;
+    size_t next;
+} stored_values_t;
+
+bool next_stored_value(void *context, const char **key_expr, const uint8_t **payload, size_t *len) {
+    stored_values_t *values = (stored_values_t *)context;
+    if (values->next == values->len) {
+        return false;
+    }
+    *key_expr = values->keys[values->next];
+    *payload = (const uint8_t *)values->keys[values->next];
+    *len = strlen(values->keys[values->next]);
+    values->next++;
+    return true;
+}
+
+void reply_from_iterator() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/from_iterator/**");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 8);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const char *keys[] = {"test/from_iterator/a", "test/from_iterator/b", "test/from_iterator/c"};
+    stored_values_t values = {keys, 3, 0};
+    assert(z_query_reply_from_iterator(z_loan(query), next_stored_value, &values) == Z_OK);
+    assert(values.next == 3);
+    // an invalid key expression stops the iteration
+    const char *invalid_keys[] = {"test/from_iterator//invalid", "test/from_iterator/d"};
+    stored_values_t invalid_values = {invalid_keys, 2, 0};
+    assert(z_query_reply_from_iterator(z_loan(query), next_stored_value, &invalid_values) == Z_EINVAL);
+    assert(invalid_values.next == 1);
+    z_drop(z_move(query));
+
+    z_owned_reply_t reply;
+    for (int i = 0; i < 3; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        const z_loaned_sample_t *sample = z_reply_ok(z_loan(reply));
+        z_view_string_t ke_str;
+        z_keyexpr_as_view_string(z_sample_keyexpr(sample), &ke_str);
+        assert(strncmp(z_string_data(z_loan(ke_str)), keys[i], z_string_len(z_loan(ke_str))) == 0);
+        z_owned_string_t payload_str;
+        z_bytes_to_string(z_sample_payload(sample), &payload_str);
+        assert(strncmp(z_string_data(z_loan(payload_str)), keys[i], z_string_len(z_loan(payload_str))) == 0);
+        z_drop(z_move(payload_str));
+        z_drop(z_move(reply));
+    }
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_with_timestamp(const z_loaned_query_t *query, const z_loaned_keyexpr_t *ke, const char *value,
+                          z_timestamp_t *timestamp) {
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, value);
+    z_query_reply_options_t options;
+    z_query_reply_options_default(&options);
+    options.timestamp = timestamp;
+    assert(z_query_reply(query, ke, z_move(payload), &options) == Z_OK);
+}
+
+void reply_latest() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke, ke_a, ke_b;
+    z_view_keyexpr_from_str(&ke, "test/reply_latest/**");
+    z_view_keyexpr_from_str(&ke_a, "test/reply_latest/a");
+    z_view_keyexpr_from_str(&ke_b, "test/reply_latest/b");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t inner;
+    z_fifo_channel_reply_new(&inner, &replies, 8);
+    z_owned_closure_reply_t reply_callback;
+    z_closure_reply_latest(&reply_callback, z_move(inner));
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_timestamp_t older, newer;
+    assert(z_timestamp_new(&older, z_loan(s)) == Z_OK);
+    z_sleep_ms(10);
+    assert(z_timestamp_new(&newer, z_loan(s)) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    // the newest reply is retained per key expression
+    reply_with_timestamp(z_loan(query), z_loan(ke_a), "older", &older);
+    reply_with_timestamp(z_loan(query), z_loan(ke_a), "a", &newer);
+    reply_with_timestamp(z_loan(query), z_loan(ke_a), "duplicate", &newer);
+    reply_with_timestamp(z_loan(query), z_loan(ke_a), "untimestamped", NULL);
+    reply_with_timestamp(z_loan(query), z_loan(ke_b), "b", &older);
+    reply_with_timestamp(z_loan(query), z_loan(ke_b), "untimestamped", NULL);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "error");
+    assert(z_query_reply_err(z_loan(query), z_move(payload), NULL) == Z_OK);
+
+    // error replies are forwarded right away, while the newest replies are only forwarded once the query is finished
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    assert(!z_reply_is_ok(z_loan(reply)));
+    z_drop(z_move(reply));
+    assert(z_try_recv(z_loan(replies), &reply) == Z_CHANNEL_NODATA);
+    z_drop(z_move(query));
+
+    // the retained replies are flushed in no particular order, each key's payload being its last letter
+    bool received_a = false, received_b = false;
+    for (int i = 0; i < 2; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        const z_loaned_sample_t *sample = z_reply_ok(z_loan(reply));
+        z_view_string_t ke_str;
+        z_keyexpr_as_view_string(z_sample_keyexpr(sample), &ke_str);
+        char last = z_string_data(z_loan(ke_str))[z_string_len(z_loan(ke_str)) - 1];
+        z_owned_string_t str;
+        z_bytes_to_string(z_sample_payload(sample), &str);
+        assert(z_string_len(z_loan(str)) == 1);
+        assert(z_string_data(z_loan(str))[0] == last);
+        z_drop(z_move(str));
+        received_a = received_a || last == 'a';
+        received_b = received_b || last == 'b';
+        z_drop(z_move(reply));
+    }
+    assert(received_a && received_b);
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_many() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/reply_many/**");
+    z_owned_closure_query_t callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(callback), NULL) == Z_OK);
+
+    z_get_options_t opts;
+    z_get_options_default(&opts);
+    opts.consolidation = z_query_consolidation_none();
+    z_owned_fifo_handler_reply_t replies;
+    z_owned_closure_reply_t reply_callback;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 8);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &opts) == Z_OK);
+
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    const char *keys[] = {"test/reply_many/a", "test/reply_many/b", "other/reply_many/c", "test/reply_many/d"};
+    z_view_keyexpr_t entry_kes[4];
+    z_moved_bytes_t payloads[4];
+    z_moved_encoding_t encodings[4];
+    z_query_reply_entry_t entries[4];
+    for (int i = 0; i < 4; i++) {
+        z_view_keyexpr_from_str(&entry_kes[i], keys[i]);
+        z_bytes_copy_from_str(&payloads[i]._this, keys[i]);
+        z_encoding_clone(&encodings[i]._this, z_encoding_text_plain());
+        entries[i].key_expr = z_loan(entry_kes[i]);
+        entries[i].payload = &payloads[i];
+        entries[i].encoding = &encodings[i];
+    }
+    entries[1].encoding = NULL;
+    z_drop(z_move(encodings[1]._this));
+
+    // the first two entries are sent in order
+    assert(z_query_reply_many(z_loan(query), entries, 2) == Z_OK);
+    // a key expression not matching the query fails, and the entries following it are not sent but still consumed
+    assert(z_query_reply_many(z_loan(query), entries + 2, 2) != Z_OK);
+    for (int i = 0; i < 4; i++) {
+        assert(!z_internal_check(payloads[i]._this));
+        assert(!z_internal_check(encodings[i]._this));
+    }
+    z_drop(z_move(query));
+
+    z_owned_reply_t reply;
+    for (int i = 0; i < 2; i++) {
+        assert(z_recv(z_loan(replies), &reply) == Z_OK);
+        const z_loaned_sample_t *sample = z_reply_ok(z_loan(reply));
+        z_view_string_t ke_str;
+        z_keyexpr_as_view_string(z_sample_keyexpr(sample), &ke_str);
+        assert(z_string_len(z_loan(ke_str)) == strlen(keys[i]));
+        assert(strncmp(z_string_data(z_loan(ke_str)), keys[i], z_string_len(z_loan(ke_str))) == 0);
+        z_owned_string_t payload_str;
+        z_bytes_to_string(z_sample_payload(sample), &payload_str);
+        assert(strncmp(z_string_data(z_loan(payload_str)), keys[i], z_string_len(z_loan(payload_str))) == 0);
+        z_drop(z_move(payload_str));
+        if (i == 0) {
+            assert(z_encoding_equals(z_sample_encoding(sample), z_encoding_text_plain()));
+        } else {
+            assert(z_encoding_equals(z_sample_encoding(sample), z_encoding_zenoh_bytes()));
+        }
+        z_drop(z_move(reply));
+    }
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+}
+
+void reply_deadline() {
+    z_owned_closure_reply_t inner;
+    z_owned_fifo_handler_reply_t replies;
+    z_fifo_channel_reply_new(&inner, &replies, 4);
+    z_owned_closure_reply_t closure;
+    assert(z_closure_reply_deadline(&closure, z_move(inner), 50) == Z_OK);
+
+    // past the deadline, a single error reply is delivered and the inner closure is dropped
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_OK);
+    assert(!z_reply_is_ok(z_loan(reply)));
+    const z_loaned_reply_err_t *err = z_reply_err(z_loan(reply));
+    z_owned_string_t err_str;
+    z_bytes_to_string(z_reply_err_payload(err), &err_str);
+    assert(strncmp(z_string_data(z_loan(err_str)), "timed out", z_string_len(z_loan(err_str))) == 0);
+    assert(z_string_len(z_loan(err_str)) == 9);
+    z_drop(z_move(err_str));
+    z_drop(z_move(reply));
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(closure));
+    z_drop(z_move(replies));
+
+    // dropping the closure before the deadline drops the inner closure without error reply
+    z_fifo_channel_reply_new(&inner, &replies, 4);
+    assert(z_closure_reply_deadline(&closure, z_move(inner), 10000) == Z_OK);
+    z_clock_t start = z_clock_now();
+    z_drop(z_move(closure));
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    assert(z_clock_elapsed_ms(&start) < 5000);
+    z_drop(z_move(replies));
+}
+#endif
+
+int main(int argc, char **argv) {
+    get_with_payload();
+    get_with_ring_handler();
+    query_recv_timeout();
+    reply_into_result();
+    reply_err_as_string();
+#if defined(Z_FEATURE_UNSTABLE_API)
+    queryable_with_handler();
+    max_pending_queries();
+    reply_drain_split();
+    reply_high_water();
+    reply_recv_indexed();
+    replies_reduce_latest();
+    reply_from_iterator();
+    reply_latest();
+    reply_many();
+    reply_deadline();
+#endif
+    return 0;
+}
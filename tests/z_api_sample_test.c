@@ -0,0 +1,1037 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#if defined(Z_FEATURE_UNSTABLE_API) && defined(__unix__)
+#include <fcntl.h>
+#include <poll.h>
+#include <sys/socket.h>
+#include <unistd.h>
+#endif
+
+#undef NDEBUG
+#include <assert.h>
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+void sample_new_for_test() {
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/sample");
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "data");
+    z_owned_encoding_t encoding;
+    z_encoding_clone(&encoding, z_encoding_text_plain());
+
+    z_owned_sample_t sample;
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), z_move(encoding), Z_SAMPLE_KIND_PUT);
+    assert(z_internal_check(sample));
+    assert(z_sample_kind(z_loan(sample)) == Z_SAMPLE_KIND_PUT);
+    assert(z_sample_timestamp(z_loan(sample)) == NULL);
+    assert(z_encoding_equals(z_sample_encoding(z_loan(sample)), z_encoding_text_plain()));
+
+    z_view_string_t ke_str;
+    z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ke_str);
+    assert(strncmp(z_string_data(z_loan(ke_str)), "test/sample", z_string_len(z_loan(ke_str))) == 0);
+
+    // feed it to a channel, as a subscriber would
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 4);
+    assert(z_fifo_handler_sample_bytes(z_loan(handler)) == 0);
+    z_call(z_loan(closure), z_loan_mut(sample));
+    z_drop(z_move(closure));
+    assert(z_fifo_handler_sample_bytes(z_loan(handler)) == 4);
+
+    z_owned_sample_t received;
+    assert(z_recv(z_loan(handler), &received) == Z_OK);
+    assert(z_fifo_handler_sample_bytes(z_loan(handler)) == 0);
+    z_owned_string_t received_payload;
+    z_bytes_to_string(z_sample_payload(z_loan(received)), &received_payload);
+    assert(strncmp(z_string_data(z_loan(received_payload)), "data", z_string_len(z_loan(received_payload))) == 0);
+    z_drop(z_move(received_payload));
+    z_drop(z_move(received));
+    assert(z_recv(z_loan(handler), &received) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(handler));
+    z_drop(z_move(sample));
+
+    z_bytes_empty(&payload);
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), NULL, Z_SAMPLE_KIND_DELETE);
+    assert(z_sample_kind(z_loan(sample)) == Z_SAMPLE_KIND_DELETE);
+    z_drop(z_move(sample));
+}
+
+void push_sample_with_payload(const z_loaned_closure_sample_t *closure, const char *key, const char *value) {
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, key);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, value);
+    z_owned_sample_t sample;
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), NULL, Z_SAMPLE_KIND_PUT);
+    z_call(closure, z_loan_mut(sample));
+    z_drop(z_move(sample));
+}
+
+void push_sample(const z_loaned_closure_sample_t *closure, const char *key) {
+    push_sample_with_payload(closure, key, key);
+}
+
+size_t count_samples(const z_loaned_fifo_handler_sample_t *handler, const char *key) {
+    size_t count = 0;
+    z_owned_sample_t sample;
+    while (z_try_recv(handler, &sample) == Z_OK) {
+        z_view_string_t ke_str;
+        z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ke_str);
+        if (strncmp(z_string_data(z_loan(ke_str)), key, z_string_len(z_loan(ke_str))) == 0) {
+            count++;
+        }
+        z_drop(z_move(sample));
+    }
+    return count;
+}
+
+void shard() {
+    z_owned_closure_sample_t inners[2];
+    z_owned_fifo_handler_sample_t handlers[2];
+    z_fifo_channel_sample_new(&inners[0], &handlers[0], 16);
+    z_fifo_channel_sample_new(&inners[1], &handlers[1], 16);
+    z_moved_closure_sample_t moved[2] = {*z_move(inners[0]), *z_move(inners[1])};
+
+    z_owned_closure_sample_t closure;
+    assert(z_closure_sample_shard(&closure, moved, 2) == Z_OK);
+    for (int i = 0; i < 3; i++) {
+        push_sample(z_loan(closure), "test/shard/a");
+    }
+    z_drop(z_move(closure));
+
+    size_t a0 = count_samples(z_loan(handlers[0]), "test/shard/a");
+    size_t a1 = count_samples(z_loan(handlers[1]), "test/shard/a");
+    assert((a0 == 3 && a1 == 0) || (a0 == 0 && a1 == 3));
+    z_drop(z_move(handlers[0]));
+    z_drop(z_move(handlers[1]));
+
+    assert(z_closure_sample_shard(&closure, NULL, 0) == Z_EINVAL);
+    assert(!z_internal_check(closure));
+}
+
+void on_change() {
+    z_owned_closure_sample_t inner;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&inner, &handler, 16);
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t suppressed;
+    z_closure_sample_on_change(&closure, z_move(inner), &suppressed);
+
+    push_sample_with_payload(z_loan(closure), "test/on_change/a", "1");
+    push_sample_with_payload(z_loan(closure), "test/on_change/a", "1");
+    push_sample_with_payload(z_loan(closure), "test/on_change/a", "2");
+    push_sample_with_payload(z_loan(closure), "test/on_change/a", "2");
+    push_sample_with_payload(z_loan(closure), "test/on_change/a", "1");
+    assert(count_samples(z_loan(handler), "test/on_change/a") == 3);
+
+    // the last payload is remembered per key expression
+    push_sample_with_payload(z_loan(closure), "test/on_change/b", "1");
+    push_sample_with_payload(z_loan(closure), "test/on_change/a", "1");
+    assert(count_samples(z_loan(handler), "test/on_change/b") == 1);
+    assert(zc_sample_counter_get(z_loan(suppressed)) == 3);
+
+    z_drop(z_move(closure));
+    z_drop(z_move(handler));
+    z_drop(z_move(suppressed));
+}
+
+void size_filter() {
+    z_owned_closure_sample_t inner;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&inner, &handler, 16);
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t filtered;
+    z_closure_sample_size_filter(&closure, z_move(inner), 2, 4, &filtered);
+
+    push_sample_with_payload(z_loan(closure), "test/size_filter", "a");
+    push_sample_with_payload(z_loan(closure), "test/size_filter", "ab");
+    push_sample_with_payload(z_loan(closure), "test/size_filter", "abcd");
+    push_sample_with_payload(z_loan(closure), "test/size_filter", "abcde");
+    assert(zc_sample_counter_get(z_loan(filtered)) == 2);
+
+    // bounds are inclusive
+    z_owned_sample_t sample;
+    z_owned_string_t payload;
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_bytes_to_string(z_sample_payload(z_loan(sample)), &payload);
+    assert(z_string_len(z_loan(payload)) == 2);
+    z_drop(z_move(payload));
+    z_drop(z_move(sample));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_bytes_to_string(z_sample_payload(z_loan(sample)), &payload);
+    assert(z_string_len(z_loan(payload)) == 4);
+    z_drop(z_move(payload));
+    z_drop(z_move(sample));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+
+    // the counter outlives the closure, and a NULL counter is accepted
+    z_drop(z_move(closure));
+    assert(zc_sample_counter_get(z_loan(filtered)) == 2);
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(filtered));
+    z_drop(z_move(handler));
+
+    z_fifo_channel_sample_new(&inner, &handler, 16);
+    z_closure_sample_size_filter(&closure, z_move(inner), 0, 0, NULL);
+    push_sample_with_payload(z_loan(closure), "test/size_filter", "a");
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+    z_drop(z_move(closure));
+    z_drop(z_move(handler));
+}
+
+void push_double(const z_loaned_closure_sample_t *closure, const char *key, double value) {
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, key);
+    z_owned_bytes_t payload;
+    ze_serialize_double(&payload, value);
+    z_owned_sample_t sample;
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), NULL, Z_SAMPLE_KIND_PUT);
+    z_call(closure, z_loan_mut(sample));
+    z_drop(z_move(sample));
+}
+
+double recv_double(const z_loaned_fifo_handler_sample_t *handler) {
+    z_owned_sample_t sample;
+    assert(z_try_recv(handler, &sample) == Z_OK);
+    double value = 0.0;
+    assert(ze_deserialize_double(z_sample_payload(z_loan(sample)), &value) == Z_OK);
+    z_drop(z_move(sample));
+    return value;
+}
+
+void downsample_avg() {
+    z_owned_closure_sample_t inner;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&inner, &handler, 16);
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t non_numeric;
+    z_closure_sample_downsample_avg(&closure, z_move(inner), 2, &non_numeric);
+
+    push_double(z_loan(closure), "test/downsample/a", 1.0);
+    z_owned_sample_t sample;
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+    push_double(z_loan(closure), "test/downsample/a", 2.0);
+    assert(recv_double(z_loan(handler)) == 1.5);
+
+    // windows are accumulated per key expression
+    push_double(z_loan(closure), "test/downsample/a", 4.0);
+    push_double(z_loan(closure), "test/downsample/b", 10.0);
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+    push_double(z_loan(closure), "test/downsample/b", 20.0);
+    assert(recv_double(z_loan(handler)) == 15.0);
+    push_double(z_loan(closure), "test/downsample/a", 6.0);
+    assert(recv_double(z_loan(handler)) == 5.0);
+
+    // non-numeric samples are forwarded unchanged and counted
+    push_sample_with_payload(z_loan(closure), "test/downsample/a", "x");
+    assert(count_samples(z_loan(handler), "test/downsample/a") == 1);
+    assert(zc_sample_counter_get(z_loan(non_numeric)) == 1);
+
+    z_drop(z_move(closure));
+    z_drop(z_move(handler));
+    z_drop(z_move(non_numeric));
+}
+
+void split_put_delete() {
+    z_owned_closure_sample_t on_put, on_delete;
+    z_owned_fifo_handler_sample_t puts, deletes;
+    z_fifo_channel_sample_new(&on_put, &puts, 16);
+    z_fifo_channel_sample_new(&on_delete, &deletes, 16);
+    z_owned_closure_sample_t closure;
+    z_closure_sample_split_put_delete(&closure, z_move(on_put), z_move(on_delete));
+
+    push_sample(z_loan(closure), "test/split/a");
+    push_sample(z_loan(closure), "test/split/a");
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/split/a");
+    z_owned_bytes_t payload;
+    z_bytes_empty(&payload);
+    z_owned_sample_t sample;
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), NULL, Z_SAMPLE_KIND_DELETE);
+    z_call(z_loan(closure), z_loan_mut(sample));
+    z_drop(z_move(sample));
+
+    // dropping the closure drops both inner closures, disconnecting both channels
+    z_drop(z_move(closure));
+    assert(count_samples(z_loan(puts), "test/split/a") == 2);
+    assert(z_try_recv(z_loan(deletes), &sample) == Z_OK);
+    assert(z_sample_kind(z_loan(sample)) == Z_SAMPLE_KIND_DELETE);
+    z_drop(z_move(sample));
+    assert(z_recv(z_loan(puts), &sample) == Z_CHANNEL_DISCONNECTED);
+    assert(z_recv(z_loan(deletes), &sample) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(puts));
+    z_drop(z_move(deletes));
+}
+
+void *consume_samples(void *arg) {
+    const z_loaned_fifo_handler_sample_t *handler = (const z_loaned_fifo_handler_sample_t *)arg;
+    z_owned_sample_t sample;
+    for (int i = 0; i < 3; i++) {
+        z_sleep_ms(10);
+        assert(z_recv(handler, &sample) == Z_OK);
+        z_drop(z_move(sample));
+    }
+    return NULL;
+}
+
+void wait_empty() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+    assert(z_fifo_handler_sample_wait_empty(z_loan(handler), 0) == Z_OK);
+
+    for (int i = 0; i < 3; i++) {
+        push_sample(z_loan(closure), "test/wait_empty");
+    }
+    assert(z_fifo_handler_sample_wait_empty(z_loan(handler), 10) == Z_ETIMEOUT);
+
+    z_owned_task_t task;
+    z_task_init(&task, NULL, consume_samples, (void *)z_loan(handler));
+    assert(z_fifo_handler_sample_wait_empty(z_loan(handler), 5000) == Z_OK);
+    z_task_join(z_move(task));
+
+    z_drop(z_move(closure));
+    z_drop(z_move(handler));
+}
+
+void recv_with_latency() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    zc_fifo_channel_sample_new_with_latency(&closure, &handler, 16);
+    push_sample(z_loan(closure), "test/latency");
+    z_sleep_ms(50);
+
+    z_owned_sample_t sample;
+    uint64_t latency_us = 0;
+    assert(z_fifo_handler_sample_recv_with_latency(z_loan(handler), &sample, &latency_us) == Z_OK);
+    assert(latency_us >= 50000);
+    z_drop(z_move(sample));
+    z_drop(z_move(closure));
+    assert(z_fifo_handler_sample_recv_with_latency(z_loan(handler), &sample, &latency_us) == Z_CHANNEL_DISCONNECTED);
+    assert(!z_internal_check(sample));
+    z_drop(z_move(handler));
+
+    // the latency is not recorded by regular fifo channels
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+    push_sample(z_loan(closure), "test/latency");
+    assert(z_fifo_handler_sample_recv_with_latency(z_loan(handler), &sample, &latency_us) == Z_OK);
+    assert(latency_us == 0);
+    z_drop(z_move(sample));
+    z_drop(z_move(closure));
+    z_drop(z_move(handler));
+}
+
+void recv_timeout() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+
+    z_owned_sample_t sample;
+    z_clock_t start = z_clock_now();
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 50) == Z_ETIMEOUT);
+    assert(z_clock_elapsed_ms(&start) >= 50);
+    assert(!z_internal_check(sample));
+
+    push_sample(z_loan(closure), "test/timeout");
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 50) == Z_OK);
+    assert(z_internal_check(sample));
+    z_drop(z_move(sample));
+
+    z_drop(z_move(closure));
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 50) == Z_CHANNEL_DISCONNECTED);
+    assert(!z_internal_check(sample));
+    z_drop(z_move(handler));
+}
+
+void fifo_len() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+    assert(z_fifo_handler_sample_is_empty(z_loan(handler)));
+    for (int i = 0; i < 3; i++) {
+        push_sample(z_loan(closure), "test/len");
+    }
+    z_drop(z_move(closure));
+    assert(z_fifo_handler_sample_len(z_loan(handler)) == 3);
+    assert(!z_fifo_handler_sample_is_empty(z_loan(handler)));
+
+    // drain exactly the remaining samples
+    size_t remaining = z_fifo_handler_sample_len(z_loan(handler));
+    for (size_t i = 0; i < remaining; i++) {
+        z_owned_sample_t sample;
+        assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+        z_drop(z_move(sample));
+    }
+    assert(z_fifo_handler_sample_len(z_loan(handler)) == 0);
+    assert(z_fifo_handler_sample_is_empty(z_loan(handler)));
+    z_drop(z_move(handler));
+}
+
+long recorded_file_size(const char *path) {
+    FILE *f = fopen(path, "rb");
+    if (f == NULL) {
+        return -1;
+    }
+    fseek(f, 0, SEEK_END);
+    long size = ftell(f);
+    fclose(f);
+    return size;
+}
+
+void to_rotating_file() {
+    remove("z_api_sample_recording.0");
+    remove("z_api_sample_recording.1");
+    remove("z_api_sample_recording.2");
+
+    // each payload takes 6 bytes, so that each file holds 2 of them
+    z_owned_closure_sample_t closure;
+    assert(z_closure_sample_to_rotating_file(&closure, ".", "z_api_sample_recording", 12, 2) == Z_OK);
+    for (int i = 0; i < 5; i++) {
+        push_sample_with_payload(z_loan(closure), "test/recording", "hello");
+    }
+    z_drop(z_move(closure));
+
+    // the oldest file is removed to keep at most 2 files
+    assert(recorded_file_size("z_api_sample_recording.0") == -1);
+    assert(recorded_file_size("z_api_sample_recording.1") == 12);
+    assert(recorded_file_size("z_api_sample_recording.2") == 6);
+
+    FILE *f = fopen("z_api_sample_recording.2", "rb");
+    uint8_t buf[6];
+    assert(fread(buf, 1, 6, f) == 6);
+    fclose(f);
+    assert(buf[0] == 5);
+    assert(memcmp(buf + 1, "hello", 5) == 0);
+
+    remove("z_api_sample_recording.1");
+    remove("z_api_sample_recording.2");
+
+    assert(z_closure_sample_to_rotating_file(&closure, ".", "z_api_sample_recording", 0, 2) == Z_EINVAL);
+    assert(!z_internal_check(closure));
+}
+
+void spsc_channel() {
+    z_owned_sample_t buffer[2];
+    z_owned_closure_sample_t closure;
+    z_owned_spsc_handler_sample_t handler;
+    assert(z_spsc_channel_sample_new(&closure, &handler, buffer, 2) == Z_OK);
+
+    z_owned_sample_t sample;
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+    push_sample(z_loan(closure), "test/spsc/a");
+    push_sample(z_loan(closure), "test/spsc/b");
+    push_sample(z_loan(closure), "test/spsc/c");
+    assert(z_spsc_handler_sample_dropped(z_loan(handler)) == 1);
+
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_view_string_t ke_str;
+    z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ke_str);
+    assert(strncmp(z_string_data(z_loan(ke_str)), "test/spsc/a", z_string_len(z_loan(ke_str))) == 0);
+    z_drop(z_move(sample));
+
+    push_sample(z_loan(closure), "test/spsc/d");
+    z_drop(z_move(closure));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_drop(z_move(sample));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ke_str);
+    assert(strncmp(z_string_data(z_loan(ke_str)), "test/spsc/d", z_string_len(z_loan(ke_str))) == 0);
+    z_drop(z_move(sample));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(handler));
+
+    assert(z_spsc_channel_sample_new(&closure, &handler, NULL, 2) == Z_EINVAL);
+    assert(!z_internal_check(handler));
+}
+
+void *push_spsc_samples(void *arg) {
+    const z_loaned_closure_sample_t *closure = (const z_loaned_closure_sample_t *)arg;
+    for (int i = 0; i < 100; i++) {
+        push_sample(closure, "test/spsc/concurrent");
+    }
+    return NULL;
+}
+
+void spsc_channel_concurrent_producers() {
+    // a subscriber callback may be called concurrently, so may be the send end of the channel
+    z_owned_sample_t buffer[64];
+    z_owned_closure_sample_t closure;
+    z_owned_spsc_handler_sample_t handler;
+    assert(z_spsc_channel_sample_new(&closure, &handler, buffer, 64) == Z_OK);
+
+    z_owned_task_t tasks[4];
+    for (int i = 0; i < 4; i++) {
+        z_task_init(&tasks[i], NULL, push_spsc_samples, (void *)z_loan(closure));
+    }
+    size_t received = 0;
+    z_owned_sample_t sample;
+    for (int i = 0; i < 4; i++) {
+        z_task_join(z_move(tasks[i]));
+        while (z_try_recv(z_loan(handler), &sample) == Z_OK) {
+            assert(z_internal_check(sample));
+            received++;
+            z_drop(z_move(sample));
+        }
+    }
+    z_drop(z_move(closure));
+    while (z_try_recv(z_loan(handler), &sample) == Z_OK) {
+        received++;
+        z_drop(z_move(sample));
+    }
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_DISCONNECTED);
+    assert(received + z_spsc_handler_sample_dropped(z_loan(handler)) == 400);
+    assert(received >= 64);
+    z_drop(z_move(handler));
+}
+
+typedef struct republish_context_t {
+    int calls;
+    int drops;
+} republish_context_t;
+
+void republish_key_map(const z_loaned_keyexpr_t *key_expr, z_owned_keyexpr_t *target, void *arg) {
+    republish_context_t *ctx = (republish_context_t *)arg;
+    ctx->calls++;
+    z_view_string_t ke_str;
+    z_keyexpr_as_view_string(key_expr, &ke_str);
+    const char *prefix = "test/republish/in/";
+    size_t prefix_len = strlen(prefix);
+    size_t len = z_string_len(z_loan(ke_str));
+    const char *data = z_string_data(z_loan(ke_str));
+    if (len <= prefix_len || strncmp(data, prefix, prefix_len) != 0 ||
+        strncmp(data + prefix_len, "skip", len - prefix_len) == 0) {
+        return;
+    }
+    char buf[128];
+    snprintf(buf, sizeof(buf), "test/republish/out/%.*s", (int)(len - prefix_len), data + prefix_len);
+    z_keyexpr_from_str(target, buf);
+}
+
+void republish_drop(void *arg) { ((republish_context_t *)arg)->drops++; }
+
+void republish() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t out_ke;
+    z_view_keyexpr_from_str(&out_ke, "test/republish/out/**");
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_t out_sub;
+    assert(z_declare_subscriber(z_loan(s), &out_sub, z_loan(out_ke), z_move(callback), NULL) == Z_OK);
+
+    republish_context_t ctx = {0, 0};
+    z_owned_closure_sample_t closure;
+    z_closure_sample_republish(&closure, z_loan(s), republish_key_map, republish_drop, &ctx);
+    z_view_keyexpr_t in_ke;
+    z_view_keyexpr_from_str(&in_ke, "test/republish/in/**");
+    z_owned_subscriber_t in_sub;
+    assert(z_declare_subscriber(z_loan(s), &in_sub, z_loan(in_ke), z_move(closure), NULL) == Z_OK);
+
+    // the sample is republished on the mapped key expression, with its payload and timestamp
+    z_timestamp_t ts;
+    assert(z_timestamp_new(&ts, z_loan(s)) == Z_OK);
+    z_put_options_t options;
+    z_put_options_default(&options);
+    options.timestamp = &ts;
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/republish/in/a");
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "bridged");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), &options) == Z_OK);
+
+    z_owned_sample_t sample;
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 5000) == Z_OK);
+    z_view_string_t ke_str;
+    z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ke_str);
+    assert(z_string_len(z_loan(ke_str)) == strlen("test/republish/out/a"));
+    assert(strncmp(z_string_data(z_loan(ke_str)), "test/republish/out/a", z_string_len(z_loan(ke_str))) == 0);
+    z_owned_string_t received;
+    z_bytes_to_string(z_sample_payload(z_loan(sample)), &received);
+    assert(strncmp(z_string_data(z_loan(received)), "bridged", z_string_len(z_loan(received))) == 0);
+    z_drop(z_move(received));
+    const z_timestamp_t *received_ts = z_sample_timestamp(z_loan(sample));
+    assert(received_ts != NULL);
+    assert(z_timestamp_ntp64_time(received_ts) == z_timestamp_ntp64_time(&ts));
+    z_drop(z_move(sample));
+
+    // samples left unmapped are not republished
+    z_view_keyexpr_from_str(&ke, "test/republish/in/skip");
+    z_bytes_copy_from_str(&payload, "skipped");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 500) == Z_ETIMEOUT);
+    assert(ctx.calls == 2);
+
+    z_drop(z_move(in_sub));
+    assert(ctx.drops == 1);
+    z_drop(z_move(out_sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void drain_until() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+    for (int i = 0; i < 5; i++) {
+        push_sample(z_loan(closure), "test/drain_until");
+    }
+
+    // a zero budget pops a single sample
+    z_owned_sample_t out[4];
+    assert(z_fifo_handler_sample_drain_until(z_loan(handler), out, 4, 0) == 1);
+    z_drop(z_move(out[0]));
+    assert(z_fifo_handler_sample_len(z_loan(handler)) == 4);
+
+    // popping stops at max, then when the buffer is empty
+    size_t n = z_fifo_handler_sample_drain_until(z_loan(handler), out, 2, 1000);
+    assert(n == 2);
+    for (size_t i = 0; i < n; i++) {
+        assert(z_internal_check(out[i]));
+        z_drop(z_move(out[i]));
+    }
+    n = z_fifo_handler_sample_drain_until(z_loan(handler), out, 4, 1000);
+    assert(n == 2);
+    for (size_t i = 0; i < n; i++) {
+        z_drop(z_move(out[i]));
+    }
+    assert(z_fifo_handler_sample_drain_until(z_loan(handler), out, 4, 1000) == 0);
+
+    z_drop(z_move(closure));
+    assert(z_fifo_handler_sample_drain_until(z_loan(handler), out, 4, 1000) == 0);
+    z_drop(z_move(handler));
+}
+
+typedef struct drain_context_t {
+    int calls;
+    int drops;
+    bool in_order;
+} drain_context_t;
+
+void drain_sample(z_loaned_sample_t *sample, void *arg) {
+    drain_context_t *ctx = (drain_context_t *)arg;
+    char expected[32];
+    snprintf(expected, sizeof(expected), "test/drain_drop/%d", ctx->calls);
+    z_view_string_t ke_str;
+    z_keyexpr_as_view_string(z_sample_keyexpr(sample), &ke_str);
+    if (z_string_len(z_loan(ke_str)) != strlen(expected) ||
+        strncmp(z_string_data(z_loan(ke_str)), expected, z_string_len(z_loan(ke_str))) != 0) {
+        ctx->in_order = false;
+    }
+    ctx->calls++;
+}
+
+void drain_drop(void *arg) { ((drain_context_t *)arg)->drops++; }
+
+void fifo_drain_drop() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+    for (int i = 0; i < 3; i++) {
+        char key[32];
+        snprintf(key, sizeof(key), "test/drain_drop/%d", i);
+        push_sample(z_loan(closure), key);
+    }
+
+    // every pending sample is passed to the drain closure in order, then both the handler and the closure are dropped
+    drain_context_t ctx;
+    ctx.calls = 0;
+    ctx.drops = 0;
+    ctx.in_order = true;
+    z_owned_closure_sample_t drain;
+    z_closure(&drain, drain_sample, drain_drop, &ctx);
+    z_fifo_handler_sample_drain_drop(z_move(handler), z_move(drain));
+    assert(ctx.calls == 3);
+    assert(ctx.in_order);
+    assert(ctx.drops == 1);
+    assert(!z_internal_check(handler));
+    assert(!z_internal_check(drain));
+
+    // samples received after the handler was dropped are discarded
+    push_sample(z_loan(closure), "test/drain_drop/3");
+    assert(ctx.calls == 3);
+    z_drop(z_move(closure));
+
+    // a gravestone handler only drops the drain closure
+    z_internal_null(&handler);
+    z_closure(&drain, drain_sample, drain_drop, &ctx);
+    z_fifo_handler_sample_drain_drop(z_move(handler), z_move(drain));
+    assert(ctx.calls == 3);
+    assert(ctx.drops == 2);
+}
+
+void forward_retry() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/forward_retry");
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback), NULL) == Z_OK);
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t overflowed, failed;
+    assert(z_closure_sample_forward_retry(&closure, z_move(pub), 3, 10, 2, &overflowed, &failed) == Z_OK);
+    // the closure owns the publisher
+    assert(!z_internal_check(pub));
+    push_sample_with_payload(z_loan(closure), "test/sample", "forwarded");
+    z_owned_sample_t sample;
+    assert(z_recv(z_loan(handler), &sample) == Z_OK);
+    z_owned_string_t payload;
+    z_bytes_to_string(z_sample_payload(z_loan(sample)), &payload);
+    assert(strncmp(z_string_data(z_loan(payload)), "forwarded", z_string_len(z_loan(payload))) == 0);
+    z_drop(z_move(payload));
+    z_drop(z_move(sample));
+
+    // publications fail once the session is closed: samples are buffered, and dropped on overflow
+    assert(z_close(z_loan_mut(s), NULL) == Z_OK);
+    for (int i = 0; i < 3; i++) {
+        push_sample(z_loan(closure), "test/sample");
+    }
+    // dropping the closure stops the retries
+    z_drop(z_move(closure));
+    assert(z_try_recv(z_loan(handler), &sample) != Z_OK);
+    assert(zc_sample_counter_get(z_loan(overflowed)) + zc_sample_counter_get(z_loan(failed)) == 3);
+    z_drop(z_move(overflowed));
+    z_drop(z_move(failed));
+
+    // invalid arguments still consume the publisher
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+    assert(z_closure_sample_forward_retry(&closure, z_move(pub), 3, 10, 0, &overflowed, NULL) == Z_EINVAL);
+    assert(!z_internal_check(closure));
+    assert(!z_internal_check(overflowed));
+    assert(!z_internal_check(pub));
+    assert(z_closure_sample_forward_retry(&closure, z_move(pub), 3, 10, 2, NULL, NULL) == Z_EINVAL);
+    assert(!z_internal_check(closure));
+
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void forward_retry_order() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/forward_retry/order");
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 128);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback), NULL) == Z_OK);
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t overflowed, failed;
+    assert(z_closure_sample_forward_retry(&closure, z_move(pub), 3, 10, 4, &overflowed, &failed) == Z_OK);
+    for (int i = 0; i < 100; i++) {
+        push_double(z_loan(closure), "test/sample", (double)i);
+    }
+    for (int i = 0; i < 100; i++) {
+        z_owned_sample_t sample;
+        assert(z_recv(z_loan(handler), &sample) == Z_OK);
+        double value = -1.0;
+        assert(ze_deserialize_double(z_sample_payload(z_loan(sample)), &value) == Z_OK);
+        assert(value == (double)i);
+        z_drop(z_move(sample));
+    }
+    z_drop(z_move(closure));
+    assert(zc_sample_counter_get(z_loan(overflowed)) == 0);
+    assert(zc_sample_counter_get(z_loan(failed)) == 0);
+    z_drop(z_move(overflowed));
+    z_drop(z_move(failed));
+
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void forward_retry_overflow() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/forward_retry/overflow");
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+    assert(z_close(z_loan_mut(s), NULL) == Z_OK);
+
+    // with a long backoff, the first failed sample stays in flight while the following ones fill the buffer
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t overflowed, failed;
+    assert(z_closure_sample_forward_retry(&closure, z_move(pub), 100, 10000, 2, &overflowed, &failed) == Z_OK);
+    for (int i = 0; i < 5; i++) {
+        push_sample(z_loan(closure), "test/sample");
+    }
+    // at most one sample in flight and two buffered remain, the others were dropped on overflow
+    assert(zc_sample_counter_get(z_loan(overflowed)) >= 2);
+
+    // dropping the closure interrupts the backoff, and counts the remaining samples as failed
+    z_clock_t start = z_clock_now();
+    z_drop(z_move(closure));
+    assert(z_clock_elapsed_ms(&start) < 5000);
+    assert(zc_sample_counter_get(z_loan(overflowed)) + zc_sample_counter_get(z_loan(failed)) == 5);
+    z_drop(z_move(overflowed));
+    z_drop(z_move(failed));
+
+    z_drop(z_move(s));
+}
+
+#if defined(__unix__)
+void to_socket() {
+    int fds[2];
+    assert(socketpair(AF_UNIX, SOCK_DGRAM, 0, fds) == 0);
+    fcntl(fds[0], F_SETFL, fcntl(fds[0], F_GETFL) | O_NONBLOCK);
+    fcntl(fds[1], F_SETFL, fcntl(fds[1], F_GETFL) | O_NONBLOCK);
+
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t dropped;
+    z_closure_sample_to_socket(&closure, fds[0], &dropped);
+    push_sample_with_payload(z_loan(closure), "test/socket", "hello");
+
+    uint8_t buf[64];
+    assert(read(fds[1], buf, sizeof(buf)) == 6);
+    assert(buf[0] == 5);
+    assert(memcmp(buf + 1, "hello", 5) == 0);
+
+    // a full socket drops samples instead of blocking
+    for (int i = 0; i < 10000; i++) {
+        push_sample_with_payload(z_loan(closure), "test/socket", "hello");
+    }
+    z_drop(z_move(closure));
+
+    // the socket is still open after the closure is dropped
+    int frames = 0;
+    while (read(fds[1], buf, sizeof(buf)) == 6) {
+        frames++;
+    }
+    assert(frames > 0 && frames < 10000);
+    assert(frames + zc_sample_counter_get(z_loan(dropped)) == 10000);
+    z_drop(z_move(dropped));
+    assert(write(fds[0], "x", 1) == 1);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+void to_stream_socket_partial_write() {
+    int fds[2];
+    assert(socketpair(AF_UNIX, SOCK_STREAM, 0, fds) == 0);
+    fcntl(fds[0], F_SETFL, fcntl(fds[0], F_GETFL) | O_NONBLOCK);
+    fcntl(fds[1], F_SETFL, fcntl(fds[1], F_GETFL) | O_NONBLOCK);
+
+    z_owned_closure_sample_t closure;
+    zc_owned_sample_counter_t dropped;
+    z_closure_sample_to_socket(&closure, fds[0], &dropped);
+
+    // a payload larger than the socket buffer is partially written, without blocking
+    size_t len = 16 * 1024 * 1024;
+    uint8_t *data = (uint8_t *)malloc(len);
+    memset(data, 'x', len);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/socket/stream");
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_buf(&payload, data, len);
+    z_owned_sample_t sample;
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), NULL, Z_SAMPLE_KIND_PUT);
+
+    z_clock_t start = z_clock_now();
+    z_call(z_loan(closure), z_loan_mut(sample));
+    assert(z_clock_elapsed_ms(&start) < 5000);
+    assert(zc_sample_counter_get(z_loan(dropped)) == 0);
+    z_drop(z_move(sample));
+
+    // while the rest of it is pending, the following samples are dropped
+    push_sample_with_payload(z_loan(closure), "test/socket/stream", "hello");
+    assert(zc_sample_counter_get(z_loan(dropped)) == 1);
+
+    // once the reader catches up, the rest of the payload is written before the next sample
+    size_t expected = 4 + len + 6;
+    uint8_t *received = (uint8_t *)malloc(expected);
+    size_t total = 0;
+    uint64_t pushed = 1;
+    while (total < expected) {
+        ssize_t n = read(fds[1], received + total, expected - total);
+        if (n > 0) {
+            total += (size_t)n;
+        } else if (zc_sample_counter_get(z_loan(dropped)) == pushed) {
+            push_sample_with_payload(z_loan(closure), "test/socket/stream", "hello");
+            pushed++;
+        }
+    }
+    assert(read(fds[1], received, 1) < 0);
+    // 16MiB is written as a 4 bytes varint
+    assert(received[0] == 0x80 && received[1] == 0x80 && received[2] == 0x80 && received[3] == 0x08);
+    assert(memcmp(received + 4, data, len) == 0);
+    assert(received[4 + len] == 5);
+    assert(memcmp(received + 4 + len + 1, "hello", 5) == 0);
+    assert(zc_sample_counter_get(z_loan(dropped)) == pushed - 1);
+    free(received);
+    free(data);
+
+    z_drop(z_move(closure));
+    z_drop(z_move(dropped));
+    close(fds[0]);
+    close(fds[1]);
+}
+
+#if defined(__linux__)
+bool fd_readable(int fd) {
+    struct pollfd pfd;
+    pfd.fd = fd;
+    pfd.events = POLLIN;
+    pfd.revents = 0;
+    return poll(&pfd, 1, 0) == 1 && (pfd.revents & POLLIN);
+}
+
+void fifo_fd() {
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 16);
+    int fd = z_fifo_handler_sample_fd(z_loan(handler));
+    assert(fd >= 0);
+    assert(!fd_readable(fd));
+
+    push_sample(z_loan(closure), "test/fd");
+    push_sample(z_loan(closure), "test/fd");
+    assert(fd_readable(fd));
+    z_owned_sample_t sample;
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_drop(z_move(sample));
+    assert(fd_readable(fd));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_OK);
+    z_drop(z_move(sample));
+    assert(!fd_readable(fd));
+
+    // the disconnection is signaled as well
+    z_drop(z_move(closure));
+    assert(fd_readable(fd));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(handler));
+}
+#endif
+#endif
+#endif
+
+void sample_qos() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/sample/qos");
+
+    z_owned_closure_sample_t closure;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&closure, &handler, 4);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(closure), NULL) == Z_OK);
+
+    // the qos set on publication is available on the received sample, so that it can be forwarded as is
+    z_put_options_t options;
+    z_put_options_default(&options);
+    options.priority = Z_PRIORITY_REAL_TIME;
+    options.congestion_control = Z_CONGESTION_CONTROL_BLOCK;
+    options.is_express = true;
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "data");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), &options) == Z_OK);
+
+    z_owned_sample_t sample;
+    assert(z_recv(z_loan(handler), &sample) == Z_OK);
+    assert(z_sample_priority(z_loan(sample)) == Z_PRIORITY_REAL_TIME);
+    assert(z_sample_congestion_control(z_loan(sample)) == Z_CONGESTION_CONTROL_BLOCK);
+    assert(z_sample_express(z_loan(sample)));
+    z_drop(z_move(sample));
+
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void timestamp_ntp64_to_unix_ns() {
+    // the high 32 bits of NTP64 time are seconds since UNIX epoch, the low 32 bits the fraction of a second
+    assert(z_timestamp_ntp64_to_unix_ns(0) == 0);
+    assert(z_timestamp_ntp64_to_unix_ns((uint64_t)1 << 32) == 1000000000ULL);
+    assert(z_timestamp_ntp64_to_unix_ns(((uint64_t)1700000000 << 32) | 0x80000000) == 1700000000500000000ULL);
+
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    // a timestamp issued by the session clock converts to the same time base as z_time_now()
+    z_time_t before = z_time_now();
+    z_timestamp_t ts;
+    assert(z_timestamp_new(&ts, z_loan(s)) == Z_OK);
+    z_time_t after = z_time_now();
+    uint64_t ts_ns = z_timestamp_ntp64_to_unix_ns(z_timestamp_ntp64_time(&ts));
+    const uint64_t tolerance_ns = 1000000000ULL;
+    assert(ts_ns + tolerance_ns >= before.t);
+    assert(ts_ns <= after.t + tolerance_ns);
+
+    z_drop(z_move(s));
+}
+
+int main(int argc, char **argv) {
+    sample_qos();
+    timestamp_ntp64_to_unix_ns();
+#if defined(Z_FEATURE_UNSTABLE_API)
+    sample_new_for_test();
+    shard();
+    on_change();
+    size_filter();
+    downsample_avg();
+    split_put_delete();
+    wait_empty();
+    recv_with_latency();
+    recv_timeout();
+    fifo_len();
+    to_rotating_file();
+    spsc_channel();
+    spsc_channel_concurrent_producers();
+    drain_until();
+    fifo_drain_drop();
+    republish();
+    forward_retry();
+    forward_retry_order();
+    forward_retry_overflow();
+#if defined(__unix__)
+    to_socket();
+    to_stream_socket_partial_write();
+#if defined(__linux__)
+    fifo_fd();
+#endif
+#endif
+#endif
+    return 0;
+}
@@ -0,0 +1,93 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+void open_peer(z_owned_session_t *s) {
+    z_owned_config_t config;
+    z_config_default(&config);
+    zc_config_insert_json5(z_loan_mut(config), "listen/endpoints", "[\"tcp/127.0.0.1:17460\"]");
+    assert(z_open(s, z_move(config), NULL) == Z_OK);
+}
+
+void scout_for_peers(z_moved_closure_hello_t *callback) {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_scout_options_t options;
+    z_scout_options_default(&options);
+    options.what = Z_WHAT_PEER;
+    options.timeout_ms = 1000;
+    assert(z_scout(z_move(config), callback, &options) == Z_OK);
+}
+
+void scout_fifo() {
+    z_owned_session_t s;
+    open_peer(&s);
+    z_id_t zid = z_info_zid(z_loan(s));
+
+    z_owned_closure_hello_t closure;
+    z_owned_fifo_handler_hello_t handler;
+    z_fifo_channel_hello_new(&closure, &handler, 16);
+    scout_for_peers(z_move(closure));
+
+    // the callback was dropped when scouting ended, so the handler is drained without blocking
+    int found = 0;
+    z_owned_hello_t hello;
+    while (z_recv(z_loan(handler), &hello) == Z_OK) {
+        z_id_t hello_zid = z_hello_zid(z_loan(hello));
+        if (memcmp(hello_zid.id, zid.id, sizeof(zid.id)) == 0) {
+            assert(z_hello_whatami(z_loan(hello)) == Z_WHATAMI_PEER);
+            found++;
+        }
+        z_drop(z_move(hello));
+    }
+    assert(found >= 1);
+    assert(z_try_recv(z_loan(handler), &hello) == Z_CHANNEL_DISCONNECTED);
+    assert(!z_internal_check(hello));
+
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void scout_ring() {
+    z_owned_session_t s;
+    open_peer(&s);
+    z_id_t zid = z_info_zid(z_loan(s));
+
+    // a single slot keeps the last hello received
+    z_owned_closure_hello_t closure;
+    z_owned_ring_handler_hello_t handler;
+    z_ring_channel_hello_new(&closure, &handler, 1);
+    scout_for_peers(z_move(closure));
+
+    z_owned_hello_t hello;
+    assert(z_try_recv(z_loan(handler), &hello) == Z_OK);
+    z_id_t hello_zid = z_hello_zid(z_loan(hello));
+    assert(memcmp(hello_zid.id, zid.id, sizeof(zid.id)) == 0);
+    z_drop(z_move(hello));
+    assert(z_recv(z_loan(handler), &hello) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+int main(int argc, char **argv) {
+    scout_fifo();
+    scout_ring();
+    return 0;
+}
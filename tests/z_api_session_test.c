@@ -83,9 +83,486 @@ void close_concurrent() {
 #endif
 }
 
+#if defined(Z_FEATURE_UNSTABLE_API)
+volatile unsigned int closed_calls = 0;
+volatile z_result_t closed_result = -1;
+
+void on_closed(z_result_t result, void *arg) {
+    closed_result = result;
+    closed_calls++;
+}
+#endif
+
+void close_async() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config;
+    z_config_default(&config);
+
+    z_owned_session_t s;
+    if (z_open(&s, z_move(config), NULL) < 0) {
+        perror("Unable to open session!");
+        exit(-1);
+    }
+
+    assert(z_close_async(z_loan(s), on_closed, NULL) == Z_OK);
+    for (int i = 0; i < 100 && closed_calls == 0; i++) {
+        z_sleep_ms(50);
+    }
+    assert(closed_calls == 1);
+    assert(closed_result == Z_OK);
+    assert(z_session_is_closed(z_loan(s)));
+    // the session can not be closed twice
+    assert(z_close_async(z_loan(s), on_closed, NULL) != Z_OK);
+    z_sleep_ms(100);
+    assert(closed_calls == 1);
+
+    z_drop(z_move(s));
+#endif
+}
+
+void zid_string_roundtrip() {
+    z_owned_config_t config;
+    z_config_default(&config);
+
+    z_owned_session_t s;
+    if (z_open(&s, z_move(config), NULL) < 0) {
+        perror("Unable to open session!");
+        exit(-1);
+    }
+
+    z_id_t zid = z_info_zid(z_loan(s));
+    z_owned_string_t str;
+    z_id_to_string(&zid, &str);
+    char buf[64];
+    size_t len = z_string_len(z_loan(str));
+    assert(len < sizeof(buf));
+    memcpy(buf, z_string_data(z_loan(str)), len);
+    buf[len] = '\0';
+    z_drop(z_move(str));
+
+    z_id_t parsed;
+    assert(z_id_from_str(&parsed, buf) == Z_OK);
+    assert(memcmp(zid.id, parsed.id, sizeof(zid.id)) == 0);
+    assert(z_id_from_str(&parsed, "not an id") == Z_EINVAL);
+
+    z_drop(z_move(s));
+}
+
+void effective_config() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config;
+    z_config_default(&config);
+    zc_config_insert_json5(z_loan_mut(config), "mode", "\"peer\"");
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_owned_string_t config_string;
+    assert(zc_session_config_to_string(z_loan(s), &config_string) == Z_OK);
+    assert(z_string_len(z_loan(config_string)) > 0);
+    z_drop(z_move(config_string));
+
+    z_drop(z_move(s));
+#endif
+}
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+void on_sample(z_loaned_sample_t *sample, void *arg) {}
+#endif
+
+void subscriber_id() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/subscriber_id");
+    z_owned_closure_sample_t callback1, callback2;
+    z_closure(&callback1, on_sample, NULL, NULL);
+    z_closure(&callback2, on_sample, NULL, NULL);
+    z_owned_subscriber_t sub1, sub2;
+    assert(z_declare_subscriber(z_loan(s), &sub1, z_loan(ke), z_move(callback1), NULL) == Z_OK);
+    assert(z_declare_subscriber(z_loan(s), &sub2, z_loan(ke), z_move(callback2), NULL) == Z_OK);
+
+    z_entity_global_id_t id1 = z_subscriber_id(z_loan(sub1));
+    z_entity_global_id_t id2 = z_subscriber_id(z_loan(sub2));
+    z_id_t zid = z_info_zid(z_loan(s));
+    z_id_t zid1 = z_entity_global_id_zid(&id1);
+    assert(memcmp(zid.id, zid1.id, sizeof(zid.id)) == 0);
+    assert(z_entity_global_id_eid(&id1) != z_entity_global_id_eid(&id2));
+    z_entity_global_id_t id1_again = z_subscriber_id(z_loan(sub1));
+    assert(z_entity_global_id_eid(&id1) == z_entity_global_id_eid(&id1_again));
+
+    z_drop(z_move(sub1));
+    z_drop(z_move(sub2));
+    z_drop(z_move(s));
+#endif
+}
+
+void subscriber_replace_handler() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/subscriber_replace_handler");
+    z_owned_closure_sample_t callback1, callback2;
+    z_owned_fifo_handler_sample_t handler1, handler2;
+    z_fifo_channel_sample_new(&callback1, &handler1, 4);
+    z_fifo_channel_sample_new(&callback2, &handler2, 4);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback1), NULL) == Z_OK);
+
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "1");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    z_owned_sample_t sample;
+    assert(z_recv(z_loan(handler1), &sample) == Z_OK);
+    z_drop(z_move(sample));
+
+    assert(z_subscriber_replace_handler(z_loan(sub), z_move(callback2)) == Z_OK);
+    // the previous channel is disconnected once its send end is dropped
+    assert(z_recv(z_loan(handler1), &sample) == Z_CHANNEL_DISCONNECTED);
+
+    z_bytes_copy_from_str(&payload, "2");
+    assert(z_put(z_loan(s), z_loan(ke), z_move(payload), NULL) == Z_OK);
+    assert(z_recv(z_loan(handler2), &sample) == Z_OK);
+    z_drop(z_move(sample));
+
+    z_drop(z_move(sub));
+    z_drop(z_move(handler1));
+    z_drop(z_move(handler2));
+    z_drop(z_move(s));
+#endif
+}
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+typedef struct links_context_t {
+    int up;
+    int down;
+    int connecting;
+} links_context_t;
+
+void on_link(const char *locator, z_link_state_t state, void *arg) {
+    links_context_t *ctx = (links_context_t *)arg;
+    if (state == Z_LINK_STATE_UP && strcmp(locator, "tcp/127.0.0.1:17449") == 0) {
+        ctx->up++;
+    } else if (state == Z_LINK_STATE_DOWN && strcmp(locator, "tcp/127.0.0.1:17450") == 0) {
+        ctx->down++;
+    } else if (state == Z_LINK_STATE_CONNECTING && strcmp(locator, "tcp/127.0.0.1:17450") == 0) {
+        ctx->connecting++;
+    }
+}
+#endif
+
+void session_links() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config1;
+    z_config_default(&config1);
+    zc_config_insert_json5(z_loan_mut(config1), "listen/endpoints", "[\"tcp/127.0.0.1:17449\"]");
+    zc_config_insert_json5(z_loan_mut(config1), "scouting/multicast/enabled", "false");
+    z_owned_session_t s1;
+    assert(z_open(&s1, z_move(config1), NULL) == Z_OK);
+
+    // the second endpoint has no listener, so it stays in connecting state
+    z_owned_config_t config2;
+    z_config_default(&config2);
+    zc_config_insert_json5(z_loan_mut(config2), "connect/endpoints",
+                           "[\"tcp/127.0.0.1:17449\", \"tcp/127.0.0.1:17450\"]");
+    zc_config_insert_json5(z_loan_mut(config2), "scouting/multicast/enabled", "false");
+    zc_config_set_adminspace_enabled(z_loan_mut(config2), true);
+    z_owned_session_t s2;
+    assert(z_open(&s2, z_move(config2), NULL) == Z_OK);
+    assert(z_session_wait_connected(z_loan(s2), 5000) == Z_OK);
+
+    links_context_t ctx = {0, 0, 0};
+    assert(z_session_links(z_loan(s2), on_link, &ctx) == Z_OK);
+    assert(ctx.up == 1);
+    assert(ctx.down == 0);
+    assert(ctx.connecting == 1);
+
+    z_close(z_loan_mut(s2), NULL);
+    ctx.up = 0;
+    ctx.connecting = 0;
+    assert(z_session_links(z_loan(s2), on_link, &ctx) == Z_OK);
+    assert(ctx.up == 0);
+    assert(ctx.down == 1);
+
+    z_drop(z_move(s2));
+    z_drop(z_move(s1));
+#endif
+}
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+typedef struct locators_context_t {
+    z_id_t expected;
+    int calls;
+    int matches;
+    int drops;
+} locators_context_t;
+
+void on_locators(const z_id_t *zid, const char *locators, void *arg) {
+    locators_context_t *ctx = (locators_context_t *)arg;
+    ctx->calls++;
+    if (memcmp(zid->id, ctx->expected.id, sizeof(zid->id)) == 0 && strstr(locators, "tcp/127.0.0.1:17453") != NULL) {
+        ctx->matches++;
+    }
+}
+
+void on_locators_drop(void *arg) { ((locators_context_t *)arg)->drops++; }
+#endif
+
+void info_locators() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config1;
+    z_config_default(&config1);
+    zc_config_insert_json5(z_loan_mut(config1), "listen/endpoints", "[\"tcp/127.0.0.1:17453\"]");
+    zc_config_insert_json5(z_loan_mut(config1), "scouting/multicast/enabled", "false");
+    z_owned_session_t s1;
+    assert(z_open(&s1, z_move(config1), NULL) == Z_OK);
+
+    z_owned_config_t config2;
+    z_config_default(&config2);
+    zc_config_insert_json5(z_loan_mut(config2), "connect/endpoints", "[\"tcp/127.0.0.1:17453\"]");
+    zc_config_insert_json5(z_loan_mut(config2), "scouting/multicast/enabled", "false");
+    zc_config_set_adminspace_enabled(z_loan_mut(config2), true);
+    z_owned_session_t s2;
+    assert(z_open(&s2, z_move(config2), NULL) == Z_OK);
+    assert(z_session_wait_connected(z_loan(s2), 5000) == Z_OK);
+
+    locators_context_t ctx;
+    ctx.expected = z_info_zid(z_loan(s1));
+    ctx.calls = 0;
+    ctx.matches = 0;
+    ctx.drops = 0;
+    zc_owned_closure_locators_t callback;
+    zc_closure_locators(&callback, on_locators, on_locators_drop, &ctx);
+    assert(zc_info_peers_locators(z_loan(s2), z_move(callback)) == Z_OK);
+    assert(ctx.calls == 1);
+    assert(ctx.matches == 1);
+    assert(ctx.drops == 1);
+
+    // the peer is not a router
+    zc_closure_locators(&callback, on_locators, on_locators_drop, &ctx);
+    assert(zc_info_routers_locators(z_loan(s2), z_move(callback)) == Z_OK);
+    assert(ctx.calls == 1);
+    assert(ctx.drops == 2);
+
+    z_drop(z_move(s2));
+    z_drop(z_move(s1));
+#endif
+}
+
+void open_listener(z_owned_session_t *s, const char *endpoints) {
+    z_owned_config_t config;
+    z_config_default(&config);
+    zc_config_insert_json5(z_loan_mut(config), "listen/endpoints", endpoints);
+    zc_config_insert_json5(z_loan_mut(config), "scouting/multicast/enabled", "false");
+    assert(z_open(s, z_move(config), NULL) == Z_OK);
+}
+
+void reconnect_marker() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_session_t s1;
+    open_listener(&s1, "[\"tcp/127.0.0.1:17451\"]");
+
+    z_owned_config_t config2;
+    z_config_default(&config2);
+    zc_config_insert_json5(z_loan_mut(config2), "connect/endpoints", "[\"tcp/127.0.0.1:17451\"]");
+    zc_config_insert_json5(z_loan_mut(config2), "listen/endpoints", "[\"tcp/127.0.0.1:17452\"]");
+    zc_config_insert_json5(z_loan_mut(config2), "scouting/multicast/enabled", "false");
+    zc_config_set_adminspace_enabled(z_loan_mut(config2), true);
+    z_owned_session_t s2;
+    assert(z_open(&s2, z_move(config2), NULL) == Z_OK);
+    assert(z_session_wait_connected(z_loan(s2), 5000) == Z_OK);
+
+    z_view_keyexpr_t marker;
+    z_view_keyexpr_from_str(&marker, "test/reconnect_marker");
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_owned_subscriber_t listener;
+    assert(zc_fifo_channel_sample_new_with_reconnect_marker(&callback, &handler, 16, z_loan(s2), z_loan(marker),
+                                                            &listener) == Z_OK);
+
+    // a new connection while another one is up is not a reconnection
+    z_owned_config_t config3;
+    z_config_default(&config3);
+    zc_config_insert_json5(z_loan_mut(config3), "connect/endpoints", "[\"tcp/127.0.0.1:17452\"]");
+    zc_config_insert_json5(z_loan_mut(config3), "scouting/multicast/enabled", "false");
+    z_owned_session_t s3;
+    assert(z_open(&s3, z_move(config3), NULL) == Z_OK);
+    z_owned_sample_t sample;
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 500) == Z_ETIMEOUT);
+    z_drop(z_move(s3));
+
+    // losing the only connection and getting it back is
+    z_drop(z_move(s1));
+    z_sleep_ms(500);
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 0) == Z_ETIMEOUT);
+    open_listener(&s1, "[\"tcp/127.0.0.1:17451\"]");
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 15000) == Z_OK);
+    z_view_string_t ke_str;
+    z_keyexpr_as_view_string(z_sample_keyexpr(z_loan(sample)), &ke_str);
+    assert(strncmp(z_string_data(z_loan(ke_str)), "test/reconnect_marker", z_string_len(z_loan(ke_str))) == 0);
+    assert(z_bytes_len(z_sample_payload(z_loan(sample))) == 0);
+    z_drop(z_move(sample));
+    assert(z_fifo_handler_sample_recv_timeout(z_loan(handler), &sample, 500) == Z_ETIMEOUT);
+
+    z_drop(z_move(listener));
+    z_drop(z_move(callback));
+    z_drop(z_move(handler));
+    z_drop(z_move(s2));
+    z_drop(z_move(s1));
+#endif
+}
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+void *delayed_abort(void *arg) {
+    z_sleep_ms(100);
+    z_session_abort_pending((const z_loaned_session_t *)arg);
+    return NULL;
+}
+#endif
+
+void abort_pending() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    // queries are kept in the handler without being replied to, so the get never completes by itself
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/abort_pending");
+    z_owned_closure_query_t query_callback;
+    z_owned_fifo_handler_query_t queries;
+    z_fifo_channel_query_new(&query_callback, &queries, 4);
+    z_owned_queryable_t qable;
+    assert(z_declare_queryable(z_loan(s), &qable, z_loan(ke), z_move(query_callback), NULL) == Z_OK);
+
+    z_owned_closure_reply_t reply_callback;
+    z_owned_fifo_handler_reply_t replies;
+    z_fifo_channel_reply_new(&reply_callback, &replies, 4);
+    z_get_options_t options;
+    z_get_options_default(&options);
+    options.timeout_ms = 60000;
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &options) == Z_OK);
+    z_owned_query_t query;
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+
+    // a blocking recv returns once the pending get is aborted
+    z_owned_task_t task;
+    assert(z_task_init(&task, NULL, delayed_abort, (void *)z_loan(s)) == Z_OK);
+    z_clock_t start = z_clock_now();
+    z_owned_reply_t reply;
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    assert(z_clock_elapsed_ms(&start) < 30000);
+    z_task_join(z_move(task));
+
+    // replying to an aborted query is harmless
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "late");
+    z_query_reply(z_loan(query), z_loan(ke), z_move(payload), NULL);
+    z_drop(z_move(query));
+
+    // the gets of a querier are aborted as well
+    z_owned_querier_t querier;
+    z_querier_options_t querier_options;
+    z_querier_options_default(&querier_options);
+    querier_options.timeout_ms = 60000;
+    assert(z_declare_querier(z_loan(s), &querier, z_loan(ke), &querier_options) == Z_OK);
+    z_drop(z_move(replies));
+    z_fifo_channel_reply_new(&reply_callback, &replies, 4);
+    assert(z_querier_get(z_loan(querier), "", z_move(reply_callback), NULL) == Z_OK);
+    assert(z_recv(z_loan(queries), &query) == Z_OK);
+    assert(z_task_init(&task, NULL, delayed_abort, (void *)z_loan(s)) == Z_OK);
+    start = z_clock_now();
+    assert(z_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    assert(z_clock_elapsed_ms(&start) < 30000);
+    z_task_join(z_move(task));
+
+    // closing the session aborts the gets still pending
+    z_drop(z_move(replies));
+    z_fifo_channel_reply_new(&reply_callback, &replies, 4);
+    assert(z_get(z_loan(s), z_loan(ke), "", z_move(reply_callback), &options) == Z_OK);
+    z_owned_query_t last_query;
+    assert(z_recv(z_loan(queries), &last_query) == Z_OK);
+    assert(z_close(z_loan_mut(s), NULL) == Z_OK);
+    assert(z_try_recv(z_loan(replies), &reply) == Z_CHANNEL_DISCONNECTED);
+    z_drop(z_move(last_query));
+    z_drop(z_move(query));
+
+    z_drop(z_move(querier));
+    z_drop(z_move(replies));
+    z_drop(z_move(qable));
+    z_drop(z_move(queries));
+    z_drop(z_move(s));
+#endif
+}
+
+void info_metadata() {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    z_owned_config_t config1;
+    z_config_default(&config1);
+    zc_config_insert_json5(z_loan_mut(config1), "listen/endpoints", "[\"tcp/127.0.0.1:17454\"]");
+    zc_config_insert_json5(z_loan_mut(config1), "scouting/multicast/enabled", "false");
+    zc_config_set_adminspace_enabled(z_loan_mut(config1), true);
+    assert(zc_config_set_metadata(z_loan_mut(config1), "name", "node1") == Z_OK);
+    assert(zc_config_set_metadata(z_loan_mut(config1), "location", "lab") == Z_OK);
+    z_owned_session_t s1;
+    assert(z_open(&s1, z_move(config1), NULL) == Z_OK);
+
+    z_owned_config_t config2;
+    z_config_default(&config2);
+    zc_config_insert_json5(z_loan_mut(config2), "connect/endpoints", "[\"tcp/127.0.0.1:17454\"]");
+    zc_config_insert_json5(z_loan_mut(config2), "scouting/multicast/enabled", "false");
+    zc_config_set_adminspace_enabled(z_loan_mut(config2), true);
+    z_owned_session_t s2;
+    assert(z_open(&s2, z_move(config2), NULL) == Z_OK);
+    assert(z_session_wait_connected(z_loan(s2), 5000) == Z_OK);
+
+    // the metadata of a remote node and of the session itself are both reachable through the admin space
+    z_id_t zid1 = z_info_zid(z_loan(s1));
+    const z_loaned_session_t *sessions[] = {z_loan(s2), z_loan(s1)};
+    for (int i = 0; i < 2; i++) {
+        z_owned_string_t metadata;
+        assert(zc_info_metadata(sessions[i], &zid1, &metadata) == Z_OK);
+        char buf[256];
+        snprintf(buf, sizeof(buf), "%.*s", (int)z_string_len(z_loan(metadata)), z_string_data(z_loan(metadata)));
+        assert(buf[0] == '{');
+        assert(strstr(buf, "\"name\":\"node1\"") != NULL);
+        assert(strstr(buf, "\"location\":\"lab\"") != NULL);
+        z_drop(z_move(metadata));
+    }
+
+    // an unknown node can not be reached
+    z_id_t unknown;
+    memset(unknown.id, 0x42, sizeof(unknown.id));
+    z_owned_string_t metadata;
+    assert(zc_info_metadata(z_loan(s2), &unknown, &metadata) == Z_EUNAVAILABLE);
+    assert(!z_internal_check(metadata));
+
+    z_drop(z_move(s2));
+    z_drop(z_move(s1));
+#endif
+}
+
 int main(int argc, char **argv) {
     zc_try_init_log_from_env();
     close_drop();
     close_sync();
     close_concurrent();
+    close_async();
+    zid_string_roundtrip();
+    effective_config();
+    subscriber_id();
+    subscriber_replace_handler();
+    session_links();
+    info_locators();
+    info_metadata();
+    reconnect_marker();
+    abort_pending();
 }
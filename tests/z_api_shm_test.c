@@ -11,6 +11,7 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
+#include <stdlib.h>
 #include <string.h>
 
 #include "zenoh.h"
@@ -107,6 +108,23 @@ int test_layouted_allocation(const z_loaned_alloc_layout_t* alloc_layout) {
         return Z_ENULL;
 }
 
+int test_layouted_allocation_or_heap(const z_loaned_alloc_layout_t* alloc_layout, size_t size, bool expect_shm) {
+    uint8_t* data = (uint8_t*)malloc(size);
+    memset(data, 42, size);
+
+    z_owned_bytes_t payload;
+    bool shm = !expect_shm;
+    z_result_t res = z_alloc_layout_alloc_or_heap(&payload, alloc_layout, data, size, &shm);
+    free(data);
+    ASSERT_OK(res);
+    ASSERT_CHECK(payload);
+    ASSERT_TRUE(shm == expect_shm);
+    // the data is copied on both paths
+    ASSERT_TRUE(z_bytes_len(z_loan(payload)) == size);
+    z_drop(z_move(payload));
+    return Z_OK;
+}
+
 int test_allocation(const z_loaned_shm_provider_t* provider, size_t size, z_alloc_alignment_t alignment) {
     z_buf_layout_alloc_result_t alloc;
 
@@ -145,6 +163,7 @@ int test_provider(z_owned_shm_provider_t* provider, z_alloc_alignment_t alignmen
         for (int i = 0; i < 100; ++i) {
             ASSERT_OK(test_layouted_allocation(z_loan(alloc_layout)));
         }
+        ASSERT_OK(test_layouted_allocation_or_heap(z_loan(alloc_layout), buf_ok_size, true));
         z_drop(z_move(alloc_layout));
         ASSERT_CHECK_ERR(alloc_layout);
     }
@@ -157,6 +176,8 @@ int test_provider(z_owned_shm_provider_t* provider, z_alloc_alignment_t alignmen
         ASSERT_CHECK(alloc_layout);
         // test layouted allocation ERROR
         ASSERT_ERR(test_layouted_allocation(z_loan(alloc_layout)));
+        // falls back to heap
+        ASSERT_OK(test_layouted_allocation_or_heap(z_loan(alloc_layout), buf_err_size, false));
         z_drop(z_move(alloc_layout));
         ASSERT_CHECK_ERR(alloc_layout);
     }
@@ -449,6 +470,114 @@ int run_c_client() {
     return Z_OK;
 }
 
+int push_batch_sample(const z_loaned_closure_sample_t* closure, const char* value) {
+    z_view_keyexpr_t ke;
+    ASSERT_OK(z_view_keyexpr_from_str(&ke, "test/shm_batch/in"));
+    z_owned_bytes_t payload;
+    ASSERT_OK(z_bytes_copy_from_str(&payload, value));
+    z_owned_sample_t sample;
+    z_sample_new_for_test(&sample, z_loan(ke), z_move(payload), NULL, Z_SAMPLE_KIND_PUT);
+    z_call(closure, z_loan_mut(sample));
+    z_drop(z_move(sample));
+    return Z_OK;
+}
+
+int check_batch(const z_loaned_fifo_handler_sample_t* handler, const char** values, size_t n) {
+    z_owned_sample_t sample;
+    ASSERT_OK(z_recv(handler, &sample));
+    const z_loaned_bytes_t* payload = z_sample_payload(z_loan(sample));
+    // each payload is written into its own SHM buffer, and the batch is made of these buffers
+    z_bytes_slice_iterator_t it = z_bytes_get_slice_iterator(payload);
+    z_view_slice_t frame;
+    size_t frames = 0;
+    while (z_bytes_slice_iterator_next(&it, &frame)) {
+        frames++;
+    }
+    ASSERT_TRUE(frames == n);
+    if (n == 1) {
+        const z_loaned_shm_t* shm = NULL;
+        ASSERT_OK(z_bytes_as_loaned_shm(payload, &shm));
+    }
+    ze_deserializer_t deserializer = ze_deserializer_from_bytes(payload);
+    for (size_t i = 0; i < n; i++) {
+        z_owned_slice_t slice;
+        ASSERT_OK(ze_deserializer_deserialize_slice(&deserializer, &slice));
+        ASSERT_TRUE(z_slice_len(z_loan(slice)) == strlen(values[i]));
+        ASSERT_TRUE(memcmp(z_slice_data(z_loan(slice)), values[i], strlen(values[i])) == 0);
+        z_drop(z_move(slice));
+    }
+    ASSERT_TRUE(ze_deserializer_is_done(&deserializer));
+    z_drop(z_move(sample));
+    return Z_OK;
+}
+
+int run_shm_batch() {
+    z_alloc_alignment_t alignment = {0};
+    z_owned_memory_layout_t layout;
+    ASSERT_OK(z_memory_layout_new(&layout, 65536, alignment));
+    z_owned_shm_provider_t provider;
+    ASSERT_OK(z_posix_shm_provider_new(&provider, z_loan(layout)));
+
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    ASSERT_OK(z_open(&s, z_move(config), NULL));
+    z_view_keyexpr_t ke;
+    ASSERT_OK(z_view_keyexpr_from_str(&ke, "test/shm_batch/out"));
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_t sub;
+    ASSERT_OK(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback), NULL));
+    z_owned_publisher_t pub;
+    ASSERT_OK(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL));
+
+    // the closure takes ownership of the provider and of the publisher
+    z_owned_closure_sample_t batcher;
+    ASSERT_OK(z_closure_sample_shm_batch(&batcher, z_move(provider), z_move(pub), 8, 0));
+    ASSERT_CHECK_ERR(provider);
+    ASSERT_CHECK_ERR(pub);
+
+    // each payload takes 4 bytes once serialized, so a batch is published every 2 samples
+    ASSERT_OK(push_batch_sample(z_loan(batcher), "abc"));
+    ASSERT_OK(push_batch_sample(z_loan(batcher), "def"));
+    ASSERT_OK(push_batch_sample(z_loan(batcher), "ghi"));
+    const char* first[] = {"abc", "def"};
+    ASSERT_OK(check_batch(z_loan(handler), first, 2));
+    z_owned_sample_t sample;
+    ASSERT_TRUE(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+
+    // dropping the closure flushes the pending payloads
+    z_drop(z_move(batcher));
+    const char* last[] = {"ghi"};
+    ASSERT_OK(check_batch(z_loan(handler), last, 1));
+
+    // with a flush interval, a pending batch is published without reaching the batch size
+    ASSERT_OK(z_posix_shm_provider_new(&provider, z_loan(layout)));
+    ASSERT_OK(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL));
+    ASSERT_OK(z_closure_sample_shm_batch(&batcher, z_move(provider), z_move(pub), 1024, 100));
+    ASSERT_OK(push_batch_sample(z_loan(batcher), "jkl"));
+    z_clock_t start = z_clock_now();
+    const char* flushed[] = {"jkl"};
+    ASSERT_OK(check_batch(z_loan(handler), flushed, 1));
+    ASSERT_TRUE(z_clock_elapsed_ms(&start) < 5000);
+    z_drop(z_move(batcher));
+    ASSERT_TRUE(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+
+    // a gravestone provider is rejected, and the publisher is consumed anyway
+    ASSERT_OK(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL));
+    z_internal_null(&provider);
+    ASSERT_ERR(z_closure_sample_shm_batch(&batcher, z_move(provider), z_move(pub), 8, 0));
+    ASSERT_CHECK_ERR(batcher);
+    ASSERT_CHECK_ERR(pub);
+
+    z_drop(z_move(layout));
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+    return Z_OK;
+}
+
 int run_cleanup() {
     zc_cleanup_orphaned_shm_segments();
     return Z_OK;
@@ -461,6 +590,7 @@ int main() {
     ASSERT_OK(run_global_client_storage());
     ASSERT_OK(run_client_storage());
     ASSERT_OK(run_c_client());
+    ASSERT_OK(run_shm_batch());
     ASSERT_OK(run_cleanup());
     return Z_OK;
 }
@@ -0,0 +1,95 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+size_t count_samples(const z_loaned_fifo_handler_sample_t *handler) {
+    size_t count = 0;
+    z_owned_sample_t sample;
+    while (z_try_recv(handler, &sample) == Z_OK) {
+        count++;
+        z_drop(z_move(sample));
+    }
+    return count;
+}
+
+void put_str(const z_loaned_session_t *s, const char *key) {
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, key);
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, "data");
+    assert(z_put(s, z_loan(ke), z_move(payload), NULL) == Z_OK);
+}
+
+void subscriber_multi() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+
+    z_view_keyexpr_t ke1, ke2, ke3;
+    z_view_keyexpr_from_str(&ke1, "test/multi/a");
+    z_view_keyexpr_from_str(&ke2, "test/other/b");
+    z_view_keyexpr_from_str(&ke3, "test/multi/c/**");
+    const z_loaned_keyexpr_t *key_exprs[3];
+    key_exprs[0] = z_loan(ke1);
+    key_exprs[1] = z_loan(ke2);
+    key_exprs[2] = z_loan(ke3);
+
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_group_t group;
+    assert(z_declare_subscriber_multi(z_loan(s), &group, key_exprs, 3, z_move(callback), NULL) == Z_OK);
+    assert(z_internal_check(group));
+    assert(z_subscriber_group_len(z_loan(group)) == 3);
+
+    put_str(z_loan(s), "test/multi/a");
+    put_str(z_loan(s), "test/other/b");
+    put_str(z_loan(s), "test/multi/c/d");
+    put_str(z_loan(s), "test/multi/e");
+    z_sleep_ms(100);
+    assert(count_samples(z_loan(handler)) == 3);
+
+    // undeclaring the group undeclares all the subscribers and drops the shared callback
+    assert(z_undeclare_subscriber_group(z_move(group)) == Z_OK);
+    assert(!z_internal_check(group));
+    put_str(z_loan(s), "test/multi/a");
+    z_sleep_ms(100);
+    z_owned_sample_t sample;
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_DISCONNECTED);
+
+    z_drop(z_move(handler));
+
+    // an empty set of key expressions is rejected
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    assert(z_declare_subscriber_multi(z_loan(s), &group, key_exprs, 0, z_move(callback), NULL) == Z_EINVAL);
+    assert(!z_internal_check(group));
+
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+#endif
+
+int main(int argc, char **argv) {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    subscriber_multi();
+#endif
+    return 0;
+}
@@ -0,0 +1,320 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+void mutex_lock_timeout() {
+    z_owned_mutex_t m;
+    assert(z_mutex_init(&m) == Z_OK);
+    assert(z_mutex_lock(z_loan_mut(m)) == Z_OK);
+    assert(z_mutex_lock_timeout(z_loan_mut(m), 20) == Z_ETIMEOUT);
+    assert(z_mutex_unlock(z_loan_mut(m)) == Z_OK);
+
+    assert(z_mutex_lock_timeout(z_loan_mut(m), 20) == Z_OK);
+    assert(z_mutex_try_lock(z_loan_mut(m)) != Z_OK);
+    assert(z_mutex_unlock(z_loan_mut(m)) == Z_OK);
+    z_drop(z_move(m));
+}
+
+typedef struct rmutex_args_t {
+    const z_loaned_rmutex_t *m;
+    volatile int try_lock_result;
+} rmutex_args_t;
+
+void *try_lock_rmutex(void *arg) {
+    rmutex_args_t *args = (rmutex_args_t *)arg;
+    args->try_lock_result = z_rmutex_try_lock(args->m);
+    if (args->try_lock_result == Z_OK) {
+        z_rmutex_unlock(args->m);
+    }
+    return NULL;
+}
+
+void rmutex_reentrant() {
+    z_owned_rmutex_t m;
+    assert(z_rmutex_init(&m) == Z_OK);
+    rmutex_args_t args;
+    args.m = z_loan(m);
+
+    // the owning thread can lock again, and must unlock as many times
+    assert(z_rmutex_lock(z_loan(m)) == Z_OK);
+    assert(z_rmutex_lock(z_loan(m)) == Z_OK);
+    assert(z_rmutex_try_lock(z_loan(m)) == Z_OK);
+    assert(z_rmutex_unlock(z_loan(m)) == Z_OK);
+    assert(z_rmutex_unlock(z_loan(m)) == Z_OK);
+
+    z_owned_task_t task;
+    z_task_init(&task, NULL, try_lock_rmutex, &args);
+    z_task_join(z_move(task));
+    assert(args.try_lock_result == Z_EBUSY_MUTEX);
+
+    assert(z_rmutex_unlock(z_loan(m)) == Z_OK);
+    assert(z_rmutex_unlock(z_loan(m)) == Z_EINVAL_MUTEX);
+
+    z_task_init(&task, NULL, try_lock_rmutex, &args);
+    z_task_join(z_move(task));
+    assert(args.try_lock_result == Z_OK);
+    z_drop(z_move(m));
+}
+
+void rwlock_read_write() {
+    z_owned_rwlock_t l;
+    assert(z_rwlock_init(&l) == Z_OK);
+    assert(z_internal_check(l));
+
+    // several readers can hold the lock, but exclude a writer
+    assert(z_rwlock_read_lock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_try_read_lock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_try_write_lock(z_loan_mut(l)) == Z_EBUSY_MUTEX);
+    assert(z_rwlock_read_unlock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_try_write_lock(z_loan_mut(l)) == Z_EBUSY_MUTEX);
+    assert(z_rwlock_read_unlock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_read_unlock(z_loan_mut(l)) != Z_OK);
+
+    // a writer excludes both readers and other writers
+    assert(z_rwlock_write_lock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_try_read_lock(z_loan_mut(l)) == Z_EBUSY_MUTEX);
+    assert(z_rwlock_try_write_lock(z_loan_mut(l)) == Z_EBUSY_MUTEX);
+    assert(z_rwlock_write_unlock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_write_unlock(z_loan_mut(l)) != Z_OK);
+
+    assert(z_rwlock_try_write_lock(z_loan_mut(l)) == Z_OK);
+    assert(z_rwlock_write_unlock(z_loan_mut(l)) == Z_OK);
+    z_drop(z_move(l));
+    assert(!z_internal_check(l));
+}
+
+uint64_t now_ms() {
+    z_time_t epoch;
+    memset(&epoch, 0, sizeof(epoch));
+    return z_time_elapsed_ms(&epoch);
+}
+
+void semaphore_try_acquire() {
+    z_owned_semaphore_t sem;
+    assert(z_semaphore_init(&sem, 2) == Z_OK);
+    assert(z_semaphore_try_acquire(z_loan(sem)) == Z_OK);
+    assert(z_semaphore_acquire_timeout(z_loan(sem), 20) == Z_OK);
+    assert(z_semaphore_try_acquire(z_loan(sem)) == Z_EBUSY_MUTEX);
+
+    uint64_t start = now_ms();
+    assert(z_semaphore_acquire_timeout(z_loan(sem), 50) == Z_ETIMEOUT);
+    assert(now_ms() >= start + 50);
+
+    assert(z_semaphore_release(z_loan(sem)) == Z_OK);
+    assert(z_semaphore_acquire_timeout(z_loan(sem), 50) == Z_OK);
+    assert(z_semaphore_try_acquire(z_loan(sem)) == Z_EBUSY_MUTEX);
+    z_drop(z_move(sem));
+}
+
+typedef struct notify_args_t {
+    z_loaned_mutex_t *m;
+    const z_loaned_condvar_t *cv;
+    volatile bool ready;
+} notify_args_t;
+
+void *notify_ready(void *arg) {
+    notify_args_t *args = (notify_args_t *)arg;
+    z_sleep_ms(20);
+    z_mutex_lock(args->m);
+    args->ready = true;
+    z_condvar_signal(args->cv);
+    z_mutex_unlock(args->m);
+    return NULL;
+}
+
+void condvar_wait_until() {
+    z_owned_mutex_t m;
+    z_owned_condvar_t cv;
+    z_mutex_init(&m);
+    z_condvar_init(&cv);
+
+    // without notification the wait times out, with the mutex locked again
+    assert(z_mutex_lock(z_loan_mut(m)) == Z_OK);
+    uint64_t start = now_ms();
+    assert(z_condvar_wait_until(z_loan(cv), z_loan_mut(m), start + 50) == Z_ETIMEOUT);
+    assert(now_ms() >= start + 50);
+    assert(z_mutex_unlock(z_loan_mut(m)) == Z_OK);
+
+    notify_args_t args;
+    args.m = z_loan_mut(m);
+    args.cv = z_loan(cv);
+    args.ready = false;
+    assert(z_mutex_lock(z_loan_mut(m)) == Z_OK);
+    z_owned_task_t task;
+    z_task_init(&task, NULL, notify_ready, &args);
+    uint64_t deadline = now_ms() + 5000;
+    while (!args.ready) {
+        assert(z_condvar_wait_until(z_loan(cv), z_loan_mut(m), deadline) != Z_ETIMEOUT);
+    }
+    assert(z_mutex_unlock(z_loan_mut(m)) == Z_OK);
+    z_task_join(z_move(task));
+
+    z_drop(z_move(cv));
+    z_drop(z_move(m));
+}
+
+typedef struct semaphore_notify_args_t {
+    const z_loaned_semaphore_t *sem;
+    const z_loaned_condvar_t *cv;
+    volatile bool ready;
+} semaphore_notify_args_t;
+
+void *notify_ready_semaphore(void *arg) {
+    semaphore_notify_args_t *args = (semaphore_notify_args_t *)arg;
+    z_sleep_ms(20);
+    // the only permit is available only while the main thread waits on the conditional variable
+    z_semaphore_acquire(args->sem);
+    args->ready = true;
+    z_condvar_signal(args->cv);
+    z_semaphore_release(args->sem);
+    return NULL;
+}
+
+void condvar_wait_semaphore() {
+    z_owned_semaphore_t sem;
+    z_owned_condvar_t cv;
+    assert(z_semaphore_init(&sem, 1) == Z_OK);
+    z_condvar_init(&cv);
+
+    semaphore_notify_args_t args;
+    args.sem = z_loan(sem);
+    args.cv = z_loan(cv);
+    args.ready = false;
+    assert(z_semaphore_acquire(z_loan(sem)) == Z_OK);
+    z_owned_task_t task;
+    z_task_init(&task, NULL, notify_ready_semaphore, &args);
+    while (!args.ready) {
+        assert(z_condvar_wait_semaphore(z_loan(cv), z_loan(sem)) == Z_OK);
+    }
+    // the permit is held again on return
+    assert(z_semaphore_try_acquire(z_loan(sem)) == Z_EBUSY_MUTEX);
+    z_task_join(z_move(task));
+
+    // once bound to the semaphore, the conditional variable can not be used with another semaphore
+    z_owned_semaphore_t other_sem;
+    assert(z_semaphore_init(&other_sem, 1) == Z_OK);
+    assert(z_semaphore_acquire(z_loan(other_sem)) == Z_OK);
+    assert(z_condvar_wait_semaphore(z_loan(cv), z_loan(other_sem)) == Z_EINVAL_MUTEX);
+    assert(z_semaphore_try_acquire(z_loan(other_sem)) == Z_EBUSY_MUTEX);
+
+    assert(z_semaphore_release(z_loan(sem)) == Z_OK);
+    z_drop(z_move(other_sem));
+    z_drop(z_move(cv));
+    z_drop(z_move(sem));
+}
+
+typedef struct waiter_args_t {
+    z_loaned_mutex_t *m;
+    const z_loaned_condvar_t *cv;
+    volatile bool released;
+    volatile unsigned int woken;
+} waiter_args_t;
+
+void *wait_released(void *arg) {
+    waiter_args_t *args = (waiter_args_t *)arg;
+    z_mutex_lock(args->m);
+    while (!args->released) {
+        z_condvar_wait(args->cv, args->m);
+    }
+    args->woken++;
+    z_mutex_unlock(args->m);
+    return NULL;
+}
+
+void condvar_signal_all() {
+    z_owned_mutex_t m;
+    z_owned_condvar_t cv;
+    z_mutex_init(&m);
+    z_condvar_init(&cv);
+    waiter_args_t args;
+    args.m = z_loan_mut(m);
+    args.cv = z_loan(cv);
+    args.released = false;
+    args.woken = 0;
+
+    z_owned_task_t tasks[3];
+    for (int i = 0; i < 3; i++) {
+        z_task_init(&tasks[i], NULL, wait_released, &args);
+    }
+    z_sleep_ms(50);
+    z_mutex_lock(z_loan_mut(m));
+    args.released = true;
+    assert(z_condvar_signal_all(z_loan(cv)) == Z_OK);
+    z_mutex_unlock(z_loan_mut(m));
+    // a single broadcast wakes up all the waiters
+    for (int i = 0; i < 3; i++) {
+        z_task_join(z_move(tasks[i]));
+    }
+    assert(args.woken == 3);
+
+    z_drop(z_move(cv));
+    z_drop(z_move(m));
+}
+
+void *fill_stack(void *arg) {
+    // use a significant part of the requested stack
+    volatile char buf[64 * 1024];
+    memset((char *)buf, 1, sizeof(buf));
+    *(volatile unsigned int *)arg = buf[sizeof(buf) - 1];
+    return NULL;
+}
+
+void task_attr() {
+    z_task_attr_t attr;
+    z_task_attr_default(&attr);
+    assert(attr.stack_size == 0);
+    assert(attr.name == NULL);
+    attr.stack_size = 256 * 1024;
+    attr.name = "zc-test-task";
+
+    volatile unsigned int done = 0;
+    z_owned_task_t task;
+    assert(z_task_init(&task, &attr, fill_stack, (void *)&done) == Z_OK);
+    assert(z_task_join(z_move(task)) == Z_OK);
+    assert(done == 1);
+}
+
+void *sleep_task(void *arg) {
+    z_sleep_ms(50);
+    return NULL;
+}
+
+void task_is_finished() {
+    z_owned_task_t task;
+    assert(z_task_init(&task, NULL, sleep_task, NULL) == Z_OK);
+    assert(!z_task_is_finished(&task));
+    while (!z_task_is_finished(&task)) {
+        z_sleep_ms(5);
+    }
+    assert(z_task_join(z_move(task)) == Z_OK);
+    assert(z_task_is_finished(&task));
+}
+
+int main(int argc, char **argv) {
+    mutex_lock_timeout();
+    rmutex_reentrant();
+    rwlock_read_write();
+    semaphore_try_acquire();
+    condvar_wait_until();
+    condvar_wait_semaphore();
+    condvar_signal_all();
+    task_attr();
+    task_is_finished();
+    return 0;
+}
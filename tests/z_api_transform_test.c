@@ -0,0 +1,206 @@
+//
+// Copyright (c) 2024 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+#include <stdio.h>
+#include <string.h>
+
+#include "zenoh.h"
+
+#undef NDEBUG
+#include <assert.h>
+
+#if defined(Z_FEATURE_UNSTABLE_API)
+// Prefixes the payload with the context string, rejecting "drop" payloads.
+bool prefix_transform(const z_loaned_bytes_t *payload, z_owned_bytes_t *out, void *context) {
+    z_owned_string_t str;
+    z_bytes_to_string(payload, &str);
+    bool keep = strncmp(z_string_data(z_loan(str)), "drop", z_string_len(z_loan(str))) != 0;
+    if (keep) {
+        const char *prefix = (const char *)context;
+        char buf[64];
+        snprintf(buf, sizeof(buf), "%s%.*s", prefix, (int)z_string_len(z_loan(str)), z_string_data(z_loan(str)));
+        z_bytes_copy_from_str(out, buf);
+    }
+    z_drop(z_move(str));
+    return keep;
+}
+
+void assert_payload(const z_loaned_sample_t *sample, const char *expected) {
+    z_owned_string_t str;
+    z_bytes_to_string(z_sample_payload(sample), &str);
+    assert(z_string_len(z_loan(str)) == strlen(expected));
+    assert(strncmp(z_string_data(z_loan(str)), expected, z_string_len(z_loan(str))) == 0);
+    z_drop(z_move(str));
+}
+
+z_result_t publish(const z_loaned_publisher_t *pub, const char *value) {
+    z_owned_bytes_t payload;
+    z_bytes_copy_from_str(&payload, value);
+    return z_publisher_put(pub, z_move(payload), NULL);
+}
+
+void publisher_transform() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/transform/publisher");
+
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback), NULL) == Z_OK);
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+
+    z_publisher_set_transform(z_loan(pub), prefix_transform, (void *)"t:");
+    assert(publish(z_loan(pub), "data") == Z_OK);
+    assert(publish(z_loan(pub), "drop") != Z_OK);
+    // removing the transform publishes payloads as is
+    z_publisher_set_transform(z_loan(pub), NULL, NULL);
+    assert(publish(z_loan(pub), "drop") == Z_OK);
+
+    z_owned_sample_t sample;
+    assert(z_recv(z_loan(handler), &sample) == Z_OK);
+    assert_payload(z_loan(sample), "t:data");
+    z_drop(z_move(sample));
+    assert(z_recv(z_loan(handler), &sample) == Z_OK);
+    assert_payload(z_loan(sample), "drop");
+    z_drop(z_move(sample));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+
+    z_drop(z_move(pub));
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+void publisher_put_batch() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/transform/batch");
+
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback), NULL) == Z_OK);
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+
+    const char *values[] = {"a", "b", "c"};
+    z_moved_bytes_t payloads[4];
+    for (size_t i = 0; i < 3; i++) {
+        z_bytes_copy_from_str(&payloads[i]._this, values[i]);
+    }
+    assert(z_publisher_put_batch(z_loan(pub), payloads, 3) == 3);
+    for (size_t i = 0; i < 3; i++) {
+        assert(!z_internal_check(payloads[i]._this));
+    }
+
+    // publication stops at the payload rejected by the transform, but every payload is consumed
+    const char *rejected[] = {"d", "e", "drop", "f"};
+    for (size_t i = 0; i < 4; i++) {
+        z_bytes_copy_from_str(&payloads[i]._this, rejected[i]);
+    }
+    z_publisher_set_transform(z_loan(pub), prefix_transform, (void *)"t:");
+    assert(z_publisher_put_batch(z_loan(pub), payloads, 4) == 2);
+    for (size_t i = 0; i < 4; i++) {
+        assert(!z_internal_check(payloads[i]._this));
+    }
+    z_publisher_set_transform(z_loan(pub), NULL, NULL);
+
+    const char *expected[] = {"a", "b", "c", "t:d", "t:e"};
+    z_owned_sample_t sample;
+    for (size_t i = 0; i < 5; i++) {
+        assert(z_recv(z_loan(handler), &sample) == Z_OK);
+        assert_payload(z_loan(sample), expected[i]);
+        z_drop(z_move(sample));
+    }
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+
+    z_drop(z_move(pub));
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+
+// Strips the prefix passed as context, rejecting payloads without it.
+bool strip_transform(const z_loaned_bytes_t *payload, z_owned_bytes_t *out, void *context) {
+    const char *prefix = (const char *)context;
+    z_owned_string_t str;
+    z_bytes_to_string(payload, &str);
+    size_t len = z_string_len(z_loan(str));
+    bool keep = len >= strlen(prefix) && strncmp(z_string_data(z_loan(str)), prefix, strlen(prefix)) == 0;
+    if (keep) {
+        z_bytes_copy_from_buf(out, (const uint8_t *)z_string_data(z_loan(str)) + strlen(prefix), len - strlen(prefix));
+    }
+    z_drop(z_move(str));
+    return keep;
+}
+
+void subscriber_transform() {
+    z_owned_config_t config;
+    z_config_default(&config);
+    z_owned_session_t s;
+    assert(z_open(&s, z_move(config), NULL) == Z_OK);
+    z_view_keyexpr_t ke;
+    z_view_keyexpr_from_str(&ke, "test/transform/subscriber");
+
+    z_owned_closure_sample_t callback;
+    z_owned_fifo_handler_sample_t handler;
+    z_fifo_channel_sample_new(&callback, &handler, 16);
+    z_owned_subscriber_t sub;
+    assert(z_declare_subscriber(z_loan(s), &sub, z_loan(ke), z_move(callback), NULL) == Z_OK);
+    assert(z_subscriber_set_transform(z_loan(sub), strip_transform, (void *)"t:") == Z_OK);
+    z_owned_publisher_t pub;
+    assert(z_declare_publisher(z_loan(s), &pub, z_loan(ke), NULL) == Z_OK);
+
+    // the publisher transform output is restored by the subscriber transform
+    z_publisher_set_transform(z_loan(pub), prefix_transform, (void *)"t:");
+    assert(publish(z_loan(pub), "data") == Z_OK);
+    // samples rejected by the subscriber transform are dropped
+    z_publisher_set_transform(z_loan(pub), NULL, NULL);
+    assert(publish(z_loan(pub), "raw") == Z_OK);
+    // removing the transform delivers payloads as is
+    assert(z_subscriber_set_transform(z_loan(sub), NULL, NULL) == Z_OK);
+    assert(publish(z_loan(pub), "t:raw") == Z_OK);
+
+    z_owned_sample_t sample;
+    assert(z_recv(z_loan(handler), &sample) == Z_OK);
+    assert_payload(z_loan(sample), "data");
+    z_drop(z_move(sample));
+    assert(z_recv(z_loan(handler), &sample) == Z_OK);
+    assert_payload(z_loan(sample), "t:raw");
+    z_drop(z_move(sample));
+    assert(z_try_recv(z_loan(handler), &sample) == Z_CHANNEL_NODATA);
+
+    z_drop(z_move(pub));
+    z_drop(z_move(sub));
+    z_drop(z_move(handler));
+    z_drop(z_move(s));
+}
+#endif
+
+int main(int argc, char **argv) {
+#if defined(Z_FEATURE_UNSTABLE_API)
+    publisher_transform();
+    publisher_put_batch();
+    subscriber_transform();
+#endif
+    return 0;
+}